@@ -0,0 +1,639 @@
+//! Commands that run without opening the window
+
+use digolog_logic::*;
+use digolog_module_loader::*;
+use std::{collections::HashMap, process::ExitCode};
+
+const RUN_USAGE: &str = "\
+Usage: digolog run --module <path> --chapter <title> --solution <file> [--seed <number>] [--formal]
+
+Checks a saved solution against the truth table and the sequence test of a chapter.
+If many books have a chapter with the title, use `book/chapter`.
+Chapters with many input wires are checked with random rows. Their seed is printed,
+and `--seed` checks the same rows again.
+With `--formal`, the solution is proven equal to the function of the truth table
+for all the inputs, instead of simulating each row.
+
+Prints the metrics of the solution if it passes.
+Exits with 1 if the solution is wrong, and with 2 if it can not be checked.";
+
+const CHECK_USAGE: &str = "\
+Usage: digolog check --module <path>
+
+Checks that the reference solution of each chapter that has one only uses
+the blocks allowed in the chapter, and passes its truth table and sequence test.
+
+Exits with 1 if a reference solution is wrong.";
+
+const VERILOG_USAGE: &str = "\
+Usage: digolog verilog --module <path> --chapter <title> --solution <file> [--output <file>]
+
+Writes a saved solution as structural Verilog, to the output file or to the standard output.
+The top module is named like the chapter and has a port for each chapter port.
+If the solution has sequential blocks, it also has a `clk` input, and each rising edge is a tick.
+
+Exits with 2 if the solution can not be exported.";
+
+const IMPORT_USAGE: &str = "\
+Usage: digolog import --module <path> --chapter <title> --circ <file> [--circuit <name>] --output <file>
+
+Converts a circuit of a Logisim `.circ` file to a solution of a chapter, and saves it.
+The main circuit is converted, unless `--circuit` names another one.
+The pins of the circuit become the chapter ports with the same label, or the same width.
+
+Prints the components that could not be converted.
+Exits with 1 if some were left out, and with 2 if the file can not be converted.";
+
+const SCHEMATIC_USAGE: &str = "\
+Usage: digolog schematic --module <path> --chapter <title> --solution <file> --format <dot|svg> [--output <file>]
+
+Draws a saved solution, to the output file or to the standard output.
+`dot` writes a Graphviz graph, to be laid out with `dot -Tsvg`.
+`svg` writes an image with the blocks where they are placed in the solution.
+
+Exits with 2 if the solution can not be drawn.";
+
+struct RunArgs {
+    module: String,
+    chapter: String,
+    solution: String,
+    seed: Option<u64>,
+    formal: bool,
+}
+
+/// `digolog run`: verifies a solution, for the CI of the module content
+pub fn run(args: &[String]) -> ExitCode {
+    let args = match RunArgs::parse(args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{RUN_USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let Some(module) = load_module(&args.module) else {
+        return ExitCode::from(2);
+    };
+    let Some(chapter) = module.find_chapter(&args.chapter) else {
+        eprintln!("error: the module has no chapter {:?}", args.chapter);
+        return ExitCode::from(2);
+    };
+    let Some(solution) = ChapterSolution::load(&args.solution, ChapterCompletionStatus::InProgress)
+    else {
+        eprintln!("error: can not read the solution {:?}", args.solution);
+        return ExitCode::from(2);
+    };
+
+    for block in &solution.blocks {
+        let name = &block.shape.description.name;
+        if module
+            .blocks
+            .get(name)
+            .and_then(|d| d.logic.as_ref())
+            .is_none()
+        {
+            eprintln!("error: the block {name:?} does not exist or it can not be simulated");
+            return ExitCode::from(2);
+        }
+    }
+
+    let title = &chapter.id.title;
+    if chapter.truth_table.is_none() && chapter.test.is_none() {
+        eprintln!("error: chapter {title:?} has no truth table or sequence test");
+        return ExitCode::from(2);
+    }
+    let mut verifier = ChapterVerifier::new(chapter, &solution, &module.blocks);
+    verifier.seed = args.seed;
+    let mut passed = true;
+
+    if chapter.truth_table.is_some() && args.formal {
+        match verifier.prove_equivalence() {
+            Ok(Equivalence::Proven) => println!("chapter {title:?}: proven equivalent"),
+            Ok(Equivalence::Counterexample(mismatch)) => {
+                println!("chapter {title:?}: FAILED, counterexample found");
+                print_mismatch(chapter, &mismatch);
+                passed = false;
+            }
+            Err(error) => {
+                eprintln!("error: chapter {title:?}: {}", formal_error_message(&error));
+                return ExitCode::from(2);
+            }
+        }
+    } else if chapter.truth_table.is_some() {
+        let report = match verifier.verify_truth_table() {
+            Ok(report) => report,
+            Err(error) => {
+                eprintln!(
+                    "error: chapter {title:?}: {}",
+                    verify_error_message(chapter, &error)
+                );
+                return ExitCode::from(2);
+            }
+        };
+        let seed = match report.seed {
+            Some(seed) => format!(", random seed {seed}"),
+            None => String::new(),
+        };
+        match &report.mismatch {
+            None => println!(
+                "chapter {title:?}: passed ({} rows checked{seed})",
+                report.checked_rows
+            ),
+            Some(mismatch) => {
+                println!(
+                    "chapter {title:?}: FAILED on row {}{seed}",
+                    report.checked_rows
+                );
+                print_mismatch(chapter, mismatch);
+                passed = false;
+            }
+        }
+    }
+
+    if chapter.test.is_some() {
+        let report = match verifier.verify_sequence_test() {
+            Ok(report) => report,
+            Err(error) => {
+                eprintln!(
+                    "error: chapter {title:?}: {}",
+                    verify_error_message(chapter, &error)
+                );
+                return ExitCode::from(2);
+            }
+        };
+        match &report.mismatch {
+            None => println!(
+                "chapter {title:?}: sequence test passed ({} steps checked)",
+                report.checked_steps
+            ),
+            Some(mismatch) => {
+                println!(
+                    "chapter {title:?}: sequence test FAILED on step {}",
+                    report.checked_steps
+                );
+                print_mismatch(chapter, mismatch);
+                passed = false;
+            }
+        }
+    }
+
+    if !passed {
+        return ExitCode::FAILURE;
+    }
+    let metrics = solution_metrics(chapter, &solution, &module.blocks);
+    let counts: Vec<String> = (metrics.block_counts.iter())
+        .map(|(name, count)| format!("{count} {name}"))
+        .collect();
+    let critical_path = match metrics.critical_path {
+        Some(blocks) => blocks.to_string(),
+        None => "unbounded, the solution has a combinational loop".into(),
+    };
+    println!(
+        "  blocks:        {} ({})",
+        metrics.blocks(),
+        counts.join(", ")
+    );
+    println!("  cost:          {}", metrics.cost);
+    println!("  critical path: {critical_path}");
+    ExitCode::SUCCESS
+}
+
+/// `digolog check`: verifies the reference solutions of a module
+pub fn check(args: &[String]) -> ExitCode {
+    let path = match Flags::parse(args, &["--module"], &[]).and_then(|mut f| f.required("--module"))
+    {
+        Ok(path) => path,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{CHECK_USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let Some(module) = load_module(&path) else {
+        return ExitCode::from(2);
+    };
+
+    let reports = check_reference_solutions(&module);
+    if reports.is_empty() {
+        println!("the module has no reference solutions");
+    }
+    let mut failed = false;
+    for ReferenceSolutionReport { chapter, result } in reports {
+        let title = &chapter.id.title;
+        let Err(error) = result else {
+            println!("chapter {title:?}: passed");
+            continue;
+        };
+        failed = true;
+        match error {
+            ReferenceSolutionError::NothingToCheck => println!(
+                "chapter {title:?}: FAILED, it has no truth table or sequence test to check the solution"
+            ),
+            ReferenceSolutionError::DanglingWire(Wire { source, sink }) => println!(
+                "chapter {title:?}: FAILED, the wire from {source:?} to {sink:?} has an end that does not exist"
+            ),
+            ReferenceSolutionError::BlockNotAllowed { block, desc } => {
+                println!("chapter {title:?}: FAILED, block {block} {:?} is not allowed", desc.name)
+            }
+            ReferenceSolutionError::MissingLogic { block, desc } => println!(
+                "chapter {title:?}: FAILED, block {block} {:?} does not exist or it can not be simulated",
+                desc.name
+            ),
+            ReferenceSolutionError::Verify(error) => println!(
+                "chapter {title:?}: FAILED, {}",
+                verify_error_message(chapter, &error)
+            ),
+            ReferenceSolutionError::Mismatch(mismatch) => {
+                println!("chapter {title:?}: FAILED");
+                print_mismatch(chapter, &mismatch);
+            }
+            ReferenceSolutionError::TestMismatch { step, mismatch } => {
+                println!("chapter {title:?}: FAILED on step {} of the sequence test", step + 1);
+                print_mismatch(chapter, &mismatch);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+struct VerilogArgs {
+    module: String,
+    chapter: String,
+    solution: String,
+    output: Option<String>,
+}
+
+/// `digolog verilog`: exports a solution to structural Verilog
+pub fn verilog(args: &[String]) -> ExitCode {
+    let args = match VerilogArgs::parse(args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{VERILOG_USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let Some(module) = load_module(&args.module) else {
+        return ExitCode::from(2);
+    };
+    let Some(chapter) = module.find_chapter(&args.chapter) else {
+        eprintln!("error: the module has no chapter {:?}", args.chapter);
+        return ExitCode::from(2);
+    };
+    let Some(solution) = ChapterSolution::load(&args.solution, ChapterCompletionStatus::InProgress)
+    else {
+        eprintln!("error: can not read the solution {:?}", args.solution);
+        return ExitCode::from(2);
+    };
+
+    let verilog = match export_verilog(chapter, &solution, &module.blocks) {
+        Ok(verilog) => verilog,
+        Err(error) => {
+            eprintln!("error: {}", verilog_error_message(&error));
+            return ExitCode::from(2);
+        }
+    };
+    match &args.output {
+        None => print!("{verilog}"),
+        Some(path) => {
+            if let Err(error) = std::fs::write(path, verilog) {
+                eprintln!("error: can not write {path:?}: {error}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+struct SchematicArgs {
+    module: String,
+    chapter: String,
+    solution: String,
+    format: SchematicFormat,
+    output: Option<String>,
+}
+
+enum SchematicFormat {
+    Dot,
+    Svg,
+}
+
+/// `digolog schematic`: draws a solution for the course notes
+pub fn schematic(args: &[String]) -> ExitCode {
+    let args = match SchematicArgs::parse(args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{SCHEMATIC_USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let Some(module) = load_module(&args.module) else {
+        return ExitCode::from(2);
+    };
+    let Some(chapter) = module.find_chapter(&args.chapter) else {
+        eprintln!("error: the module has no chapter {:?}", args.chapter);
+        return ExitCode::from(2);
+    };
+    let Some(solution) = ChapterSolution::load(&args.solution, ChapterCompletionStatus::InProgress)
+    else {
+        eprintln!("error: can not read the solution {:?}", args.solution);
+        return ExitCode::from(2);
+    };
+
+    let schematic = match args.format {
+        SchematicFormat::Dot => export_dot(chapter, &solution, &module.blocks),
+        SchematicFormat::Svg => export_svg(chapter, &solution, &module.blocks),
+    };
+    match &args.output {
+        None => print!("{schematic}"),
+        Some(path) => {
+            if let Err(error) = std::fs::write(path, schematic) {
+                eprintln!("error: can not write {path:?}: {error}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+struct ImportArgs {
+    module: String,
+    chapter: String,
+    circ: String,
+    circuit: Option<String>,
+    output: String,
+}
+
+/// `digolog import`: converts a Logisim circuit to a solution
+pub fn import(args: &[String]) -> ExitCode {
+    let args = match ImportArgs::parse(args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{IMPORT_USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let Some(module) = load_module(&args.module) else {
+        return ExitCode::from(2);
+    };
+    let Some(chapter) = module.find_chapter(&args.chapter) else {
+        eprintln!("error: the module has no chapter {:?}", args.chapter);
+        return ExitCode::from(2);
+    };
+    let xml = match std::fs::read_to_string(&args.circ) {
+        Ok(xml) => xml,
+        Err(error) => {
+            eprintln!("error: can not read {:?}: {error}", args.circ);
+            return ExitCode::from(2);
+        }
+    };
+
+    let import = match import_logisim(&xml, args.circuit.as_deref(), chapter, &module.blocks) {
+        Ok(import) => import,
+        Err(LogisimError::Xml(error)) => {
+            eprintln!("error: {:?} is not a Logisim file: {error}", args.circ);
+            return ExitCode::from(2);
+        }
+        Err(LogisimError::NoCircuit(name)) => {
+            eprintln!("error: {:?} has no circuit {name:?}", args.circ);
+            return ExitCode::from(2);
+        }
+    };
+    if let Err(error) = import.solution.save(&args.output) {
+        eprintln!("error: can not write {:?}: {error}", args.output);
+        return ExitCode::from(2);
+    }
+
+    println!(
+        "imported {} blocks and {} wires",
+        import.solution.blocks.len(),
+        import.solution.wires.len()
+    );
+    for untranslated in &import.untranslated {
+        let Untranslated {
+            component,
+            loc,
+            reason,
+        } = untranslated;
+        let reason = match reason {
+            UntranslatedReason::UnknownComponent => "there is no similar block".into(),
+            UntranslatedReason::Subcircuit => "subcircuits are not imported".into(),
+            UntranslatedReason::MissingBlock(name) => {
+                format!("the module has no block {name:?}")
+            }
+            UntranslatedReason::UnsupportedAttribute { name, value } => {
+                format!("{name} = {value:?} is not supported")
+            }
+            UntranslatedReason::UnsupportedPin(pin) => {
+                format!("the {pin} pin is connected, but the block has no such pin")
+            }
+            UntranslatedReason::NoChapterPort => {
+                "the chapter has no port with its label and width".into()
+            }
+            UntranslatedReason::ManyDrivers => "it is driven by more than one output".into(),
+        };
+        let [x, y] = <[i32; 2]>::from(*loc);
+        println!("  left out {component} at ({x}, {y}): {reason}");
+    }
+
+    match import.untranslated.is_empty() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// The `--flag value` arguments of a command, and the `--switch` ones without value
+struct Flags {
+    values: HashMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Flags {
+    /// Fails on a flag that is not in `flags` or `switches`, or on a flag without value
+    fn parse(args: &[String], flags: &[&str], switches: &[&str]) -> Result<Self, String> {
+        let (mut values, mut found) = (HashMap::new(), Vec::new());
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if switches.contains(&arg.as_str()) {
+                found.push(arg.clone());
+            } else if flags.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+                values.insert(arg.clone(), value.clone());
+            } else {
+                return Err(format!("unexpected argument {arg:?}"));
+            }
+        }
+        Ok(Self {
+            values,
+            switches: found,
+        })
+    }
+
+    fn optional(&mut self, flag: &str) -> Option<String> {
+        self.values.remove(flag)
+    }
+
+    fn required(&mut self, flag: &str) -> Result<String, String> {
+        self.optional(flag)
+            .ok_or_else(|| format!("{flag} is missing"))
+    }
+
+    fn switch(&self, switch: &str) -> bool {
+        self.switches.iter().any(|found| found == switch)
+    }
+}
+
+impl SchematicArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let flags = [
+            "--module",
+            "--chapter",
+            "--solution",
+            "--format",
+            "--output",
+        ];
+        let mut flags = Flags::parse(args, &flags, &[])?;
+        let format = match flags.required("--format")?.as_str() {
+            "dot" => SchematicFormat::Dot,
+            "svg" => SchematicFormat::Svg,
+            format => return Err(format!("unknown format {format:?}")),
+        };
+        Ok(Self {
+            module: flags.required("--module")?,
+            chapter: flags.required("--chapter")?,
+            solution: flags.required("--solution")?,
+            format,
+            output: flags.optional("--output"),
+        })
+    }
+}
+
+impl ImportArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let flags = ["--module", "--chapter", "--circ", "--circuit", "--output"];
+        let mut flags = Flags::parse(args, &flags, &[])?;
+        Ok(Self {
+            module: flags.required("--module")?,
+            chapter: flags.required("--chapter")?,
+            circ: flags.required("--circ")?,
+            circuit: flags.optional("--circuit"),
+            output: flags.required("--output")?,
+        })
+    }
+}
+
+impl VerilogArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let flags = ["--module", "--chapter", "--solution", "--output"];
+        let mut flags = Flags::parse(args, &flags, &[])?;
+        Ok(Self {
+            module: flags.required("--module")?,
+            chapter: flags.required("--chapter")?,
+            solution: flags.required("--solution")?,
+            output: flags.optional("--output"),
+        })
+    }
+}
+
+impl RunArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let flags = ["--module", "--chapter", "--solution", "--seed"];
+        let mut flags = Flags::parse(args, &flags, &["--formal"])?;
+        Ok(Self {
+            module: flags.required("--module")?,
+            chapter: flags.required("--chapter")?,
+            solution: flags.required("--solution")?,
+            seed: (flags.optional("--seed").map(|seed| seed.parse()))
+                .transpose()
+                .map_err(|_| "--seed needs a number")?,
+            formal: flags.switch("--formal"),
+        })
+    }
+}
+
+/// Loads the module of a command, printing why it can not be loaded
+fn load_module(path: &str) -> Option<Module> {
+    match Module::from_path(path, "local".into()) {
+        Ok(module) => Some(module),
+        Err(error) => {
+            eprintln!("error: can not load the module {path:?}: {error}");
+            None
+        }
+    }
+}
+
+fn print_mismatch(chapter: &Chapter, mismatch: &Mismatch) {
+    let outputs: Vec<Option<u64>> = mismatch.outputs.iter().copied().map(Some).collect();
+    let inputs: Vec<Option<u64>> = mismatch.inputs.iter().copied().map(Some).collect();
+    println!("  inputs:   {}", format_ports(&chapter.inputs, &inputs));
+    println!(
+        "  expected: {}",
+        format_ports(&chapter.outputs, &mismatch.expected)
+    );
+    println!("  got:      {}", format_ports(&chapter.outputs, &outputs));
+}
+
+fn formal_error_message(error: &FormalError) -> String {
+    match error {
+        FormalError::NoFunction => "its truth table is not a function".into(),
+        FormalError::UnknownFunction(function) => {
+            format!("the function {function:?} can not be used in proofs")
+        }
+        FormalError::WrongPorts { inputs, outputs } => {
+            format!("its function needs {inputs} input ports and {outputs} output ports")
+        }
+        FormalError::UnsupportedBlock { block } => {
+            format!("the block {block} is sequential or has too many inputs to be proven")
+        }
+        FormalError::CombinationalLoop => "the solution has a combinational loop".into(),
+        FormalError::TooLarge => "the solution is too large to be proven".into(),
+    }
+}
+
+fn verilog_error_message(error: &VerilogError) -> String {
+    match error {
+        VerilogError::NoLogic(desc) => {
+            format!("the block {:?} does not exist or it has no logic", desc.name)
+        }
+        VerilogError::TooManyWires { desc, wires } => format!(
+            "the block {:?} has {wires} input and state wires, at most {MAX_TABLE_WIRES} can be exported",
+            desc.name
+        ),
+        VerilogError::Wasm { desc, error } => {
+            format!("the block {:?} has failed: {error}", desc.name)
+        }
+    }
+}
+
+fn verify_error_message(chapter: &Chapter, error: &VerifyError) -> String {
+    match error {
+        VerifyError::NoTruthTable => "it has no truth table".into(),
+        VerifyError::NoSequenceTest => "it has no sequence test".into(),
+        VerifyError::UnknownFunction(function) => {
+            format!("its truth table uses the unknown function {function:?}")
+        }
+        VerifyError::NotSettled { inputs, unsettled } => {
+            let inputs: Vec<Option<u64>> = inputs.iter().copied().map(Some).collect();
+            format!(
+                "the signals do not settle with {}, the blocks {:?} keep changing",
+                format_ports(&chapter.inputs, &inputs),
+                unsettled.blocks,
+            )
+        }
+    }
+}
+
+/// `name = value` for each port. A `None` value is not checked.
+fn format_ports(ports: &[BlockCable], values: &[Option<u64>]) -> String {
+    let values = ports.iter().zip(values).map(|(port, value)| match value {
+        Some(value) => format!("{} = {value}", port.lable),
+        None => format!("{} = -", port.lable),
+    });
+    values.collect::<Vec<_>>().join(", ")
+}
@@ -1,7 +1,21 @@
+mod cli;
+
 use digolog_window::*;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("run") => return cli::run(&args[1..]),
+        Some("check") => return cli::check(&args[1..]),
+        Some("verilog") => return cli::verilog(&args[1..]),
+        Some("import") => return cli::import(&args[1..]),
+        Some("schematic") => return cli::schematic(&args[1..]),
+        _ => {}
+    }
 
-fn main() {
     run_app(Digolog::setup());
+    ExitCode::SUCCESS
 }
 
 struct Digolog;
@@ -0,0 +1,94 @@
+//! The commands of the binary, on the modules shipped with the game
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn fundamentals() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../builtin_modules/Fundamentals")
+}
+
+fn digolog(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_digolog"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn run_passes_a_reference_solution() {
+    let module = fundamentals();
+    let solution = module.join("solutions/Half Adder.txt");
+    let output = digolog(&[
+        "run",
+        "--module",
+        module.to_str().unwrap(),
+        "--chapter",
+        "Arithmetic/Half Adder",
+        "--solution",
+        solution.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = stdout(&output);
+    assert!(stdout.contains("chapter \"Half Adder\": passed (4 rows checked)"));
+    assert!(stdout.contains("blocks:        2 ("));
+}
+
+#[test]
+fn run_fails_a_wrong_solution() {
+    let module = fundamentals();
+    let reference = std::fs::read_to_string(module.join("solutions/Half Adder.txt")).unwrap();
+    let solution = Path::new(env!("CARGO_TARGET_TMPDIR")).join("or_half_adder.txt");
+    std::fs::write(&solution, reference.replace("XOr(", "Or(")).unwrap();
+
+    let output = digolog(&[
+        "run",
+        "--module",
+        module.to_str().unwrap(),
+        "--chapter",
+        "Half Adder",
+        "--solution",
+        solution.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert!(stdout(&output).contains("chapter \"Half Adder\": FAILED on row 4"));
+}
+
+#[test]
+fn check_passes_the_shipped_module() {
+    let module = fundamentals();
+    let output = digolog(&["check", "--module", module.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).contains("chapter \"Half Adder\": passed"));
+}
+
+#[test]
+fn commands_report_a_module_that_can_not_be_loaded() {
+    let module = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_module");
+    let output = digolog(&["check", "--module", module.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("error: can not load the module"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn commands_report_invalid_arguments() {
+    let output = digolog(&["run", "--module"]);
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("error: --module needs a value"), "{stderr}");
+
+    let output = digolog(&["verilog", "--module", "m", "--chapter", "c"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("error: --solution is missing"), "{stderr}");
+
+    let output = digolog(&["schematic", "--format", "png"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("error: unknown format \"png\""), "{stderr}");
+}
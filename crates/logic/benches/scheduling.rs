@@ -0,0 +1,169 @@
+//! Compares the runner scheduling modes on large generated circuits.
+//!
+//! Run with `cargo bench -p digolog_logic --bench scheduling`
+
+#![feature(test)]
+
+extern crate test;
+
+use digolog_logic::*;
+use digolog_math::*;
+use digolog_module_loader::*;
+use std::sync::Arc;
+use test::Bencher;
+
+/// Wires per layer of the generated circuit
+const WIDTH: usize = 32;
+/// Layers of gates of the generated circuit
+const DEPTH: usize = 128;
+
+fn and(input: BlockInput, mut output: BlockOutputMut) {
+    output[0] = input[0] & input[1];
+}
+
+fn xor(input: BlockInput, mut output: BlockOutputMut) {
+    output[0] = input[0] ^ input[1];
+}
+
+fn block_desc(name: &str, logic: fn(BlockInput, BlockOutputMut)) -> (String, Arc<BlockDesc>) {
+    let desc = BlockDesc {
+        id: BlockDescId { name: name.into() },
+        lable: name.into(),
+        group: "Boolean".into(),
+        color: "#00f".into(),
+        inputs: vec![],
+        outputs: vec![],
+        logic: Some(BlockLogic::Builtin(logic)),
+        cost: 1,
+        constants: Default::default(),
+    };
+    (name.into(), Arc::new(desc))
+}
+
+fn cable(lable: &str) -> BlockCable {
+    BlockCable {
+        lable: lable.into(),
+        wires: 1,
+    }
+}
+
+/// Layers of 2-input gates where each gate reads two pseudo-random gates of the previous layer
+fn generate_circuit() -> (Chapter, ChapterSolution, ModuleBlocks) {
+    let blocks: ModuleBlocks = [block_desc("And", and), block_desc("XOr", xor)].into();
+
+    let chapter = Chapter {
+        id: ChapterId {
+            book_id: BookId {
+                module_id: ModuleId {
+                    name: "Bench".into(),
+                    namespace: "bench".into(),
+                },
+                title: "Bench".into(),
+            },
+            title: "Generated".into(),
+        },
+        allowed_blocks: vec![],
+        inputs: (0..WIDTH).map(|_| cable("in")).collect(),
+        outputs: (0..WIDTH).map(|_| cable("out")).collect(),
+        truth_table: None,
+        test: None,
+        reference_solution: None,
+        completion_status: ChapterCompletionStatus::NotStarted,
+        best_metrics: None,
+    };
+
+    let mut solution = chapter.new_solution();
+    let mut seed = 0x2545_f491_u32;
+    let mut random = move |max: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as usize % max
+    };
+
+    for layer in 0..DEPTH {
+        for x in 0..WIDTH {
+            let name = if random(2) == 0 { "And" } else { "XOr" };
+            let block = solution.blocks.len();
+            solution.blocks.push(Block {
+                shape: BlockShape {
+                    description: BlockDescId { name: name.into() },
+                    lable: name.into(),
+                    inputs: vec![cable("a"), cable("b")],
+                    outputs: vec![cable("r")],
+                    layout: PinLayout::default(),
+                },
+                pos: Vec2::new(x as i32 * 4, layer as i32 * 4),
+                orientation: Orientation::default(),
+            });
+
+            for pin in 0..2 {
+                let source = match layer {
+                    0 => PinRef::Chapter {
+                        port: random(WIDTH),
+                    },
+                    _ => PinRef::Block {
+                        block: (layer - 1) * WIDTH + random(WIDTH),
+                        pin: 0,
+                    },
+                };
+                let sink = PinRef::Block { block, pin };
+                solution.wires.push(Wire { source, sink });
+            }
+        }
+    }
+
+    for port in 0..WIDTH {
+        solution.wires.push(Wire {
+            source: PinRef::Block {
+                block: (DEPTH - 1) * WIDTH + port,
+                pin: 0,
+            },
+            sink: PinRef::Chapter { port },
+        });
+    }
+
+    (chapter, solution, blocks)
+}
+
+/// Toggles one input and waits for the circuit to settle
+fn bench_toggle_input(b: &mut Bencher, scheduling: Scheduling) {
+    let (chapter, solution, blocks) = generate_circuit();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    let mut reference = ChapterRunner::new(&chapter, &solution, &blocks);
+    runner.set_scheduling(scheduling);
+    reference.set_scheduling(Scheduling::FullSweep);
+
+    for port in 0..WIDTH {
+        runner.set_input(port, &[port as u8 & 1]);
+        reference.set_input(port, &[port as u8 & 1]);
+    }
+    runner.settle().unwrap();
+    reference.settle().unwrap();
+    for port in 0..WIDTH {
+        assert_eq!(runner.output(port), reference.output(port));
+    }
+
+    let mut value = 0;
+    b.iter(|| {
+        value ^= 1;
+        runner.set_input(0, &[value]);
+        runner.settle().unwrap();
+        test::black_box(runner.output(0)[0])
+    });
+}
+
+#[bench]
+fn full_sweep(b: &mut Bencher) {
+    bench_toggle_input(b, Scheduling::FullSweep);
+}
+
+#[bench]
+fn event_driven(b: &mut Bencher) {
+    bench_toggle_input(b, Scheduling::EventDriven);
+}
+
+#[bench]
+fn levelized(b: &mut Bencher) {
+    bench_toggle_input(b, Scheduling::Levelized);
+}
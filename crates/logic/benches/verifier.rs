@@ -0,0 +1,195 @@
+//! Compares the scalar and the bit-parallel truth table verification.
+//!
+//! Run with `cargo bench -p digolog_logic --bench verifier`
+
+#![feature(test)]
+
+extern crate test;
+
+use digolog_logic::*;
+use digolog_math::*;
+use digolog_module_loader::*;
+use std::sync::Arc;
+use test::Bencher;
+
+/// Wires of each operand of the adder, as in the `Addition` chapter
+const BITS: u8 = 4;
+
+/// Splits a cable into one cable for each wire
+fn split(input: BlockInput, mut output: BlockOutputMut) {
+    for (i, wire) in output.iter_mut().enumerate() {
+        *wire = input[i / 8] >> (i % 8) & 1;
+    }
+}
+
+/// Joins one cable for each wire into a single cable
+fn join(input: BlockInput, mut output: BlockOutputMut) {
+    output.fill(0);
+    for (i, wire) in input.iter().enumerate() {
+        output[i / 8] |= (wire & 1) << (i % 8);
+    }
+}
+
+fn block_desc(name: &str, logic: BlockLogic) -> (String, Arc<BlockDesc>) {
+    let desc = BlockDesc {
+        id: BlockDescId { name: name.into() },
+        lable: name.into(),
+        group: "Bench".into(),
+        color: "#00f".into(),
+        inputs: vec![],
+        outputs: vec![],
+        logic: Some(logic),
+        cost: 1,
+        constants: Default::default(),
+    };
+    (name.into(), Arc::new(desc))
+}
+
+fn cable(lable: &str, wires: u8) -> BlockCable {
+    BlockCable {
+        lable: lable.into(),
+        wires,
+    }
+}
+
+fn block(name: &str, inputs: Vec<BlockCable>, outputs: Vec<BlockCable>) -> Block {
+    Block {
+        shape: BlockShape {
+            description: BlockDescId { name: name.into() },
+            lable: name.into(),
+            inputs,
+            outputs,
+            layout: PinLayout::default(),
+        },
+        pos: Vec2::new(0, 0),
+        orientation: Orientation::default(),
+    }
+}
+
+/// A ripple carry adder made of gates
+fn generate_adder() -> (Chapter, ChapterSolution, ModuleBlocks) {
+    let blocks: ModuleBlocks = [
+        block_desc("And", BlockLogic::Gate(Gate::And)),
+        block_desc("Or", BlockLogic::Gate(Gate::Or)),
+        block_desc("XOr", BlockLogic::Gate(Gate::XOr)),
+        block_desc("Split", BlockLogic::Builtin(split)),
+        block_desc("Join", BlockLogic::Builtin(join)),
+    ]
+    .into();
+
+    let chapter = Chapter {
+        id: ChapterId {
+            book_id: BookId {
+                module_id: ModuleId {
+                    name: "Bench".into(),
+                    namespace: "bench".into(),
+                },
+                title: "Arithmetic".into(),
+            },
+            title: "Addition".into(),
+        },
+        allowed_blocks: vec![],
+        inputs: vec![cable("a", BITS), cable("b", BITS)],
+        outputs: vec![cable("sum", BITS + 1)],
+        truth_table: Some(TruthTable {
+            format: "$a + $b = $sum".into(),
+            rows: TruthTableRows::Function("Adder".into()),
+            max_exhaustive_wires: None,
+            random_rows: None,
+        }),
+        test: None,
+        reference_solution: None,
+        completion_status: ChapterCompletionStatus::NotStarted,
+        best_metrics: None,
+    };
+
+    let mut solution = chapter.new_solution();
+    let pin = |block, pin| PinRef::Block { block, pin };
+    let wire = |source, sink| Wire { source, sink };
+    let bits = || (0..BITS).map(|_| cable("", 1)).collect::<Vec<_>>();
+    let gate = |name| block(name, vec![cable("", 1), cable("", 1)], vec![cable("", 1)]);
+
+    let (split_a, split_b, join_sum) = (0, 1, 2);
+    solution
+        .blocks
+        .push(block("Split", vec![cable("", BITS)], bits()));
+    solution
+        .blocks
+        .push(block("Split", vec![cable("", BITS)], bits()));
+    solution.blocks.push(block(
+        "Join",
+        (0..=BITS).map(|_| cable("", 1)).collect(),
+        vec![cable("", BITS + 1)],
+    ));
+    solution
+        .wires
+        .push(wire(PinRef::Chapter { port: 0 }, pin(split_a, 0)));
+    solution
+        .wires
+        .push(wire(PinRef::Chapter { port: 1 }, pin(split_b, 0)));
+    solution
+        .wires
+        .push(wire(pin(join_sum, 0), PinRef::Chapter { port: 0 }));
+
+    let mut carry = None;
+    for bit in 0..BITS as usize {
+        let first = solution.blocks.len();
+        let (half, sum, generate, propagate, carry_out) =
+            (first, first + 1, first + 2, first + 3, first + 4);
+        for name in ["XOr", "XOr", "And", "And", "Or"] {
+            solution.blocks.push(gate(name));
+        }
+
+        for block in [half, generate] {
+            solution.wires.push(wire(pin(split_a, bit), pin(block, 0)));
+            solution.wires.push(wire(pin(split_b, bit), pin(block, 1)));
+        }
+        solution.wires.push(wire(pin(half, 0), pin(sum, 0)));
+        solution.wires.push(wire(pin(half, 0), pin(propagate, 0)));
+        if let Some(carry) = carry {
+            solution.wires.push(wire(carry, pin(sum, 1)));
+            solution.wires.push(wire(carry, pin(propagate, 1)));
+        }
+        solution
+            .wires
+            .push(wire(pin(generate, 0), pin(carry_out, 0)));
+        solution
+            .wires
+            .push(wire(pin(propagate, 0), pin(carry_out, 1)));
+        solution.wires.push(wire(pin(sum, 0), pin(join_sum, bit)));
+        carry = Some(pin(carry_out, 0));
+    }
+    solution
+        .wires
+        .push(wire(carry.unwrap(), pin(join_sum, BITS as usize)));
+
+    (chapter, solution, blocks)
+}
+
+fn bench_verify(b: &mut Bencher, bit_parallel: bool) {
+    let (chapter, mut solution, blocks) = generate_adder();
+    let mut verifier = ChapterVerifier::new(&chapter, &solution, &blocks);
+    verifier.bit_parallel = bit_parallel;
+
+    let report = verifier.verify_truth_table().unwrap();
+    assert!(report.passed());
+    assert_eq!(report.checked_rows, 1 << (2 * BITS));
+
+    // A broken carry has to be found by both modes
+    solution.blocks[6].shape.description.name = "Or".into();
+    let mut broken_verifier = ChapterVerifier::new(&chapter, &solution, &blocks);
+    broken_verifier.bit_parallel = bit_parallel;
+    assert!(!broken_verifier.verify_truth_table().unwrap().passed());
+
+    b.iter(|| test::black_box(verifier.verify_truth_table().unwrap()));
+}
+
+#[bench]
+fn scalar(b: &mut Bencher) {
+    bench_verify(b, false);
+}
+
+#[bench]
+fn bit_parallel(b: &mut Bencher) {
+    bench_verify(b, true);
+}
@@ -0,0 +1,229 @@
+use crate::runner::flatten;
+use digolog_module_loader::*;
+
+/// Blocks that depend on themselves without a sequential block in between.
+/// Its signals may never settle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombinationalLoop {
+    /// Index in `solution.blocks` of the blocks of the loop
+    pub blocks: Vec<usize>,
+    /// Index in `solution.wires` of the wires that connect the blocks of the loop
+    pub wires: Vec<usize>,
+}
+
+/// Finds the combinational loops of a solution.
+/// The blocks are searched by name in `blocks` to know which ones are sequential.
+///
+/// Loops that share a block are reported as a single loop.
+pub fn find_combinational_loops(
+    solution: &ChapterSolution,
+    blocks: &ModuleBlocks,
+) -> Vec<CombinationalLoop> {
+    // Each output of each block is a node of the graph, the outputs of a block come one after the other
+    let mut first_output = Vec::with_capacity(solution.blocks.len() + 1);
+    first_output.push(0);
+    for block in &solution.blocks {
+        first_output.push(first_output.last().unwrap() + block.shape.outputs.len());
+    }
+    let nodes = *first_output.last().unwrap();
+    let mut node_block = vec![0; nodes];
+    for (block, range) in first_output.windows(2).enumerate() {
+        node_block[range[0]..range[1]].fill(block);
+    }
+
+    // An output depends on the output that drives an input, if it depends on the input.
+    // The outputs of a sequential block do not depend on its inputs, so it breaks any loop.
+    let dependencies: Vec<Vec<Vec<bool>>> = (solution.blocks.iter())
+        .map(|block| output_dependencies(block, blocks))
+        .collect();
+    let mut dependents = vec![Vec::new(); nodes];
+    for (wire_index, wire) in solution.wires.iter().enumerate() {
+        let (
+            PinRef::Block {
+                block: source,
+                pin: output,
+            },
+            PinRef::Block { block: sink, pin },
+        ) = (wire.source, wire.sink)
+        else {
+            continue;
+        };
+        if output >= solution.blocks[source].shape.outputs.len() {
+            continue;
+        }
+        for (sink_output, inputs) in dependencies[sink].iter().enumerate() {
+            if inputs.get(pin).copied().unwrap_or(false) {
+                let dependent = first_output[sink] + sink_output;
+                dependents[first_output[source] + output].push((dependent, wire_index));
+            }
+        }
+    }
+
+    let mut loops: Vec<CombinationalLoop> = Vec::new();
+    for component in strongly_connected_components(&dependents) {
+        let in_component = |node: usize| component.binary_search(&node).is_ok();
+
+        let wires: Vec<usize> = (component.iter())
+            .flat_map(|&node| &dependents[node])
+            .filter(|(dependent, _)| in_component(*dependent))
+            .map(|&(_, wire)| wire)
+            .collect();
+
+        // A single output is only a loop if it depends on itself
+        if wires.is_empty() {
+            continue;
+        }
+
+        let mut found = CombinationalLoop {
+            blocks: component.iter().map(|&node| node_block[node]).collect(),
+            wires,
+        };
+        // The outputs of a block can be in different loops
+        loops.retain(|other| {
+            let shared = (other.blocks.iter()).any(|block| found.blocks.contains(block));
+            if shared {
+                found.blocks.extend(&other.blocks);
+                found.wires.extend(&other.wires);
+            }
+            !shared
+        });
+        found.blocks.sort();
+        found.blocks.dedup();
+        found.wires.sort();
+        found.wires.dedup();
+        loops.push(found);
+    }
+    loops.sort_by(|a, b| a.blocks.cmp(&b.blocks));
+    loops
+}
+
+/// For each output of a block, whether it depends on each input
+/// without a sequential block in between
+fn output_dependencies(block: &Block, blocks: &ModuleBlocks) -> Vec<Vec<bool>> {
+    let (inputs, outputs) = (block.shape.inputs.len(), block.shape.outputs.len());
+    let logic = (blocks.get(&block.shape.description.name)).and_then(|d| d.logic.as_ref());
+    let composite = match logic {
+        Some(BlockLogic::Sequential { .. }) => return vec![vec![false; inputs]; outputs],
+        Some(BlockLogic::Composite(composite)) => composite,
+        _ => return vec![vec![true; inputs]; outputs],
+    };
+
+    // The flat netlist only has blocks whose outputs depend on all their inputs, or on none
+    let netlist = flatten(&composite.blocks, &composite.wires, blocks);
+    let is_sequential = |block: usize| {
+        let name = &netlist.blocks[block].1.shape.description.name;
+        let logic = blocks.get(name).and_then(|d| d.logic.as_ref());
+        matches!(logic, Some(BlockLogic::Sequential { .. }))
+    };
+
+    let mut dependencies = vec![vec![false; inputs]; outputs];
+    for input in 0..inputs {
+        // Blocks reached from the input through combinational blocks
+        let mut reached = vec![false; netlist.blocks.len()];
+        let mut pending = Vec::new();
+        for wire in &netlist.wires {
+            if let (PinRef::Chapter { port }, PinRef::Block { block, .. }) =
+                (wire.source, wire.sink)
+            {
+                if port == input && !reached[block] {
+                    reached[block] = true;
+                    pending.push(block);
+                }
+            }
+        }
+        while let Some(block) = pending.pop() {
+            if is_sequential(block) {
+                continue;
+            }
+            for wire in &netlist.wires {
+                if let (PinRef::Block { block: source, .. }, PinRef::Block { block: sink, .. }) =
+                    (wire.source, wire.sink)
+                {
+                    if source == block && !reached[sink] {
+                        reached[sink] = true;
+                        pending.push(sink);
+                    }
+                }
+            }
+        }
+
+        for wire in &netlist.wires {
+            let PinRef::Chapter { port: output } = wire.sink else {
+                continue;
+            };
+            let depends = match wire.source {
+                PinRef::Chapter { port } => port == input,
+                PinRef::Block { block, .. } => reached[block] && !is_sequential(block),
+            };
+            if let Some(output) = dependencies.get_mut(output) {
+                output[input] |= depends;
+            }
+        }
+    }
+    dependencies
+}
+
+/// Tarjan's algorithm, without recursion so it does not overflow the stack on long chains.
+/// Returns the sorted nodes of each component.
+fn strongly_connected_components(dependents: &[Vec<(usize, usize)>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut index = vec![UNVISITED; dependents.len()];
+    let mut low_link = vec![0; dependents.len()];
+    let mut on_stack = vec![false; dependents.len()];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut components = Vec::new();
+
+    for root in 0..dependents.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        // Each frame is a node and the next dependent to visit
+        let mut call_stack = vec![(root, 0)];
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&mut (node, ref mut next)) = call_stack.last_mut() {
+            if let Some(&(dependent, _)) = dependents[node].get(*next) {
+                *next += 1;
+                if index[dependent] == UNVISITED {
+                    index[dependent] = next_index;
+                    low_link[dependent] = next_index;
+                    next_index += 1;
+                    stack.push(dependent);
+                    on_stack[dependent] = true;
+                    call_stack.push((dependent, 0));
+                } else if on_stack[dependent] {
+                    low_link[node] = low_link[node].min(index[dependent]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+
+            if low_link[node] == index[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
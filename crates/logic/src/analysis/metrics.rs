@@ -0,0 +1,60 @@
+//! Measures of the quality of a solution
+
+use crate::*;
+use digolog_module_loader::*;
+use std::collections::BTreeMap;
+
+/// Measures a solution. The blocks are searched by name in `blocks`.
+///
+/// # Panic
+/// If a wire refers to a missing pin, or a block has no logic.
+pub fn solution_metrics(
+    chapter: &Chapter,
+    solution: &ChapterSolution,
+    blocks: &ModuleBlocks,
+) -> SolutionMetrics {
+    let mut block_counts = BTreeMap::new();
+    for block in &solution.blocks {
+        *block_counts
+            .entry(block.shape.description.name.clone())
+            .or_default() += 1;
+    }
+
+    // The composite blocks are replaced by their blocks in the program
+    let program = ChapterProgram::compile(chapter, solution, blocks);
+    let cost = (program.ops.iter())
+        .map(|op| blocks[&program.descs[op.desc].name].cost as u64)
+        .sum();
+
+    SolutionMetrics {
+        block_counts,
+        cost,
+        critical_path: critical_path(&program),
+    }
+}
+
+/// Number of combinational operations on the longest path of the program,
+/// `None` if it has a combinational loop
+fn critical_path(program: &ChapterProgram) -> Option<u32> {
+    program.levels.as_ref()?;
+
+    // Operations on the longest path that ends on each slot.
+    // The operations are in level order, so their inputs are known before them.
+    let mut depth = vec![0; program.slots.len()];
+    let mut longest = 0;
+    for op in &program.ops {
+        // The outputs of a sequential block do not depend on its inputs
+        let op_depth = match op.state {
+            Some(_) => 0,
+            None => {
+                let inputs = op.inputs.iter().filter_map(|input| input.source);
+                inputs.map(|slot| depth[slot]).max().unwrap_or(0) + 1
+            }
+        };
+        for slot in op.outputs.clone() {
+            depth[slot] = op_depth;
+        }
+        longest = longest.max(op_depth);
+    }
+    Some(longest)
+}
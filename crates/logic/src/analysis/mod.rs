@@ -0,0 +1,5 @@
+mod loops;
+mod metrics;
+
+pub use loops::*;
+pub use metrics::*;
@@ -0,0 +1,109 @@
+//! Undo and redo of the edits of a solution
+
+use super::*;
+use std::collections::VecDeque;
+
+/// An accepted edit, with what is needed to revert it
+#[derive(Debug, Clone)]
+pub(super) enum Edit {
+    Place {
+        index: usize,
+        block: Block,
+    },
+    Move {
+        block: usize,
+        from: Vec2<i32>,
+        to: Vec2<i32>,
+    },
+    Orient {
+        block: usize,
+        from: Orientation,
+        to: Orientation,
+    },
+    Delete(RemovedBlock),
+    Connect {
+        wire: Wire,
+        /// The widths given to the block by the wire
+        reshape: Option<Reshape>,
+    },
+    Disconnect(Wire),
+}
+
+/// A change of the widths of a block
+#[derive(Debug, Clone)]
+pub(super) struct Reshape {
+    pub block: usize,
+    pub before: BlockShape,
+    pub after: BlockShape,
+}
+
+/// The edits that can be undone, and the undone edits that can be redone
+pub(super) struct EditHistory {
+    /// Maximum number of edits kept, the oldest are forgotten
+    capacity: usize,
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    /// The block being dragged, if the last edit is its drag
+    dragging: Option<usize>,
+}
+
+impl EditHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            dragging: None,
+        }
+    }
+
+    /// Records a new edit. The undone edits can not be redone anymore.
+    pub fn push(&mut self, edit: Edit) {
+        self.dragging = None;
+        self.redo.clear();
+        if self.undo.len() == self.capacity {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(edit);
+    }
+
+    /// Records a move of a dragged block, merged with the previous moves of the drag
+    pub fn drag(&mut self, block: usize, from: Vec2<i32>, to: Vec2<i32>) {
+        if self.dragging == Some(block) {
+            if let Some(Edit::Move { to: last, .. }) = self.undo.back_mut() {
+                *last = to;
+                return;
+            }
+        }
+        self.push(Edit::Move { block, from, to });
+        self.dragging = Some(block);
+    }
+
+    pub fn end_drag(&mut self) {
+        self.dragging = None;
+    }
+
+    /// Takes the edit to revert
+    pub fn undo(&mut self) -> Option<Edit> {
+        self.dragging = None;
+        let edit = self.undo.pop_back()?;
+        self.redo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// Takes the edit to apply again
+    pub fn redo(&mut self) -> Option<Edit> {
+        self.dragging = None;
+        let edit = self.redo.pop()?;
+        self.undo.push_back(edit.clone());
+        Some(edit)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
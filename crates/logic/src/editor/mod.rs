@@ -0,0 +1,494 @@
+//! Changes of a solution, checked against the rules of its chapter
+
+mod history;
+
+use crate::*;
+use digolog_math::*;
+use digolog_module_loader::*;
+use history::*;
+use std::fmt;
+
+/// Edits the solution of a chapter.
+///
+/// Every change is checked first: blocks must be in the palette of the chapter,
+/// and wires must go from an output to an input of the same width.
+/// A rejected change leaves the solution as it was.
+///
+/// The accepted changes can be undone and redone. Open one editor for each chapter,
+/// so that each chapter has its own history.
+pub struct SolutionEditor<'a> {
+    book: &'a Book,
+    chapter: &'a Chapter,
+    solution: ChapterSolution,
+    history: EditHistory,
+}
+
+/// Why an edit is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// The block is not in the palette of the chapter or its book,
+    /// or the palette only has it with other template values
+    BlockNotAllowed(BlockDescId),
+    /// The shape does not follow its description, it has a wrong number of cables
+    /// or a cable with a wrong width
+    InvalidShape(BlockDescId),
+    /// The solution has no `blocks[block]`
+    NoSuchBlock(usize),
+    /// The pin is not an output of a block or a chapter input
+    NotASource(PinRef),
+    /// The pin is not an input of a block or a chapter output
+    NotASink(PinRef),
+    /// The wire connects cables with a different number of wires
+    WidthMismatch { source: PinType, sink: PinType },
+    /// Neither end of the wire has a known width
+    UnknownWidths,
+    /// The width of the wire gives two values to a template of `blocks[block]`
+    TemplateConflict {
+        block: usize,
+        conflict: TemplateConflict,
+    },
+    /// The sink is already driven by the wire
+    AlreadyConnected(Wire),
+    /// There is no wire into the sink
+    NotConnected(PinRef),
+}
+
+/// A block taken out of the solution, with the wires that were connected to it
+#[derive(Debug, Clone)]
+pub struct RemovedBlock {
+    pub index: usize,
+    pub block: Block,
+    /// The wires with the indexes from before the removal
+    pub wires: Vec<Wire>,
+}
+
+impl<'a> SolutionEditor<'a> {
+    /// Edits a solution, keeping the last 256 edits to undo
+    pub fn new(book: &'a Book, chapter: &'a Chapter, solution: ChapterSolution) -> Self {
+        Self::with_history_capacity(book, chapter, solution, 256)
+    }
+
+    /// Edits a solution, keeping the last `capacity` edits to undo
+    pub fn with_history_capacity(
+        book: &'a Book,
+        chapter: &'a Chapter,
+        solution: ChapterSolution,
+        capacity: usize,
+    ) -> Self {
+        Self {
+            book,
+            chapter,
+            solution,
+            history: EditHistory::new(capacity),
+        }
+    }
+
+    pub fn chapter(&self) -> &'a Chapter {
+        self.chapter
+    }
+
+    pub fn solution(&self) -> &ChapterSolution {
+        &self.solution
+    }
+
+    pub fn into_solution(self) -> ChapterSolution {
+        self.solution
+    }
+
+    /// Places a new block of the palette. Returns its index.
+    ///
+    /// The widths that depend on templates are not known until the block is connected,
+    /// and the bundles of unknown size have [`DEFAULT_BUNDLE_CABLES`] cables.
+    pub fn place(&mut self, subset: &BlockDescSubset, pos: Vec2<i32>) -> Result<usize, EditError> {
+        let desc = &subset.block_desc;
+        let conflict = |conflict| EditError::TemplateConflict {
+            block: self.solution.blocks.len(),
+            conflict,
+        };
+        let values = subset.new_values().map_err(conflict)?;
+        let shape = desc.shape(&values).map_err(conflict)?;
+        self.place_shape(shape, pos)
+    }
+
+    /// Places a new block with the given shape, if the palette allows it. Returns its index.
+    ///
+    /// The shape must follow its description, and have the template values of the palette.
+    pub fn place_shape(&mut self, shape: BlockShape, pos: Vec2<i32>) -> Result<usize, EditError> {
+        let index = self.solution.blocks.len();
+        if !self.chapter.allows_block(self.book, &shape) {
+            let desc = (self.desc(&shape.description))
+                .ok_or_else(|| EditError::BlockNotAllowed(shape.description.clone()))?;
+            let mut values = (desc.template_values(&shape))
+                .ok_or_else(|| EditError::InvalidShape(shape.description.clone()))?;
+            (desc.resolve(&mut values)).map_err(|conflict| EditError::TemplateConflict {
+                block: index,
+                conflict,
+            })?;
+            return Err(EditError::BlockNotAllowed(shape.description));
+        }
+        let block = Block {
+            shape,
+            pos,
+            orientation: Orientation::default(),
+        };
+        self.solution.blocks.push(block.clone());
+        self.history.push(Edit::Place { index, block });
+        Ok(index)
+    }
+
+    /// Moves a block. Returns its previous position.
+    pub fn move_block(&mut self, block: usize, pos: Vec2<i32>) -> Result<Vec2<i32>, EditError> {
+        let from = self.set_pos(block, pos)?;
+        let to = pos;
+        self.history.push(Edit::Move { block, from, to });
+        Ok(from)
+    }
+
+    /// Moves a block while it is dragged. Returns its previous position.
+    ///
+    /// All the moves of the block until [`Self::end_drag`] or another edit are undone in one step.
+    pub fn drag_block(&mut self, block: usize, pos: Vec2<i32>) -> Result<Vec2<i32>, EditError> {
+        let from = self.set_pos(block, pos)?;
+        self.history.drag(block, from, pos);
+        Ok(from)
+    }
+
+    /// Ends the drag of a block, the next moves are a new step of the history
+    pub fn end_drag(&mut self) {
+        self.history.end_drag();
+    }
+
+    /// Turns or mirrors a block, its wires stay connected. Returns its previous orientation.
+    pub fn orient_block(
+        &mut self,
+        block: usize,
+        orientation: Orientation,
+    ) -> Result<Orientation, EditError> {
+        let placed = (self.solution.blocks.get_mut(block)).ok_or(EditError::NoSuchBlock(block))?;
+        let from = std::mem::replace(&mut placed.orientation, orientation);
+        let to = orientation;
+        self.history.push(Edit::Orient { block, from, to });
+        Ok(from)
+    }
+
+    /// Deletes a block and the wires connected to it.
+    /// The blocks after it move down one index.
+    pub fn delete_block(&mut self, block: usize) -> Result<RemovedBlock, EditError> {
+        if block >= self.solution.blocks.len() {
+            return Err(EditError::NoSuchBlock(block));
+        }
+        let removed = self.remove_block(block);
+        self.history.push(Edit::Delete(removed.clone()));
+        Ok(removed)
+    }
+
+    /// Wires an output cable to an input cable of the same width.
+    ///
+    /// If the width of one end is not known yet, it is taken from the other end,
+    /// and the other pins of the block with the same template get it too.
+    pub fn connect(&mut self, source: PinRef, sink: PinRef) -> Result<(), EditError> {
+        let source_type = self.pin_type(source, true)?;
+        let sink_type = self.pin_type(sink, false)?;
+        if let Some(wire) = self.solution.wires.iter().find(|wire| wire.sink == sink) {
+            return Err(EditError::AlreadyConnected(*wire));
+        }
+
+        let reshape = match (source_type.cable_wires(), sink_type.cable_wires()) {
+            (0, 0) => return Err(EditError::UnknownWidths),
+            (0, wires) => Some(self.infer_width(source, true, wires)?),
+            (wires, 0) => Some(self.infer_width(sink, false, wires)?),
+            (source_wires, sink_wires) if source_wires != sink_wires => {
+                return Err(EditError::WidthMismatch {
+                    source: source_type,
+                    sink: sink_type,
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(reshape) = &reshape {
+            self.solution.blocks[reshape.block].shape = reshape.after.clone();
+        }
+        let wire = Wire { source, sink };
+        self.solution.wires.push(wire);
+        self.history.push(Edit::Connect { wire, reshape });
+        Ok(())
+    }
+
+    /// Removes the wire into an input cable. Returns it.
+    pub fn disconnect(&mut self, sink: PinRef) -> Result<Wire, EditError> {
+        self.sink_cable(sink)?;
+        let wire = self
+            .remove_wire(sink)
+            .ok_or(EditError::NotConnected(sink))?;
+        self.history.push(Edit::Disconnect(wire));
+        Ok(wire)
+    }
+
+    /// Reverts the last edit. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.history.undo() else {
+            return false;
+        };
+        match edit {
+            Edit::Place { index, .. } => {
+                self.remove_block(index);
+            }
+            Edit::Move { block, from, .. } => {
+                self.solution.blocks[block].pos = from;
+            }
+            Edit::Orient { block, from, .. } => {
+                self.solution.blocks[block].orientation = from;
+            }
+            Edit::Delete(removed) => self.insert_block(removed),
+            Edit::Connect { wire, reshape } => {
+                self.remove_wire(wire.sink);
+                if let Some(Reshape { block, before, .. }) = reshape {
+                    self.solution.blocks[block].shape = before;
+                }
+            }
+            Edit::Disconnect(wire) => self.solution.wires.push(wire),
+        }
+        true
+    }
+
+    /// Applies again the last undone edit. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.history.redo() else {
+            return false;
+        };
+        match edit {
+            Edit::Place { index, block } => self.insert_block(RemovedBlock {
+                index,
+                block,
+                wires: vec![],
+            }),
+            Edit::Move { block, to, .. } => {
+                self.solution.blocks[block].pos = to;
+            }
+            Edit::Orient { block, to, .. } => {
+                self.solution.blocks[block].orientation = to;
+            }
+            Edit::Delete(removed) => {
+                self.remove_block(removed.index);
+            }
+            Edit::Connect { wire, reshape } => {
+                if let Some(Reshape { block, after, .. }) = reshape {
+                    self.solution.blocks[block].shape = after;
+                }
+                self.solution.wires.push(wire);
+            }
+            Edit::Disconnect(wire) => {
+                self.remove_wire(wire.sink);
+            }
+        }
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    fn set_pos(&mut self, block: usize, pos: Vec2<i32>) -> Result<Vec2<i32>, EditError> {
+        let placed = (self.solution.blocks.get_mut(block)).ok_or(EditError::NoSuchBlock(block))?;
+        Ok(std::mem::replace(&mut placed.pos, pos))
+    }
+
+    /// Removes `blocks[block]` and its wires, without recording it
+    fn remove_block(&mut self, block: usize) -> RemovedBlock {
+        let touches = |pin: PinRef| matches!(pin, PinRef::Block { block: b, .. } if b == block);
+        let (wires, kept) = (self.solution.wires.iter())
+            .partition(|wire| touches(wire.source) || touches(wire.sink));
+        self.solution.wires = kept;
+
+        let shift = |pin: &mut PinRef| {
+            if let PinRef::Block { block: b, .. } = pin {
+                if *b > block {
+                    *b -= 1;
+                }
+            }
+        };
+        for wire in &mut self.solution.wires {
+            shift(&mut wire.source);
+            shift(&mut wire.sink);
+        }
+
+        RemovedBlock {
+            index: block,
+            block: self.solution.blocks.remove(block),
+            wires,
+        }
+    }
+
+    /// Puts back a removed block at its index, with its wires, without recording it
+    fn insert_block(&mut self, removed: RemovedBlock) {
+        let RemovedBlock {
+            index,
+            block,
+            wires,
+        } = removed;
+        for wire in &mut self.solution.wires {
+            for pin in [&mut wire.source, &mut wire.sink] {
+                if let PinRef::Block { block, .. } = pin {
+                    if *block >= index {
+                        *block += 1;
+                    }
+                }
+            }
+        }
+        self.solution.blocks.insert(index, block);
+        self.solution.wires.extend(wires);
+    }
+
+    /// Removes the wire into `sink`, without recording it
+    fn remove_wire(&mut self, sink: PinRef) -> Option<Wire> {
+        let index = (self.solution.wires.iter()).position(|wire| wire.sink == sink)?;
+        Some(self.solution.wires.remove(index))
+    }
+
+    /// The type of an output of a block or a chapter input if `output`,
+    /// or of an input of a block or a chapter output
+    fn pin_type(&self, pin: PinRef, output: bool) -> Result<PinType, EditError> {
+        let (cable, desc_pin) = match output {
+            true => (self.source_cable(pin)?, self.desc_pin(pin, true)),
+            false => (self.sink_cable(pin)?, self.desc_pin(pin, false)),
+        };
+        match (pin, desc_pin) {
+            (PinRef::Block { block, .. }, Some((desc_pin, values))) => (desc_pin.pin_type)
+                .resolve(&values)
+                .map_err(|conflict| EditError::TemplateConflict { block, conflict }),
+            _ => Ok(PinType::Cable(cable.wires)),
+        }
+    }
+
+    /// The pin of the block description with the cable of a block, and the template values of the block
+    fn desc_pin(&self, pin: PinRef, output: bool) -> Option<(&BlockPinDesc, TemplateValues)> {
+        let PinRef::Block { block, pin } = pin else {
+            return None;
+        };
+        let shape = &self.solution.blocks.get(block)?.shape;
+        let desc = self.desc(&shape.description)?;
+        let values = desc.template_values(shape)?;
+        Some((desc.cable_pin(&values, output, pin)?, values))
+    }
+
+    /// Gives a width to the unknown cable `pin` of a block, and to the cables with the same template.
+    /// Returns the change of the shape of the block.
+    fn infer_width(&self, pin: PinRef, output: bool, wires: u8) -> Result<Reshape, EditError> {
+        let PinRef::Block { block, .. } = pin else {
+            return Err(EditError::UnknownWidths);
+        };
+        let (desc_pin, mut values) =
+            (self.desc_pin(pin, output)).ok_or(EditError::UnknownWidths)?;
+        let name = (desc_pin.pin_type.width_name()).ok_or(EditError::UnknownWidths)?;
+        values.insert(name.into(), wires as i64);
+
+        let before = &self.solution.blocks[block].shape;
+        let desc = self.desc(&before.description).unwrap();
+        let conflict = |conflict| EditError::TemplateConflict { block, conflict };
+        desc.resolve(&mut values).map_err(conflict)?;
+        let resolved = desc.shape(&values).map_err(conflict)?;
+        let mut after = before.clone();
+        for (cables, resolved) in [
+            (&mut after.inputs, &resolved.inputs),
+            (&mut after.outputs, &resolved.outputs),
+        ] {
+            for (cable, resolved) in cables.iter_mut().zip(resolved) {
+                cable.wires = resolved.wires;
+            }
+        }
+        Ok(Reshape {
+            block,
+            before: before.clone(),
+            after,
+        })
+    }
+
+    /// The description of a block of the palette
+    fn desc(&self, id: &BlockDescId) -> Option<&'a BlockDesc> {
+        let mut palette = (self.chapter.allowed_blocks.iter()).chain(&self.book.allowed_blocks);
+        (palette.find(|subset| subset.block_desc.id == *id)).map(|subset| &*subset.block_desc)
+    }
+
+    /// The cable of an output of a block or a chapter input
+    fn source_cable(&self, pin: PinRef) -> Result<&BlockCable, EditError> {
+        match pin {
+            PinRef::Chapter { port } => self.chapter.inputs.get(port),
+            PinRef::Block { block, pin } => self.block(block)?.shape.outputs.get(pin),
+        }
+        .ok_or(EditError::NotASource(pin))
+    }
+
+    /// The cable of an input of a block or a chapter output
+    fn sink_cable(&self, pin: PinRef) -> Result<&BlockCable, EditError> {
+        match pin {
+            PinRef::Chapter { port } => self.chapter.outputs.get(port),
+            PinRef::Block { block, pin } => self.block(block)?.shape.inputs.get(pin),
+        }
+        .ok_or(EditError::NotASink(pin))
+    }
+
+    fn block(&self, block: usize) -> Result<&Block, EditError> {
+        (self.solution.blocks.get(block)).ok_or(EditError::NoSuchBlock(block))
+    }
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pin = |pin: &PinRef| match pin {
+            PinRef::Block { block, pin } => format!("pin {pin} of block {block}"),
+            PinRef::Chapter { port } => format!("chapter port {port}"),
+        };
+        match self {
+            EditError::BlockNotAllowed(desc) => {
+                write!(
+                    f,
+                    "the block {:?} is not allowed in this chapter",
+                    desc.name
+                )
+            }
+            EditError::InvalidShape(desc) => write!(
+                f,
+                "the shape does not have the cables of the block {:?}",
+                desc.name
+            ),
+            EditError::NoSuchBlock(block) => write!(f, "there is no block {block}"),
+            EditError::NotASource(source) => {
+                write!(f, "the {} is not an output", pin(source))
+            }
+            EditError::NotASink(sink) => write!(f, "the {} is not an input", pin(sink)),
+            EditError::WidthMismatch { source, sink } => {
+                write!(f, "a {source} can not be connected to a {sink}")?;
+                for pin_type in [source, sink] {
+                    if let PinType::Bundle {
+                        wires_per_cable, ..
+                    } = pin_type
+                    {
+                        write!(
+                            f,
+                            ", each cable of a {pin_type} has {wires_per_cable} wires"
+                        )?;
+                    }
+                }
+                Ok(())
+            }
+            EditError::UnknownWidths => write!(
+                f,
+                "the widths of both ends are not known, connect one of them to a known width first"
+            ),
+            EditError::TemplateConflict { block, conflict } => write!(
+                f,
+                "the template {} of block {block} would be both {} and {}",
+                conflict.name, conflict.values[0], conflict.values[1]
+            ),
+            EditError::AlreadyConnected(wire) => {
+                write!(f, "the {} is already connected", pin(&wire.sink))
+            }
+            EditError::NotConnected(sink) => write!(f, "the {} is not connected", pin(sink)),
+        }
+    }
+}
@@ -9,11 +9,30 @@
 //! ```
 
 #![allow(unused)]
+#![feature(portable_simd)]
 
+mod analysis;
 mod app;
+mod editor;
+mod logisim;
 // mod modules;
-// mod runner;
+mod router;
+mod runner;
+mod schematic;
+mod timeline;
+mod verifier;
+mod verilog;
+mod waveform;
 
+pub use analysis::*;
 pub use app::*;
+pub use editor::*;
+pub use logisim::*;
 // pub use modules::*;
-// pub use runner::*;
+pub use router::*;
+pub use runner::*;
+pub use schematic::*;
+pub use timeline::*;
+pub use verifier::*;
+pub use verilog::*;
+pub use waveform::*;
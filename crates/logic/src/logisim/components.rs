@@ -0,0 +1,467 @@
+//! The blocks that replace each Logisim component, and where their pins are.
+//!
+//! | Logisim                                | Blocks                                  |
+//! |----------------------------------------|-----------------------------------------|
+//! | AND, OR, XOR, NOT Gate, Buffer         | `And`, `Or`, `XOr`, `Not`, `Or`         |
+//! | NAND, NOR, XNOR Gate, Parity           | the same gates, followed by a `Not`     |
+//! | Negated gate inputs                    | a `Not` before the input                |
+//! | Multiplexer                            | `Mux`: the data inputs, then the select |
+//! | Register                               | `Register`: data and enable             |
+//! | RAM with separate load and store ports | `Ram`: address, data and write          |
+//! | Adder, Subtractor                      | `Add`, `Sub`                            |
+//! | Constant 0 or all ones                 | an `Or` without inputs, and a `Not`     |
+//!
+//! The clocks are left out, because all the blocks of a solution tick together.
+//! The pin locations are the ones of Logisim 2.7, with the components facing east.
+
+use super::*;
+
+/// A `<comp>` element of a circuit
+pub(super) struct Component<'a> {
+    /// The `desc` of the library, like `#Gates`. Subcircuits have no library.
+    pub library: Option<&'a str>,
+    pub name: &'a str,
+    pub loc: Vec2<i32>,
+    pub attributes: HashMap<&'a str, &'a str>,
+    /// The file is from Logisim-evolution, which has other defaults
+    pub evolution: bool,
+}
+
+pub(super) enum Translated {
+    Blocks(Translation),
+    /// A pin of the circuit, which is a chapter port
+    Pin {
+        output: bool,
+        wires: u8,
+        label: String,
+    },
+    /// Connects the wires of all the tunnels with the same label
+    Tunnel(String),
+    /// The component has no effect on the solution
+    Ignored,
+}
+
+/// Blocks that replace a component
+#[derive(Default)]
+pub(super) struct Translation {
+    pub blocks: Vec<TranslatedBlock>,
+    /// Wires between the blocks of the translation
+    pub wires: Vec<Wire>,
+    pub ports: Vec<Port>,
+    /// Parts of the component that are left out
+    pub untranslated: Vec<UntranslatedReason>,
+}
+
+pub(super) struct TranslatedBlock {
+    /// Name of the block description in the Fundamentals module
+    pub name: &'static str,
+    pub inputs: Vec<BlockCable>,
+    pub outputs: Vec<BlockCable>,
+    pub loc: Vec2<i32>,
+}
+
+/// A point of the component where wires can be connected
+#[derive(Copy, Clone)]
+pub(super) struct Port {
+    pub loc: Vec2<i32>,
+    pub kind: PortKind,
+}
+
+#[derive(Copy, Clone)]
+pub(super) enum PortKind {
+    /// An output pin of a block of the translation
+    Output(PinRef),
+    /// An input pin of a block of the translation.
+    /// If nothing drives it, it is 1 like in Logisim.
+    Input { pin: PinRef, default_one: bool },
+    /// Whatever is connected, it does not change the solution
+    Ignored,
+    /// The blocks have no similar pin, so it can not be connected
+    Unsupported(&'static str),
+}
+
+impl Component<'_> {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).copied()
+    }
+
+    /// A number attribute, like a width
+    fn number(&self, name: &str, default: u32) -> Result<u32, UntranslatedReason> {
+        match self.attribute(name) {
+            None => Ok(default),
+            Some(value) => value.parse().map_err(|_| self.unsupported(name)),
+        }
+    }
+
+    fn wires(&self, name: &str, default: u8) -> Result<u8, UntranslatedReason> {
+        let wires = self.number(name, default as u32)?;
+        u8::try_from(wires).map_err(|_| self.unsupported(name))
+    }
+
+    fn facing(&self) -> Result<Facing, UntranslatedReason> {
+        match self.attribute("facing") {
+            None | Some("east") => Ok(Facing::East),
+            Some("west") => Ok(Facing::West),
+            Some("north") => Ok(Facing::North),
+            Some("south") => Ok(Facing::South),
+            Some(_) => Err(self.unsupported("facing")),
+        }
+    }
+
+    fn unsupported(&self, name: &str) -> UntranslatedReason {
+        UntranslatedReason::UnsupportedAttribute {
+            name: name.into(),
+            value: self.attribute(name).unwrap_or_default().into(),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Facing {
+    East,
+    West,
+    North,
+    South,
+}
+
+/// The location of a pin, from its offset when the component faces east
+fn offset(component: &Component, facing: Facing, dx: i32, dy: i32) -> Vec2<i32> {
+    let (x, y) = match facing {
+        Facing::East => (dx, dy),
+        Facing::West => (-dx, -dy),
+        Facing::North => (dy, -dx),
+        Facing::South => (-dy, dx),
+    };
+    component.loc + Vec2::new(x, y)
+}
+
+fn cable(lable: &str, wires: u8) -> BlockCable {
+    BlockCable {
+        lable: lable.into(),
+        wires,
+    }
+}
+
+/// The blocks that replace a component
+pub(super) fn translate(component: &Component) -> Result<Translated, UntranslatedReason> {
+    let Some(library) = component.library else {
+        return Err(UntranslatedReason::Subcircuit);
+    };
+    let translation = match (library, component.name) {
+        ("#Wiring", "Pin") => {
+            return Ok(Translated::Pin {
+                output: component.attribute("output") == Some("true"),
+                wires: component.wires("width", 1)?,
+                label: component.attribute("label").unwrap_or_default().into(),
+            })
+        }
+        ("#Wiring", "Tunnel") => {
+            let label = component.attribute("label").unwrap_or_default();
+            return Ok(Translated::Tunnel(label.into()));
+        }
+        ("#Wiring", "Clock" | "Probe") | ("#Base", "Text" | "Label") => {
+            return Ok(Translated::Ignored)
+        }
+        ("#Wiring", "Constant") => constant(component)?,
+        ("#Gates", "AND Gate") => gate(component, "And", false, false)?,
+        ("#Gates", "OR Gate") => gate(component, "Or", false, false)?,
+        ("#Gates", "XOR Gate") => gate(component, "XOr", false, true)?,
+        ("#Gates", "NAND Gate") => gate(component, "And", true, false)?,
+        ("#Gates", "NOR Gate") => gate(component, "Or", true, false)?,
+        ("#Gates", "XNOR Gate") => gate(component, "XOr", true, true)?,
+        ("#Gates", "Odd Parity") => gate(component, "XOr", false, false)?,
+        ("#Gates", "Even Parity") => gate(component, "XOr", true, false)?,
+        ("#Gates", "NOT Gate") => {
+            let size = component.number("size", 30)? as i32;
+            buffer(component, "Not", size)?
+        }
+        ("#Gates", "Buffer") => buffer(component, "Or", 20)?,
+        ("#Plexers", "Multiplexer") => multiplexer(component)?,
+        ("#Memory", "Register") => register(component)?,
+        ("#Memory", "RAM") => ram(component)?,
+        ("#Arithmetic", "Adder") => arithmetic(component, "Add", ["carry in", "carry out"])?,
+        ("#Arithmetic", "Subtractor") => arithmetic(component, "Sub", ["borrow in", "borrow out"])?,
+        _ => return Err(UntranslatedReason::UnknownComponent),
+    };
+    Ok(Translated::Blocks(translation))
+}
+
+impl Translation {
+    /// Adds a block and returns its index
+    fn block(
+        &mut self,
+        name: &'static str,
+        inputs: Vec<BlockCable>,
+        outputs: Vec<BlockCable>,
+        loc: Vec2<i32>,
+    ) -> usize {
+        self.blocks.push(TranslatedBlock {
+            name,
+            inputs,
+            outputs,
+            loc,
+        });
+        self.blocks.len() - 1
+    }
+
+    fn wire(&mut self, source: usize, sink: usize, pin: usize) {
+        self.wires.push(Wire {
+            source: PinRef::Block {
+                block: source,
+                pin: 0,
+            },
+            sink: PinRef::Block { block: sink, pin },
+        });
+    }
+
+    fn input(&mut self, loc: Vec2<i32>, block: usize, pin: usize) {
+        let pin = PinRef::Block { block, pin };
+        let kind = PortKind::Input {
+            pin,
+            default_one: false,
+        };
+        self.ports.push(Port { loc, kind });
+    }
+
+    fn output(&mut self, loc: Vec2<i32>, block: usize) {
+        let kind = PortKind::Output(PinRef::Block { block, pin: 0 });
+        self.ports.push(Port { loc, kind });
+    }
+
+    fn port(&mut self, loc: Vec2<i32>, kind: PortKind) {
+        self.ports.push(Port { loc, kind });
+    }
+
+    /// Adds a `Not` after the output of `block`, and returns its index
+    fn negate(&mut self, block: usize, wires: u8) -> usize {
+        let loc = self.blocks[block].loc;
+        let not = self.block("Not", vec![cable("", wires)], vec![cable("", wires)], loc);
+        self.wire(block, not, 0);
+        not
+    }
+}
+
+/// A gate with the `inputs` and `negate<n>` attributes of Logisim
+fn gate(
+    component: &Component,
+    name: &'static str,
+    negated: bool,
+    xor_shape: bool,
+) -> Result<Translation, UntranslatedReason> {
+    let wires = component.wires("width", 1)?;
+    let inputs = component.number("inputs", if component.evolution { 2 } else { 5 })? as i32;
+    let size = component.number("size", 50)? as i32;
+    let facing = component.facing()?;
+
+    let mut translation = Translation::default();
+    let cables = (0..inputs).map(|_| cable("", wires)).collect();
+    let block = translation.block(name, cables, vec![cable("", wires)], component.loc);
+    let output = match negated {
+        true => translation.negate(block, wires),
+        false => block,
+    };
+    translation.output(component.loc, output);
+
+    for input in 0..inputs {
+        let negated = component.attribute(&format!("negate{input}")) == Some("true");
+        let (mut dx, dy) = gate_input_offset(inputs, size, input);
+        if xor_shape {
+            dx -= 10;
+        }
+        if negated {
+            dx -= 10;
+        }
+        let loc = offset(component, facing, dx, dy);
+        match negated {
+            true => {
+                let not =
+                    translation.block("Not", vec![cable("", wires)], vec![cable("", wires)], loc);
+                translation.wire(not, block, input as usize);
+                translation.input(loc, not, 0);
+            }
+            false => translation.input(loc, block, input as usize),
+        }
+    }
+    Ok(translation)
+}
+
+/// Where the input `index` of a gate is, like Logisim spreads them on the back of the gate
+fn gate_input_offset(inputs: i32, size: i32, index: i32) -> (i32, i32) {
+    let (start, distance, lower_even) = match (inputs, size) {
+        (..=3, ..40) => (-5, 10, 10),
+        (..=3, ..60) | (..=2, _) => (-10, 20, 20),
+        (..=3, _) => (-15, 30, 30),
+        (4, 60..) => (-5, 20, 0),
+        _ => (-5, 10, 10),
+    };
+    let dy = match inputs % 2 {
+        1 => start * (inputs - 1) + distance * index,
+        _ if index >= inputs / 2 => start * inputs + distance * index + lower_even,
+        _ => start * inputs + distance * index,
+    };
+    (-size, dy)
+}
+
+/// A gate with a single input
+fn buffer(
+    component: &Component,
+    name: &'static str,
+    size: i32,
+) -> Result<Translation, UntranslatedReason> {
+    let wires = component.wires("width", 1)?;
+    let facing = component.facing()?;
+
+    let mut translation = Translation::default();
+    let cables = vec![cable("", wires)];
+    let block = translation.block(name, cables, vec![cable("", wires)], component.loc);
+    translation.output(component.loc, block);
+    translation.input(offset(component, facing, -size, 0), block, 0);
+    Ok(translation)
+}
+
+fn constant(component: &Component) -> Result<Translation, UntranslatedReason> {
+    let wires = component.wires("width", 1)?;
+    let value = component.attribute("value").unwrap_or("0x1");
+    let value = u64::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|_| component.unsupported("value"))?;
+    let ones = u64::MAX >> (64 - wires.clamp(1, 64));
+
+    if value != 0 && value != ones {
+        return Err(component.unsupported("value"));
+    }
+    let mut translation = Translation::default();
+    // An `Or` of an empty bundle is 0, and a `Not` after it makes all the wires 1
+    let zero = translation.block("Or", vec![], vec![cable("", wires)], component.loc);
+    let block = match value {
+        0 => zero,
+        _ => translation.negate(zero, wires),
+    };
+    translation.output(component.loc, block);
+    Ok(translation)
+}
+
+fn multiplexer(component: &Component) -> Result<Translation, UntranslatedReason> {
+    let wires = component.wires("width", 1)?;
+    let select = component.wires("select", 1)?;
+    let facing = component.facing()?;
+    if select > 5 {
+        return Err(component.unsupported("select"));
+    }
+    let inputs = 1 << select;
+
+    let mut translation = Translation::default();
+    let mut cables: Vec<BlockCable> = (0..inputs).map(|_| cable("", wires)).collect();
+    cables.push(cable("select", select));
+    let block = translation.block("Mux", cables, vec![cable("", wires)], component.loc);
+    translation.output(component.loc, block);
+
+    for input in 0..inputs {
+        let (dx, dy) = match inputs {
+            2 => (-30, -10 + 20 * input),
+            _ if input >= inputs / 2 => (-40, -(inputs / 2) * 10 + 10 * input + 10),
+            _ => (-40, -(inputs / 2) * 10 + 10 * input),
+        };
+        let loc = offset(component, facing, dx, dy);
+        translation.input(loc, block, input as usize);
+    }
+
+    let mut select_y = match inputs {
+        2 => 20,
+        _ => (inputs / 2) * 10 + 10,
+    };
+    if component.attribute("selloc") == Some("tr") {
+        select_y = -select_y;
+    }
+    let loc = offset(component, facing, -20, select_y);
+    translation.input(loc, block, inputs as usize);
+    if component.attribute("enable") != Some("false") {
+        let loc = offset(component, facing, -10, select_y);
+        translation.port(loc, PortKind::Unsupported("enable"));
+    }
+    Ok(translation)
+}
+
+fn register(component: &Component) -> Result<Translation, UntranslatedReason> {
+    let wires = component.wires("width", 8)?;
+    let loc = |dx, dy| component.loc + Vec2::new(dx, dy);
+
+    let mut translation = Translation::default();
+    let block = translation.block(
+        "Register",
+        vec![cable("data", wires), cable("enable", 1)],
+        vec![cable("value", wires)],
+        component.loc,
+    );
+    translation.output(loc(0, 0), block);
+    translation.input(loc(-30, 0), block, 0);
+    let enable = PinRef::Block { block, pin: 1 };
+    let kind = PortKind::Input {
+        pin: enable,
+        default_one: true,
+    };
+    translation.port(loc(-30, 10), kind);
+    translation.port(loc(-20, 20), PortKind::Ignored);
+    translation.port(loc(-10, 20), PortKind::Unsupported("clear"));
+    Ok(translation)
+}
+
+fn ram(component: &Component) -> Result<Translation, UntranslatedReason> {
+    let address = component.wires("addrWidth", 8)?;
+    let data = component.wires("dataWidth", 8)?;
+    let bus = component.attribute("bus").unwrap_or("combined");
+    let loc = |dx, dy| component.loc + Vec2::new(dx, dy);
+
+    let mut translation = Translation::default();
+    let block = translation.block(
+        "Ram",
+        vec![
+            cable("address", address),
+            cable("data", data),
+            cable("write", 1),
+        ],
+        vec![cable("data", data)],
+        component.loc,
+    );
+    translation.output(loc(0, 0), block);
+    translation.input(loc(-140, 0), block, 0);
+    translation.port(loc(-90, 40), PortKind::Unsupported("chip select"));
+    translation.port(loc(-50, 40), PortKind::Unsupported("output enable"));
+    translation.port(loc(-30, 40), PortKind::Unsupported("clear"));
+    if bus != "asynch" {
+        translation.port(loc(-70, 40), PortKind::Ignored);
+    }
+    match bus {
+        "separate" => {
+            translation.input(loc(-140, 20), block, 1);
+            translation.input(loc(-110, 40), block, 2);
+        }
+        // The data is stored from the same pin where it is loaded,
+        // so only loads are imported
+        _ => translation.untranslated.push(component.unsupported("bus")),
+    }
+    Ok(translation)
+}
+
+/// An adder or a subtractor, without the carry or borrow pins
+fn arithmetic(
+    component: &Component,
+    name: &'static str,
+    [carry_in, carry_out]: [&'static str; 2],
+) -> Result<Translation, UntranslatedReason> {
+    let wires = component.wires("width", 8)?;
+    let loc = |dx, dy| component.loc + Vec2::new(dx, dy);
+
+    let mut translation = Translation::default();
+    let block = translation.block(
+        name,
+        vec![cable("a", wires), cable("b", wires)],
+        vec![cable("", wires)],
+        component.loc,
+    );
+    translation.output(loc(0, 0), block);
+    translation.input(loc(-40, -10), block, 0);
+    translation.input(loc(-40, 10), block, 1);
+    translation.port(loc(-20, -20), PortKind::Unsupported(carry_in));
+    translation.port(loc(-20, 20), PortKind::Unsupported(carry_out));
+    Ok(translation)
+}
@@ -0,0 +1,448 @@
+//! Import of circuits drawn in Logisim, from the `.circ` files of Logisim 2.7 and Logisim-evolution
+//!
+//! Each component is replaced by the closest blocks of the Fundamentals module,
+//! with the same widths and at the same position of the grid.
+//! Logisim wires connect points, so two pins are connected when their components
+//! touch the same wires. The components that can not be replaced are left out and reported.
+
+mod components;
+
+use crate::*;
+use components::*;
+use digolog_math::*;
+use digolog_module_loader::*;
+use std::collections::HashMap;
+
+/// Logisim draws on a grid of 10 pixels
+const GRID: i32 = 10;
+
+pub struct LogisimImport {
+    pub solution: ChapterSolution,
+    pub untranslated: Vec<Untranslated>,
+}
+
+/// A part of the circuit that is not in the solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Untranslated {
+    /// Name of the Logisim component, or `wire`
+    pub component: String,
+    /// Location in the Logisim circuit, in pixels
+    pub loc: Vec2<i32>,
+    pub reason: UntranslatedReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UntranslatedReason {
+    /// There is no similar block
+    UnknownComponent,
+    /// Circuits used inside other circuits are not imported
+    Subcircuit,
+    /// The module has no block with this name
+    MissingBlock(String),
+    /// The value of an attribute has no similar block
+    UnsupportedAttribute { name: String, value: String },
+    /// A pin of the component is connected, but the blocks have no similar pin
+    UnsupportedPin(&'static str),
+    /// The chapter has no free port with the label and the width of the pin
+    NoChapterPort,
+    /// The wires are driven by more than one output
+    ManyDrivers,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogisimError {
+    /// The file is not valid XML
+    Xml(String),
+    /// The file has no circuit with this name, or no circuits
+    NoCircuit(String),
+}
+
+/// Imports a circuit of a `.circ` file as a solution of `chapter`.
+/// Imports the main circuit if `circuit` is `None`.
+///
+/// The pins of the circuit are matched to the chapter ports with the same label,
+/// and the other pins to the free ports with the same width, from top to bottom.
+pub fn import_logisim(
+    xml: &str,
+    circuit: Option<&str>,
+    chapter: &Chapter,
+    blocks: &ModuleBlocks,
+) -> Result<LogisimImport, LogisimError> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|error| LogisimError::Xml(error.to_string()))?;
+    let project = document.root_element();
+
+    let libraries: HashMap<&str, &str> = children(project, "lib")
+        .filter_map(|lib| Some((lib.attribute("name")?, lib.attribute("desc")?)))
+        .collect();
+    let main = children(project, "main").find_map(|main| main.attribute("name"));
+    let name = circuit.or(main);
+    let circuit = children(project, "circuit")
+        .find(|node| name.is_none_or(|name| node.attribute("name") == Some(name)))
+        .ok_or_else(|| LogisimError::NoCircuit(name.unwrap_or_default().into()))?;
+    let evolution = (project.attribute("source")).is_some_and(|source| !source.starts_with("2."));
+
+    let mut importer = Importer {
+        chapter,
+        blocks,
+        solution: chapter.new_solution(),
+        untranslated: Vec::new(),
+        points: Points::default(),
+        ports: Vec::new(),
+        pins: Vec::new(),
+    };
+    let mut segments = Vec::new();
+    for wire in children(circuit, "wire") {
+        let point = |name| wire.attribute(name).and_then(parse_point);
+        if let (Some(from), Some(to)) = (point("from"), point("to")) {
+            importer.points.wire(from, to);
+            segments.push((from, to));
+        }
+    }
+    // A wire that ends in the middle of another one is connected to it
+    for &(from, to) in &segments {
+        for end in [from, to] {
+            if let Some(&(segment, _)) = segments.iter().find(|&&s| inside(s, end)) {
+                importer.points.union(end, segment);
+            }
+        }
+    }
+
+    let mut tunnels: HashMap<String, Vec2<i32>> = HashMap::new();
+    for element in children(circuit, "comp") {
+        let Some(loc) = element.attribute("loc").and_then(parse_point) else {
+            continue;
+        };
+        let component = Component {
+            library: (element.attribute("lib")).and_then(|lib| libraries.get(lib).copied()),
+            name: element.attribute("name").unwrap_or_default(),
+            loc,
+            attributes: children(element, "a")
+                .filter_map(|a| Some((a.attribute("name")?, a.attribute("val")?)))
+                .collect(),
+            evolution,
+        };
+
+        match translate(&component) {
+            Ok(Translated::Blocks(translation)) => importer.add(&component, translation),
+            Ok(Translated::Pin {
+                output,
+                wires,
+                label,
+            }) => importer.pins.push(Pin {
+                loc,
+                output,
+                wires,
+                label,
+            }),
+            Ok(Translated::Tunnel(label)) => {
+                let first = *tunnels.entry(label).or_insert(loc);
+                importer.points.union(first, loc);
+            }
+            Ok(Translated::Ignored) => {}
+            Err(reason) => importer.untranslate(component.name, loc, reason),
+        }
+    }
+
+    importer.match_pins();
+    importer.connect();
+    Ok(LogisimImport {
+        solution: importer.solution,
+        untranslated: importer.untranslated,
+    })
+}
+
+struct Importer<'a> {
+    chapter: &'a Chapter,
+    blocks: &'a ModuleBlocks,
+    solution: ChapterSolution,
+    untranslated: Vec<Untranslated>,
+    /// The points connected by the wires
+    points: Points,
+    /// The ports of all the translated components, and their names
+    ports: Vec<(String, Port)>,
+    pins: Vec<Pin>,
+}
+
+/// A pin of the circuit
+struct Pin {
+    loc: Vec2<i32>,
+    output: bool,
+    wires: u8,
+    label: String,
+}
+
+impl Importer<'_> {
+    fn untranslate(&mut self, component: &str, loc: Vec2<i32>, reason: UntranslatedReason) {
+        self.untranslated.push(Untranslated {
+            component: component.into(),
+            loc,
+            reason,
+        });
+    }
+
+    /// Adds the blocks of a component to the solution
+    fn add(&mut self, component: &Component, translation: Translation) {
+        let missing =
+            (translation.blocks.iter()).find(|block| !self.blocks.contains_key(block.name));
+        if let Some(block) = missing {
+            let reason = UntranslatedReason::MissingBlock(block.name.into());
+            return self.untranslate(component.name, component.loc, reason);
+        }
+        for reason in translation.untranslated {
+            self.untranslate(component.name, component.loc, reason);
+        }
+
+        let first = self.solution.blocks.len();
+        let shift = |pin: PinRef| match pin {
+            PinRef::Block { block, pin } => PinRef::Block {
+                block: first + block,
+                pin,
+            },
+            chapter => chapter,
+        };
+        for (index, block) in translation.blocks.into_iter().enumerate() {
+            let label = component
+                .attribute("label")
+                .filter(|label| !label.is_empty());
+            let lable = match label {
+                Some(label) if index == 0 => label.into(),
+                _ => self.blocks[block.name].lable.clone(),
+            };
+            self.solution.blocks.push(Block {
+                shape: BlockShape {
+                    description: BlockDescId {
+                        name: block.name.into(),
+                    },
+                    lable,
+                    inputs: block.inputs,
+                    outputs: block.outputs,
+                    layout: PinLayout::default(),
+                },
+                pos: Vec2::new(block.loc.x / GRID, block.loc.y / GRID),
+                orientation: Orientation::default(),
+            });
+        }
+        for wire in translation.wires {
+            self.solution.wires.push(Wire {
+                source: shift(wire.source),
+                sink: shift(wire.sink),
+            });
+        }
+        for port in translation.ports {
+            let kind = match port.kind {
+                PortKind::Output(pin) => PortKind::Output(shift(pin)),
+                PortKind::Input { pin, default_one } => PortKind::Input {
+                    pin: shift(pin),
+                    default_one,
+                },
+                kind => kind,
+            };
+            let port = Port {
+                loc: port.loc,
+                kind,
+            };
+            self.ports.push((component.name.into(), port));
+        }
+    }
+
+    /// Makes the pins of the circuit ports of the chapter
+    fn match_pins(&mut self) {
+        let mut pins = std::mem::take(&mut self.pins);
+        pins.sort_by_key(|pin| (pin.loc.y, pin.loc.x));
+
+        for output in [false, true] {
+            let ports = match output {
+                false => &self.chapter.inputs,
+                true => &self.chapter.outputs,
+            };
+            let mut taken = vec![false; ports.len()];
+            let mut matched: Vec<Option<usize>> = vec![None; pins.len()];
+            let mut take = |pin: &Pin, same_label: bool| {
+                let port = (ports.iter().enumerate()).position(|(port, cable)| {
+                    !taken[port]
+                        && cable.wires == pin.wires
+                        && (!same_label || cable.lable == pin.label)
+                })?;
+                taken[port] = true;
+                Some(port)
+            };
+            for same_label in [true, false] {
+                for (index, pin) in pins.iter().enumerate() {
+                    if pin.output == output && matched[index].is_none() {
+                        matched[index] = take(pin, same_label);
+                    }
+                }
+            }
+
+            for (pin, port) in pins.iter().zip(matched) {
+                if pin.output != output {
+                    continue;
+                }
+                let Some(port) = port else {
+                    self.untranslate("Pin", pin.loc, UntranslatedReason::NoChapterPort);
+                    continue;
+                };
+                let pin_ref = PinRef::Chapter { port };
+                let kind = match output {
+                    false => PortKind::Output(pin_ref),
+                    true => PortKind::Input {
+                        pin: pin_ref,
+                        default_one: false,
+                    },
+                };
+                let port = Port { loc: pin.loc, kind };
+                self.ports.push(("Pin".into(), port));
+            }
+        }
+    }
+
+    /// Adds the wires between the ports that touch the same Logisim wires
+    fn connect(&mut self) {
+        let mut nets: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (index, (_, port)) in self.ports.iter().enumerate() {
+            let net = self.points.find(port.loc);
+            nets.entry(net).or_default().push(index);
+        }
+        let mut nets: Vec<(usize, Vec<usize>)> = nets.into_iter().collect();
+        nets.sort_by_key(|(_, ports)| ports[0]);
+
+        for (net, ports) in nets {
+            let connected = ports.len() > 1 || self.points.wired(net);
+            let sources: Vec<PinRef> = (ports.iter())
+                .filter_map(|&port| match self.ports[port].1.kind {
+                    PortKind::Output(pin) => Some(pin),
+                    _ => None,
+                })
+                .collect();
+            if sources.len() > 1 {
+                let loc = self.ports[ports[0]].1.loc;
+                self.untranslate("wire", loc, UntranslatedReason::ManyDrivers);
+                continue;
+            }
+
+            for &port in &ports {
+                let (component, port) = self.ports[port].clone();
+                match port.kind {
+                    PortKind::Input { pin, default_one } => match sources.first() {
+                        Some(&source) => self.solution.wires.push(Wire { source, sink: pin }),
+                        None if default_one => self.one(pin, port.loc),
+                        None => {}
+                    },
+                    PortKind::Unsupported(name) if connected => {
+                        let reason = UntranslatedReason::UnsupportedPin(name);
+                        self.untranslate(&component, port.loc, reason);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Drives a pin with a constant 1: a `Not` of an `Or` without inputs, which is 0
+    fn one(&mut self, sink: PinRef, loc: Vec2<i32>) {
+        let wire = || BlockCable {
+            lable: String::new(),
+            wires: 1,
+        };
+        let first = self.solution.blocks.len();
+        for (index, (name, inputs)) in [("Or", vec![]), ("Not", vec![wire()])]
+            .into_iter()
+            .enumerate()
+        {
+            self.solution.blocks.push(Block {
+                shape: BlockShape {
+                    description: BlockDescId { name: name.into() },
+                    lable: (self.blocks.get(name)).map_or(name.into(), |d| d.lable.clone()),
+                    inputs,
+                    outputs: vec![wire()],
+                    layout: PinLayout::default(),
+                },
+                pos: Vec2::new(loc.x / GRID - 4 + 2 * index as i32, loc.y / GRID),
+                orientation: Orientation::default(),
+            });
+        }
+        let (or, not) = (first, first + 1);
+        self.solution.wires.push(Wire {
+            source: PinRef::Block { block: or, pin: 0 },
+            sink: PinRef::Block { block: not, pin: 0 },
+        });
+        let source = PinRef::Block { block: not, pin: 0 };
+        self.solution.wires.push(Wire { source, sink });
+    }
+}
+
+/// The points of the circuit, in sets of connected points
+#[derive(Default)]
+struct Points {
+    indices: HashMap<Vec2<i32>, usize>,
+    parents: Vec<usize>,
+    /// The point is the end of a wire
+    wired: Vec<bool>,
+}
+
+impl Points {
+    fn index(&mut self, point: Vec2<i32>) -> usize {
+        let next = self.parents.len();
+        let index = *self.indices.entry(point).or_insert(next);
+        if index == next {
+            self.parents.push(next);
+            self.wired.push(false);
+        }
+        index
+    }
+
+    /// The set of a point, the same for all the connected points
+    fn find(&mut self, point: Vec2<i32>) -> usize {
+        let mut index = self.index(point);
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+
+    /// Connects two points
+    fn union(&mut self, a: Vec2<i32>, b: Vec2<i32>) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[b] = a;
+        self.wired[a] |= self.wired[b];
+    }
+
+    /// Connects two points with a wire
+    fn wire(&mut self, a: Vec2<i32>, b: Vec2<i32>) {
+        self.union(a, b);
+        let set = self.find(a);
+        self.wired[set] = true;
+    }
+
+    /// The set of points has wires
+    fn wired(&self, set: usize) -> bool {
+        self.wired[set]
+    }
+}
+
+/// The child elements with a name
+fn children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    (node.children()).filter(move |child| child.has_tag_name(name))
+}
+
+/// Reads a point written as `(x,y)`
+fn parse_point(text: &str) -> Option<Vec2<i32>> {
+    let (x, y) = text
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split_once(',')?;
+    Some(Vec2::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// The point is in a horizontal or vertical segment, but not at its ends
+fn inside((from, to): (Vec2<i32>, Vec2<i32>), point: Vec2<i32>) -> bool {
+    let between = |a: i32, b: i32, c: i32| a.min(b) < c && c < a.max(b);
+    (from.x == to.x && point.x == from.x && between(from.y, to.y, point.y))
+        || (from.y == to.y && point.y == from.y && between(from.x, to.x, point.x))
+}
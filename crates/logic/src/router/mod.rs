@@ -0,0 +1,249 @@
+//! Orthogonal paths of the wires on the grid
+//!
+//! The wires go around the blocks, with few bends and few crossings.
+//! Wires of the same source can share their path, wires of different sources only cross.
+//! The paths are kept between edits, and only the wires that an edit touches are routed again.
+//!
+//! The SVG schematics draw the wires on these paths. The editor of the window still draws
+//! straight wires with its `WireBuilder`, and does not use the router yet.
+
+mod search;
+
+use digolog_math::*;
+use digolog_module_loader::*;
+use search::*;
+use std::collections::HashMap;
+
+/// Grid units between the blocks and the chapter ports
+const PORT_GAP: i32 = 3;
+
+/// The path of a wire, from its source to its sink
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WirePath {
+    /// The ends of the wire and the points where it turns
+    pub points: Vec<Vec2<i32>>,
+}
+
+/// Positions of the chapter ports, that are not part of the solution.
+/// The chapter inputs are sources going right, the chapter outputs are sinks entered from the left.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChapterPorts {
+    pub inputs: Vec<Vec2<i32>>,
+    pub outputs: Vec<Vec2<i32>>,
+}
+
+/// The paths of the wires of a solution
+#[derive(Debug, Clone)]
+pub struct Router {
+    ports: ChapterPorts,
+    paths: HashMap<Wire, WirePath>,
+    occupied: Occupancy,
+}
+
+impl WirePath {
+    pub fn bends(&self) -> usize {
+        self.points.len().saturating_sub(2)
+    }
+}
+
+impl ChapterPorts {
+    /// The chapter inputs in a column left of the blocks and the outputs in a column right of them
+    pub fn beside(chapter: &Chapter, solution: &ChapterSolution) -> Self {
+        let rects = solution.blocks.iter().map(Block::rect);
+        let (mut min, mut max) = ([0, 0], [0, 0]);
+        for (i, rect) in rects.enumerate() {
+            let ([x0, y0], [x1, y1]) = (rect.min.into(), rect.max.into());
+            if i == 0 {
+                (min, max) = ([x0, y0], [x1, y1]);
+            }
+            min = [min[0].min(x0), min[1].min(y0)];
+            max = [max[0].max(x1), max[1].max(y1)];
+        }
+        let column = |cables: &[BlockCable], x: i32| {
+            (0..cables.len())
+                .map(|port| Vec2::new(x, min[1] + port as i32 + 1))
+                .collect()
+        };
+        ChapterPorts {
+            inputs: column(&chapter.inputs, min[0] - PORT_GAP),
+            outputs: column(&chapter.outputs, max[0] + PORT_GAP),
+        }
+    }
+}
+
+impl Router {
+    /// A router without paths, call [`Self::update`] to route the wires of a solution
+    pub fn new(ports: ChapterPorts) -> Self {
+        Self {
+            ports,
+            paths: HashMap::new(),
+            occupied: Occupancy::new(),
+        }
+    }
+
+    /// Routes all the wires of a solution, in their order
+    pub fn route(solution: &ChapterSolution, ports: ChapterPorts) -> Self {
+        let mut router = Router::new(ports);
+        router.update(solution);
+        router
+    }
+
+    pub fn ports(&self) -> &ChapterPorts {
+        &self.ports
+    }
+
+    /// The path of a wire, if it is routed
+    pub fn path(&self, wire: &Wire) -> Option<&WirePath> {
+        self.paths.get(wire)
+    }
+
+    /// Routes the wires that have no path or whose pins are not at the ends of their path,
+    /// and forgets the wires that are not in the solution anymore.
+    /// Returns the routed wires.
+    pub fn update(&mut self, solution: &ChapterSolution) -> Vec<Wire> {
+        let stale: Vec<Wire> = (self.paths.iter())
+            .filter(|(wire, path)| {
+                !solution.wires.contains(wire)
+                    || self.ends(solution, wire).is_none_or(|[start, end]| {
+                        path.points.first() != Some(&start.pos.into())
+                            || path.points.last() != Some(&end.pos.into())
+                    })
+            })
+            .map(|(wire, _)| *wire)
+            .collect();
+        for wire in stale {
+            self.unroute(wire);
+        }
+
+        let mut routed = vec![];
+        for wire in &solution.wires {
+            if !self.paths.contains_key(wire) && self.route_wire(solution, *wire) {
+                routed.push(*wire);
+            }
+        }
+        routed
+    }
+
+    /// Routes again the wires of a block that moved or turned,
+    /// and the wires that go through its new place. The other wires keep their path.
+    /// Returns the routed wires.
+    pub fn block_moved(&mut self, solution: &ChapterSolution, block: usize) -> Vec<Wire> {
+        let Some(placed) = solution.blocks.get(block) else {
+            return self.update(solution);
+        };
+        let ([x0, y0], [x1, y1]) = (placed.rect().min.into(), placed.rect().max.into());
+        let inside =
+            |point: [i32; 2]| (x0..=x1).contains(&point[0]) && (y0..=y1).contains(&point[1]);
+        let touches = |pin: PinRef| matches!(pin, PinRef::Block { block: b, .. } if b == block);
+
+        let crossed: Vec<Wire> = (self.paths.iter())
+            .filter(|(wire, path)| {
+                !touches(wire.source)
+                    && !touches(wire.sink)
+                    && (path.points.windows(2)).any(|segment| {
+                        let [a, b] = [segment[0], segment[1]].map(<[i32; 2]>::from);
+                        segment_points(a, b).any(inside)
+                    })
+            })
+            .map(|(wire, _)| *wire)
+            .collect();
+        for wire in crossed {
+            self.unroute(wire);
+        }
+        self.update(solution)
+    }
+
+    /// Finds and records the path of a wire. Returns false if its pins do not exist.
+    fn route_wire(&mut self, solution: &ChapterSolution, wire: Wire) -> bool {
+        let Some([start, end]) = self.ends(solution, &wire) else {
+            return false;
+        };
+        let obstacles: Vec<[i32; 4]> = (solution.blocks.iter())
+            .map(|block| {
+                let ([x0, y0], [x1, y1]) = (block.rect().min.into(), block.rect().max.into());
+                [x0, y0, x1, y1]
+            })
+            .collect();
+        let ports: Vec<[i32; 2]> = (self.ports.inputs.iter().chain(&self.ports.outputs))
+            .map(|port| (*port).into())
+            .collect();
+        let search = Search {
+            obstacles: &obstacles,
+            ports: &ports,
+            occupied: &self.occupied,
+            source: wire.source,
+        };
+        // A walled in pin still gets a path, through the blocks
+        let corners = search.route(start, end).unwrap_or_else(|| {
+            let mid = (start.pos[0] + end.pos[0]) / 2;
+            let mut corners = vec![start.pos, [mid, start.pos[1]], [mid, end.pos[1]], end.pos];
+            corners.dedup();
+            corners
+        });
+
+        self.occupy(wire.source, &corners, true);
+        let points = corners.into_iter().map(Vec2::from).collect();
+        self.paths.insert(wire, WirePath { points });
+        true
+    }
+
+    fn unroute(&mut self, wire: Wire) {
+        if let Some(path) = self.paths.remove(&wire) {
+            let corners: Vec<[i32; 2]> = path.points.into_iter().map(Into::into).collect();
+            self.occupy(wire.source, &corners, false);
+        }
+    }
+
+    /// Adds or removes the points of a path in the occupancy of the grid
+    fn occupy(&mut self, source: PinRef, corners: &[[i32; 2]], add: bool) {
+        for segment in corners.windows(2) {
+            let horizontal = segment[0][1] == segment[1][1];
+            for point in segment_points(segment[0], segment[1]) {
+                let wires = self.occupied.entry(point).or_default();
+                match add {
+                    true => wires.push((source, horizontal)),
+                    false => {
+                        if let Some(i) = wires.iter().position(|w| *w == (source, horizontal)) {
+                            wires.swap_remove(i);
+                        }
+                    }
+                }
+                if wires.is_empty() {
+                    self.occupied.remove(&point);
+                }
+            }
+        }
+    }
+
+    /// The source and sink ends of a wire, if its pins exist
+    fn ends(&self, solution: &ChapterSolution, wire: &Wire) -> Option<[PinEnd; 2]> {
+        Some([
+            self.pin_end(solution, wire.source, true)?,
+            self.pin_end(solution, wire.sink, false)?,
+        ])
+    }
+
+    fn pin_end(&self, solution: &ChapterSolution, pin: PinRef, output: bool) -> Option<PinEnd> {
+        let (pos, side) = match pin {
+            PinRef::Chapter { port } => match output {
+                true => (*self.ports.inputs.get(port)?, Side::Right),
+                false => (*self.ports.outputs.get(port)?, Side::Left),
+            },
+            PinRef::Block { block, pin } => {
+                let block = solution.blocks.get(block)?;
+                (block.pin_pos(output, pin)?, block.pin_side(output, pin))
+            }
+        };
+        Some(PinEnd {
+            pos: pos.into(),
+            direction: side as usize,
+        })
+    }
+}
+
+/// The points of a horizontal or vertical segment, with its ends
+fn segment_points(a: [i32; 2], b: [i32; 2]) -> impl Iterator<Item = [i32; 2]> {
+    let steps = (b[0] - a[0]).abs().max((b[1] - a[1]).abs());
+    let direction = [(b[0] - a[0]).signum(), (b[1] - a[1]).signum()];
+    (0..=steps).map(move |i| [a[0] + direction[0] * i, a[1] + direction[1] * i])
+}
@@ -0,0 +1,158 @@
+//! Shortest orthogonal path on the grid between two pins
+
+use super::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Cost of a step of one grid unit
+const STEP_COST: u32 = 1;
+/// Cost of a turn of the wire
+const BEND_COST: u32 = 4;
+/// Cost of going across a wire of another source
+const CROSSING_COST: u32 = 6;
+/// Cost of a step on top of a wire of another source, which makes the drawing ambiguous
+const OVERLAP_COST: u32 = 20;
+
+/// Grid units around the blocks and ports where the wires can go
+const SEARCH_MARGIN: i32 = 2;
+
+/// One grid unit towards each [`Side`], in its order
+const DIRECTIONS: [[i32; 2]; 4] = [[-1, 0], [0, -1], [1, 0], [0, 1]];
+
+/// Sources of the wires on each point, and whether they are horizontal there
+pub(super) type Occupancy = HashMap<[i32; 2], Vec<(PinRef, bool)>>;
+
+/// A point of the grid and the direction in which the path reached it
+type State = ([i32; 2], usize);
+
+/// An end of a wire, with the direction that goes away from its pin
+#[derive(Debug, Copy, Clone)]
+pub(super) struct PinEnd {
+    pub pos: [i32; 2],
+    pub direction: usize,
+}
+
+pub(super) struct Search<'a> {
+    pub obstacles: &'a [[i32; 4]],
+    /// Chapter ports, that only their own wires can touch
+    pub ports: &'a [[i32; 2]],
+    pub occupied: &'a Occupancy,
+    pub source: PinRef,
+}
+
+impl Search<'_> {
+    /// The corners of the cheapest path that leaves `start` and enters `end` straight.
+    /// Returns `None` if the pins are walled in.
+    pub fn route(&self, start: PinEnd, end: PinEnd) -> Option<Vec<[i32; 2]>> {
+        let bounds = self.bounds(start.pos, end.pos);
+        let arrival = (end.direction + 2) % 4;
+        let blocked = |pos: [i32; 2]| {
+            if pos == start.pos || pos == end.pos {
+                return false;
+            }
+            let [x, y] = pos;
+            let outside = x < bounds[0] || y < bounds[1] || x > bounds[2] || y > bounds[3];
+            outside
+                || self.ports.contains(&pos)
+                || (self.obstacles.iter())
+                    .any(|[x0, y0, x1, y1]| (*x0..=*x1).contains(&x) && (*y0..=*y1).contains(&y))
+        };
+
+        let mut costs: HashMap<State, u32> = HashMap::new();
+        let mut previous: HashMap<State, State> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        costs.insert((start.pos, start.direction), 0);
+        queue.push(Reverse((0, start.pos, start.direction)));
+
+        while let Some(Reverse((cost, pos, direction))) = queue.pop() {
+            if (pos, direction) == (end.pos, arrival) {
+                return Some(self.corners(&previous, (pos, direction), start));
+            }
+            if costs
+                .get(&(pos, direction))
+                .is_some_and(|best| *best < cost)
+            {
+                continue;
+            }
+            for (next_direction, [dx, dy]) in DIRECTIONS.into_iter().enumerate() {
+                if next_direction == (direction + 2) % 4 {
+                    continue;
+                }
+                let next = [pos[0] + dx, pos[1] + dy];
+                if blocked(next) {
+                    continue;
+                }
+                let bend = if next_direction == direction {
+                    0
+                } else {
+                    BEND_COST
+                };
+                let next_cost = cost + STEP_COST + bend + self.step_cost(next, next_direction);
+                let state = (next, next_direction);
+                if costs.get(&state).is_some_and(|best| *best <= next_cost) {
+                    continue;
+                }
+                costs.insert(state, next_cost);
+                previous.insert(state, (pos, direction));
+                queue.push(Reverse((next_cost, next, next_direction)));
+            }
+        }
+        None
+    }
+
+    /// The cost of the other wires on a point that is reached in a direction
+    fn step_cost(&self, pos: [i32; 2], direction: usize) -> u32 {
+        let horizontal = direction.is_multiple_of(2);
+        let Some(wires) = self.occupied.get(&pos) else {
+            return 0;
+        };
+        (wires.iter())
+            .filter(|(source, _)| *source != self.source)
+            .map(|(_, other)| match *other == horizontal {
+                true => OVERLAP_COST,
+                false => CROSSING_COST,
+            })
+            .sum()
+    }
+
+    /// The area that the search can use: the blocks, the ports and the pins, with a margin
+    fn bounds(&self, start: [i32; 2], end: [i32; 2]) -> [i32; 4] {
+        let points = (self.obstacles.iter())
+            .flat_map(|[x0, y0, x1, y1]| [[*x0, *y0], [*x1, *y1]])
+            .chain(self.ports.iter().copied())
+            .chain([start, end]);
+        let mut bounds = [start[0], start[1], start[0], start[1]];
+        for [x, y] in points {
+            bounds = [
+                bounds[0].min(x),
+                bounds[1].min(y),
+                bounds[2].max(x),
+                bounds[3].max(y),
+            ];
+        }
+        let m = SEARCH_MARGIN;
+        [bounds[0] - m, bounds[1] - m, bounds[2] + m, bounds[3] + m]
+    }
+
+    /// Walks back from the end of the search, keeping the points where the path turns
+    fn corners(
+        &self,
+        previous: &HashMap<State, State>,
+        mut state: State,
+        start: PinEnd,
+    ) -> Vec<[i32; 2]> {
+        let mut corners = vec![state.0];
+        while state != (start.pos, start.direction) {
+            let before = previous[&state];
+            if before.1 != state.1 {
+                corners.push(before.0);
+            }
+            state = before;
+        }
+        if corners.last() != Some(&start.pos) {
+            corners.push(start.pos);
+        }
+        corners.reverse();
+        corners
+    }
+}
@@ -0,0 +1,148 @@
+//! Evaluation of the arithmetic blocks on cables stored as bytes.
+//!
+//! The cables are little endian numbers of any width,
+//! so the operations are done byte by byte with a carry.
+
+use super::*;
+use std::cmp::Ordering;
+
+/// Evaluates `arithmetic` with `inputs` laid out as [`BlockInput`],
+/// where `input_wires` are the wires of each input cable,
+/// and `output_wires` the wires of each output cable.
+pub(crate) fn evaluate_arithmetic(
+    arithmetic: Arithmetic,
+    inputs: &[u8],
+    input_wires: impl Iterator<Item = u8>,
+    output: &mut [u8],
+    output_wires: impl Iterator<Item = u8>,
+) {
+    let cables: Vec<&[u8]> = input_wires
+        .scan(0, |offset, wires| {
+            let cable = &inputs[*offset..][..cable_bytes(wires)];
+            *offset += cable.len();
+            Some(cable)
+        })
+        .collect();
+    let cable = |i: usize| cables.get(i).copied().unwrap_or(&[]);
+    let mut outputs = output_wires.map(cable_bytes);
+    let result_bytes = outputs.next().unwrap_or(0);
+    output.fill(0);
+
+    let result = match arithmetic {
+        Arithmetic::Add => {
+            let mut sum = vec![0; result_bytes];
+            for cable in &cables {
+                add_assign(&mut sum, cable);
+            }
+            sum
+        }
+        Arithmetic::Sub => {
+            let len = cable(0).len().max(cable(1).len()).max(result_bytes);
+            let mut difference = cable(0).to_vec();
+            difference.resize(len, 0);
+            let borrow = sub_assign(&mut difference, cable(1));
+            if outputs.next().is_some() {
+                output[result_bytes] = borrow as u8;
+            }
+            difference
+        }
+        Arithmetic::Mul => {
+            let mut product = vec![0; result_bytes];
+            if let Some(first) = product.first_mut() {
+                *first = 1;
+            }
+            for cable in &cables {
+                product = mul(&product, cable);
+            }
+            product
+        }
+        Arithmetic::Div | Arithmetic::Mod => {
+            let (quotient, remainder) = div_rem(cable(0), cable(1));
+            match arithmetic {
+                Arithmetic::Div => quotient,
+                _ => remainder,
+            }
+        }
+    };
+
+    let len = result.len().min(result_bytes);
+    output[..len].copy_from_slice(&result[..len]);
+    if arithmetic == Arithmetic::Div && is_zero(cable(1)) {
+        output[..result_bytes].fill(!0);
+    }
+}
+
+fn byte(number: &[u8], i: usize) -> u8 {
+    number.get(i).copied().unwrap_or(0)
+}
+
+fn is_zero(number: &[u8]) -> bool {
+    number.iter().all(|&byte| byte == 0)
+}
+
+fn compare(a: &[u8], b: &[u8]) -> Ordering {
+    (0..a.len().max(b.len()))
+        .rev()
+        .map(|i| byte(a, i).cmp(&byte(b, i)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// `a += b`, without the carry out of the last byte of `a`
+fn add_assign(a: &mut [u8], b: &[u8]) {
+    let mut carry = 0;
+    for (i, a) in a.iter_mut().enumerate() {
+        let sum = *a as u16 + byte(b, i) as u16 + carry;
+        *a = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+/// `a -= b`. Returns true if `b` is greater than `a`, then `a` wraps around.
+fn sub_assign(a: &mut [u8], b: &[u8]) -> bool {
+    let mut borrow = 0;
+    for (i, a) in a.iter_mut().enumerate() {
+        let difference = *a as i16 - byte(b, i) as i16 - borrow;
+        *a = difference as u8;
+        borrow = (difference < 0) as i16;
+    }
+    borrow == 1 || b.iter().skip(a.len()).any(|&byte| byte != 0)
+}
+
+/// `a * b`, with as many bytes as `a`
+fn mul(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut product = vec![0; a.len()];
+    for (i, &a_byte) in a.iter().enumerate() {
+        let mut carry = 0;
+        for j in 0..a.len() - i {
+            let sum = product[i + j] as u16 + a_byte as u16 * byte(b, j) as u16 + carry;
+            product[i + j] = sum as u8;
+            carry = sum >> 8;
+        }
+    }
+    product
+}
+
+/// The quotient and the remainder of `a / b`, one bit at a time.
+/// Dividing by 0 gives a quotient of 0 and a remainder of `a`.
+fn div_rem(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut quotient = vec![0; a.len()];
+    // The remainder is less than `b`, so shifting it never overflows
+    let mut remainder = vec![0; b.len() + 1];
+    if is_zero(b) {
+        return (quotient, a.to_vec());
+    }
+    for bit in (0..a.len() * 8).rev() {
+        let mut carry = a[bit / 8] >> (bit % 8) & 1;
+        for byte in &mut remainder {
+            let shifted = *byte >> 7;
+            *byte = *byte << 1 | carry;
+            carry = shifted;
+        }
+        if compare(&remainder, b).is_ge() {
+            sub_assign(&mut remainder, b);
+            quotient[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+    (quotient, remainder)
+}
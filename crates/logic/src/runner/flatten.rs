@@ -0,0 +1,195 @@
+//! Expansion of the composite blocks into the blocks they are made of
+
+use digolog_module_loader::*;
+
+/// A netlist without composite blocks
+pub(crate) struct FlatNetlist<'a> {
+    /// The blocks that are not composite,
+    /// with the index of the outermost block that contains them.
+    pub blocks: Vec<(usize, &'a Block)>,
+    /// Wires between `blocks` and the ports of the netlist
+    pub wires: Vec<Wire>,
+    /// The flat block pin or the netlist port that drives each output of each netlist block
+    pub block_outputs: Vec<Vec<Option<PinRef>>>,
+}
+
+/// A block of the netlist that is being flattened
+enum Nested {
+    /// Index in `FlatNetlist::blocks`
+    Flat(usize),
+    /// Position in `FlatNetlist::blocks` of the first block of the composite
+    /// and the wires of its flattened netlist
+    Composite { offset: usize, wires: Vec<Wire> },
+}
+
+/// Replaces each composite block with the blocks it is made of.
+/// The blocks are searched by name in `blocks`.
+///
+/// # Panic
+/// If a composite block contains itself.
+pub(crate) fn flatten<'a>(
+    netlist_blocks: &'a [Block],
+    wires: &[Wire],
+    blocks: &'a ModuleBlocks,
+) -> FlatNetlist<'a> {
+    flatten_nested(netlist_blocks, wires, blocks, &mut Vec::new())
+}
+
+fn flatten_nested<'a>(
+    netlist_blocks: &'a [Block],
+    wires: &[Wire],
+    blocks: &'a ModuleBlocks,
+    parents: &mut Vec<&'a BlockDescId>,
+) -> FlatNetlist<'a> {
+    let mut flat = FlatNetlist {
+        blocks: Vec::with_capacity(netlist_blocks.len()),
+        wires: Vec::with_capacity(wires.len()),
+        block_outputs: Vec::with_capacity(netlist_blocks.len()),
+    };
+
+    let mut nested = Vec::with_capacity(netlist_blocks.len());
+    for (block_index, block) in netlist_blocks.iter().enumerate() {
+        let desc_id = &block.shape.description;
+        match blocks.get(&desc_id.name).and_then(|d| d.logic.as_ref()) {
+            Some(BlockLogic::Composite(composite)) => {
+                assert!(
+                    !parents.contains(&desc_id),
+                    "The composite block {desc_id:?} contains itself"
+                );
+                parents.push(desc_id);
+                let inner = flatten_nested(&composite.blocks, &composite.wires, blocks, parents);
+                parents.pop();
+
+                let offset = flat.blocks.len();
+                flat.blocks
+                    .extend(inner.blocks.iter().map(|&(_, block)| (block_index, block)));
+                nested.push(Nested::Composite {
+                    offset,
+                    wires: inner.wires,
+                });
+            }
+            _ => {
+                flat.blocks.push((block_index, block));
+                nested.push(Nested::Flat(flat.blocks.len() - 1));
+            }
+        }
+    }
+
+    let netlist = Netlist {
+        nested: &nested,
+        wires,
+    };
+
+    for wire in wires {
+        let PinRef::Block { block, pin } = wire.sink else {
+            if let Some(source) = netlist.resolve_source(wire.source, 0) {
+                flat.wires.push(Wire {
+                    source,
+                    sink: wire.sink,
+                });
+            }
+            continue;
+        };
+        // The sinks inside of composite blocks are connected below
+        if let Nested::Flat(flat_block) = nested[block] {
+            if let Some(source) = netlist.resolve_source(wire.source, 0) {
+                flat.wires.push(Wire {
+                    source,
+                    sink: PinRef::Block {
+                        block: flat_block,
+                        pin,
+                    },
+                });
+            }
+        }
+    }
+
+    for (block, nested_block) in nested.iter().enumerate() {
+        let Nested::Composite { offset, wires } = nested_block else {
+            continue;
+        };
+        for wire in wires {
+            // The outputs of the composite are connected by `resolve_source`
+            let PinRef::Block { block: sink, pin } = wire.sink else {
+                continue;
+            };
+            let source = match wire.source {
+                PinRef::Block { block, pin } => Some(PinRef::Block {
+                    block: offset + block,
+                    pin,
+                }),
+                PinRef::Chapter { port } => netlist.resolve_driver(block, port, 0),
+            };
+            if let Some(source) = source {
+                flat.wires.push(Wire {
+                    source,
+                    sink: PinRef::Block {
+                        block: offset + sink,
+                        pin,
+                    },
+                });
+            }
+        }
+    }
+
+    for (block_index, block) in netlist_blocks.iter().enumerate() {
+        let outputs = (0..block.shape.outputs.len())
+            .map(|pin| {
+                let source = PinRef::Block {
+                    block: block_index,
+                    pin,
+                };
+                netlist.resolve_source(source, 0)
+            })
+            .collect();
+        flat.block_outputs.push(outputs);
+    }
+
+    flat
+}
+
+struct Netlist<'a> {
+    nested: &'a [Nested],
+    wires: &'a [Wire],
+}
+
+impl Netlist<'_> {
+    /// Finds the flat block pin or the netlist port that drives `source`.
+    /// Returns `None` if nothing drives it.
+    ///
+    /// `depth` counts the composite blocks that connect an input to an output,
+    /// to stop if they are wired in a loop.
+    fn resolve_source(&self, source: PinRef, depth: usize) -> Option<PinRef> {
+        let PinRef::Block { block, pin } = source else {
+            return Some(source);
+        };
+        match &self.nested[block] {
+            Nested::Flat(flat_block) => Some(PinRef::Block {
+                block: *flat_block,
+                pin,
+            }),
+            Nested::Composite { offset, wires } => {
+                let inner = wires
+                    .iter()
+                    .find(|w| w.sink == PinRef::Chapter { port: pin })?;
+                match inner.source {
+                    PinRef::Block { block, pin } => Some(PinRef::Block {
+                        block: offset + block,
+                        pin,
+                    }),
+                    PinRef::Chapter { port } => self.resolve_driver(block, port, depth + 1),
+                }
+            }
+        }
+    }
+
+    /// Finds the flat block pin or the netlist port that drives the input `pin` of `block`
+    fn resolve_driver(&self, block: usize, pin: usize, depth: usize) -> Option<PinRef> {
+        if depth > self.wires.len() {
+            return None;
+        }
+        let sink = PinRef::Block { block, pin };
+        let wire = self.wires.iter().find(|wire| wire.sink == sink)?;
+        self.resolve_source(wire.source, depth)
+    }
+}
@@ -0,0 +1,49 @@
+//! Evaluation of the logic gates on cables stored as bytes
+
+use super::*;
+
+/// Evaluates `gate` with `inputs` laid out as [`BlockInput`],
+/// where `input_wires` are the wires of each input cable.
+pub(super) fn evaluate_gate(
+    gate: Gate,
+    inputs: &[u8],
+    input_wires: impl Iterator<Item = u8> + Clone,
+    output: &mut [u8],
+) {
+    let cables = || {
+        input_wires.clone().scan(0, |offset, wires| {
+            let cable = &inputs[*offset..][..cable_bytes(wires)];
+            *offset += cable.len();
+            Some(cable)
+        })
+    };
+    let byte = |cable: &[u8], i: usize| cable.get(i).copied().unwrap_or(0);
+
+    match gate {
+        Gate::Not => {
+            let cable = cables().next().unwrap_or(&[]);
+            for (i, out) in output.iter_mut().enumerate() {
+                *out = !byte(cable, i);
+            }
+        }
+        Gate::And | Gate::Or | Gate::XOr => {
+            for (i, out) in output.iter_mut().enumerate() {
+                let mut bytes = cables().map(|cable| byte(cable, i));
+                *out = match gate {
+                    Gate::And => bytes.fold(!0, |a, b| a & b),
+                    Gate::Or => bytes.fold(0, |a, b| a | b),
+                    _ => bytes.fold(0, |a, b| a ^ b),
+                };
+            }
+        }
+        Gate::Equal => {
+            let first = cables().next().unwrap_or(&[]);
+            let equal = cables().all(|cable| {
+                let len = cable.len().max(first.len());
+                (0..len).all(|i| byte(cable, i) == byte(first, i))
+            });
+            output.fill(0);
+            output[0] = equal as u8;
+        }
+    }
+}
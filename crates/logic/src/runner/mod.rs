@@ -1,27 +1,535 @@
+mod arithmetic;
+mod flatten;
+mod gate;
+mod probe;
+mod program;
+mod snapshot;
+mod wasm;
+mod watch;
+
 use crate::*;
-use derive_more::*;
+pub(crate) use arithmetic::*;
+use digolog_module_loader::*;
+pub(crate) use flatten::*;
+use gate::*;
+pub use probe::*;
+pub use program::*;
+pub use snapshot::*;
+pub use wasm::*;
+pub use watch::*;
 
+/// Simulates a solution of a chapter.
+///
+/// The simulation advances in steps. On each step, the evaluated blocks read the
+/// outputs of the previous step, so all the blocks behave as if they had a delay of one step.
+/// A tick of the clock updates the state of the sequential blocks, and lets the signals settle.
 pub struct ChapterRunner {
-    chapter: Chapter,
+    program: ChapterProgram,
+    /// Logic of each operation of the program
+    logic: Vec<OpLogic>,
+    /// Instances of the blocks written in WebAssembly
+    wasm: Vec<WasmBlock>,
+    /// Blocks that have stopped working
+    faults: Vec<BlockFault>,
+    scheduling: Scheduling,
 
     /// Used to generate new outputs.
-    /// It should only be when a tick is completed.
+    /// It should only be written when a step is completed.
     old_outputs: Vec<u8>,
 
     /// A buffer to store the new state.
-    /// It should only be read to move the data to `old_outputs` when a tick is completed.
+    /// It should only be read to move the data to `old_outputs` when a step is completed.
     new_outputs: Vec<u8>,
+
+    /// State of the sequential blocks
+    states: Vec<u8>,
+
+    /// Buffer where the inputs of a block are gathered before evaluating it
+    input_scratch: Vec<u8>,
+
+    /// Operations to evaluate on the next event driven step
+    active_ops: Vec<usize>,
+    /// `is_active[op]` is true if the operation is in `active_ops`
+    is_active: Vec<bool>,
+
+    /// Number of ticks of the clock since the start
+    ticks: u64,
+
+    /// Maximum number of steps that `settle` does before giving up
+    pub settle_limit: usize,
+    /// Conditions that stop [`ChapterRunner::run_until`]
+    pub watches: Vec<Watch>,
 }
 
-pub struct BlockPanelIndex(usize);
+/// How the runner chooses which blocks to evaluate on each step.
+/// All the modes give the same results once the signals have settled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Scheduling {
+    /// Evaluate all the blocks on every step.
+    FullSweep,
+    /// Evaluate only the blocks with an input that changed on the previous step.
+    #[default]
+    EventDriven,
+    /// Evaluate all the blocks in dependency order,
+    /// so the signals settle in a single step.
+    ///
+    /// Only available if the program [is levelized](ChapterProgram::is_levelized).
+    Levelized,
+}
 
-#[derive(Deref, DerefMut)]
-pub struct BlockOutputMut<'a>(&'a mut [u8]);
+/// The signals that have not settled, usually because of a combinational loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsettled {
+    /// Blocks with an output that keeps changing
+    pub blocks: Vec<usize>,
+    /// Block outputs that keep changing
+    pub pins: Vec<PinRef>,
+}
 
-#[derive(Deref)]
-pub struct BlockInput<'a>(&'a [u8]);
+impl Unsettled {
+    /// Index in `solution.wires` of the wires driven by an output that keeps changing
+    pub fn wires(&self, solution: &ChapterSolution) -> Vec<usize> {
+        (solution.wires.iter().enumerate())
+            .filter(|(_, wire)| self.pins.contains(&wire.source))
+            .map(|(wire_index, _)| wire_index)
+            .collect()
+    }
+}
+
+/// A block that has failed while running, so its outputs are 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockFault {
+    /// Index of the block in the solution,
+    /// or of the composite block that contains it
+    pub block: usize,
+    pub error: WasmError,
+}
 
-pub enum BlockLogic {
-    /// A stateless rust function. (example: Logic Gates)
+#[derive(Copy, Clone)]
+pub(crate) enum OpLogic {
     Builtin(fn(BlockInput, BlockOutputMut)),
+    Gate(Gate),
+    Arithmetic(Arithmetic),
+    Sequential {
+        output: fn(BlockState, BlockOutputMut),
+        update: fn(BlockInput, BlockStateMut),
+    },
+    /// Index of the instance of the WebAssembly module
+    Wasm(usize),
+    /// A block that has failed, it outputs 0
+    Faulted,
+}
+
+/// Finds the logic of each operation of `program`,
+/// and instantiates the WebAssembly modules that it uses.
+/// The logic of each block is searched by name in `blocks`.
+///
+/// # Panic
+/// If a block has no logic.
+pub(crate) fn link(
+    program: &ChapterProgram,
+    blocks: &ModuleBlocks,
+) -> (Vec<OpLogic>, Vec<WasmBlock>) {
+    let mut wasm = Vec::new();
+    let desc_logic: Vec<OpLogic> = (program.descs.iter())
+        .map(
+            |desc_id| match blocks.get(&desc_id.name).and_then(|d| d.logic.as_ref()) {
+                Some(BlockLogic::Builtin(logic)) => OpLogic::Builtin(*logic),
+                Some(BlockLogic::Gate(gate)) => OpLogic::Gate(*gate),
+                Some(BlockLogic::Arithmetic(arithmetic)) => OpLogic::Arithmetic(*arithmetic),
+                Some(BlockLogic::Sequential { output, update, .. }) => OpLogic::Sequential {
+                    output: *output,
+                    update: *update,
+                },
+                Some(BlockLogic::Wasm(logic)) => {
+                    wasm.push(WasmBlock::new(logic));
+                    OpLogic::Wasm(wasm.len() - 1)
+                }
+                Some(BlockLogic::Composite(_)) => unreachable!("The program is flattened"),
+                None => panic!("The block {:?} has no logic", desc_id),
+            },
+        )
+        .collect();
+
+    let logic = program.ops.iter().map(|op| desc_logic[op.desc]).collect();
+    (logic, wasm)
+}
+
+impl ChapterRunner {
+    /// Prepares the simulation of `solution`.
+    /// The logic of each block is searched by name in `blocks`.
+    ///
+    /// # Panic
+    /// If a wire refers to a missing pin, or a block has no logic.
+    pub fn new(chapter: &Chapter, solution: &ChapterSolution, blocks: &ModuleBlocks) -> Self {
+        Self::from_program(ChapterProgram::compile(chapter, solution, blocks), blocks)
+    }
+
+    /// Prepares the simulation of a compiled solution.
+    /// The logic of each block is searched by name in `blocks`.
+    ///
+    /// # Panic
+    /// If a block has no logic.
+    pub fn from_program(program: ChapterProgram, blocks: &ModuleBlocks) -> Self {
+        let op_count = program.ops.len();
+        let (logic, wasm) = link(&program, blocks);
+        Self {
+            logic,
+            wasm,
+            faults: Vec::new(),
+            old_outputs: vec![0; program.buffer_size],
+            new_outputs: vec![0; program.buffer_size],
+            states: vec![0; program.state_size],
+            input_scratch: Vec::new(),
+            active_ops: (0..op_count).collect(),
+            is_active: vec![true; op_count],
+            scheduling: Scheduling::default(),
+            ticks: 0,
+            settle_limit: 1000,
+            watches: Vec::new(),
+            program,
+        }
+    }
+
+    pub fn program(&self) -> &ChapterProgram {
+        &self.program
+    }
+
+    pub fn scheduling(&self) -> Scheduling {
+        self.scheduling
+    }
+
+    /// # Panic
+    /// If the scheduling is `Levelized` and the program is not levelized.
+    pub fn set_scheduling(&mut self, scheduling: Scheduling) {
+        if scheduling == Scheduling::Levelized {
+            assert!(
+                self.program.is_levelized(),
+                "A solution with a combinational loop can not be levelized"
+            );
+        }
+        if scheduling == Scheduling::EventDriven && self.scheduling != scheduling {
+            // The other modes do not track changes, so everything could be outdated.
+            for op in 0..self.program.ops.len() {
+                self.activate(op);
+            }
+        }
+        self.scheduling = scheduling;
+    }
+
+    /// Sets the value of a chapter input. It will be seen by the blocks on the next step.
+    pub fn set_input(&mut self, port: usize, value: &[u8]) {
+        let slot_id = self.program.inputs[port];
+        let slot = self.program.slots[slot_id];
+        let range = slot.range();
+
+        let mut cable = vec![0; slot.bytes()];
+        let len = value.len().min(cable.len());
+        cable[..len].copy_from_slice(&value[..len]);
+        mask_cable(&mut cable, slot.wires);
+
+        if self.old_outputs[range.clone()] != cable[..] {
+            self.old_outputs[range.clone()].copy_from_slice(&cable);
+            self.new_outputs[range].copy_from_slice(&cable);
+            self.activate_fanout(slot_id);
+        }
+    }
+
+    /// Value of a chapter output after the last step
+    pub fn output(&self, port: usize) -> &[u8] {
+        self.slot(self.program.outputs[port])
+    }
+
+    /// Value of a chapter input
+    pub fn input(&self, port: usize) -> &[u8] {
+        self.slot(self.program.inputs[port])
+    }
+
+    /// Value of an output pin of a solution block.
+    /// Returns `None` for an output of a composite block that is not driven.
+    pub fn block_output(&self, block: usize, pin: usize) -> Option<&[u8]> {
+        self.program.block_outputs[block][pin].map(|slot| self.slot(slot))
+    }
+
+    fn slot(&self, slot: usize) -> &[u8] {
+        &self.old_outputs[self.program.slots[slot].range()]
+    }
+
+    /// Evaluates the blocks once.
+    /// Returns true if any signal has changed.
+    pub fn step(&mut self) -> bool {
+        match self.scheduling {
+            Scheduling::FullSweep => self.step_full_sweep(),
+            Scheduling::EventDriven => self.step_event_driven(),
+            Scheduling::Levelized => self.step_levelized(),
+        }
+    }
+
+    /// Number of ticks of the clock since the start
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Blocks that have failed. Their outputs are 0 from the step where they failed.
+    pub fn faults(&self) -> &[BlockFault] {
+        &self.faults
+    }
+
+    /// Steps until the signals stop changing.
+    ///
+    /// If they have not settled after `settle_limit` steps,
+    /// it keeps stepping to find the outputs that do not settle.
+    pub fn settle(&mut self) -> Result<(), Unsettled> {
+        if self.scheduling == Scheduling::Levelized {
+            self.step_levelized();
+            return Ok(());
+        }
+        for _ in 0..self.settle_limit {
+            if !self.step() {
+                return Ok(());
+            }
+        }
+        Err(self.find_unsettled())
+    }
+
+    /// Advances the clock: the signals settle, the sequential blocks update their state
+    /// from their inputs, and the signals settle again.
+    pub fn tick(&mut self) -> Result<(), Unsettled> {
+        self.settle()?;
+        self.update_states();
+        self.ticks += 1;
+        self.settle()
+    }
+
+    /// Steps `settle_limit` times, and returns the outputs that have changed
+    fn find_unsettled(&mut self) -> Unsettled {
+        let mut changed = vec![false; self.program.slots.len()];
+        let mut previous = self.old_outputs.clone();
+
+        for _ in 0..self.settle_limit {
+            self.step();
+            for (slot_id, slot) in self.program.slots.iter().enumerate() {
+                if previous[slot.range()] != self.old_outputs[slot.range()] {
+                    changed[slot_id] = true;
+                }
+            }
+            previous.copy_from_slice(&self.old_outputs);
+        }
+
+        let mut unsettled = Unsettled {
+            blocks: Vec::new(),
+            pins: Vec::new(),
+        };
+        for op in &self.program.ops {
+            if op.outputs.clone().any(|slot| changed[slot]) {
+                unsettled.blocks.push(op.block);
+            }
+        }
+        unsettled.blocks.sort();
+        unsettled.blocks.dedup();
+
+        for (block, pins) in self.program.block_outputs.iter().enumerate() {
+            for (pin, slot) in pins.iter().enumerate() {
+                if slot.is_some_and(|slot| changed[slot]) {
+                    unsettled.pins.push(PinRef::Block { block, pin });
+                }
+            }
+        }
+        unsettled
+    }
+
+    fn step_full_sweep(&mut self) -> bool {
+        for op in 0..self.program.ops.len() {
+            self.evaluate(op);
+        }
+
+        let changed = self.old_outputs != self.new_outputs;
+        std::mem::swap(&mut self.old_outputs, &mut self.new_outputs);
+        changed
+    }
+
+    fn step_event_driven(&mut self) -> bool {
+        let mut active_ops = std::mem::take(&mut self.active_ops);
+
+        for &op in &active_ops {
+            self.is_active[op] = false;
+            self.evaluate(op);
+        }
+
+        // The new outputs are only visible once all the active blocks are evaluated
+        let mut changed = false;
+        for &op in &active_ops {
+            changed |= self.apply_outputs(op);
+        }
+
+        // Reuse the allocation if nothing has been activated while applying the changes
+        if self.active_ops.is_empty() {
+            active_ops.clear();
+            self.active_ops = active_ops;
+        }
+
+        changed
+    }
+
+    /// The operations are sorted by level, so the new outputs can be applied immediately
+    fn step_levelized(&mut self) -> bool {
+        let mut changed = false;
+        for op in 0..self.program.ops.len() {
+            self.evaluate(op);
+            changed |= self.apply_outputs(op);
+        }
+        changed
+    }
+
+    /// Moves the outputs of `op` from `new_outputs` to `old_outputs`.
+    /// Returns true if they have changed.
+    fn apply_outputs(&mut self, op: usize) -> bool {
+        let mut changed = false;
+        for slot_id in self.program.ops[op].outputs.clone() {
+            let range = self.program.slots[slot_id].range();
+            if self.old_outputs[range.clone()] != self.new_outputs[range.clone()] {
+                self.old_outputs[range.clone()].copy_from_slice(&self.new_outputs[range]);
+                self.activate_fanout(slot_id);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Updates the state of the sequential blocks from their inputs in `old_outputs`
+    fn update_states(&mut self) {
+        let mut old_state = Vec::new();
+        for op in 0..self.program.ops.len() {
+            let Some(state_range) = self.program.ops[op].state.clone() else {
+                continue;
+            };
+
+            self.gather_inputs(op);
+            old_state.clear();
+            old_state.extend_from_slice(&self.states[state_range.clone()]);
+
+            let state = &mut self.states[state_range.clone()];
+            match self.logic[op] {
+                OpLogic::Sequential { update, .. } => update(
+                    BlockInput::from(&self.input_scratch[..]),
+                    BlockStateMut::from(&mut *state),
+                ),
+                OpLogic::Wasm(wasm) => {
+                    if let Err(error) = self.wasm[wasm].update(&self.input_scratch, state) {
+                        self.fault(op, error);
+                    }
+                }
+                _ => {}
+            }
+
+            if old_state[..] != self.states[state_range] {
+                self.activate(op);
+            }
+        }
+    }
+
+    /// Writes the inputs of `op` in `input_scratch` from `old_outputs`
+    fn gather_inputs(&mut self, op: usize) {
+        self.input_scratch.clear();
+        for input in &self.program.ops[op].inputs {
+            let start = self.input_scratch.len();
+            self.input_scratch
+                .resize(start + cable_bytes(input.wires), 0);
+
+            if let Some(source) = input.source {
+                let source = self.program.slots[source];
+                let cable = &mut self.input_scratch[start..];
+                let len = cable.len().min(source.bytes());
+                cable[..len].copy_from_slice(&self.old_outputs[source.offset..][..len]);
+                mask_cable(cable, input.wires);
+            }
+        }
+    }
+
+    /// Writes the outputs of `op` in `new_outputs` from the inputs in `old_outputs`
+    fn evaluate(&mut self, op: usize) {
+        let bytes = self.program.ops[op].bytes.clone();
+
+        match self.logic[op] {
+            OpLogic::Builtin(logic) => {
+                self.gather_inputs(op);
+                logic(
+                    BlockInput::from(&self.input_scratch[..]),
+                    BlockOutputMut::from(&mut self.new_outputs[bytes.clone()]),
+                );
+            }
+            OpLogic::Gate(gate) => {
+                self.gather_inputs(op);
+                evaluate_gate(
+                    gate,
+                    &self.input_scratch,
+                    self.program.ops[op].inputs.iter().map(|input| input.wires),
+                    &mut self.new_outputs[bytes.clone()],
+                );
+            }
+            OpLogic::Arithmetic(arithmetic) => {
+                self.gather_inputs(op);
+                let op = &self.program.ops[op];
+                evaluate_arithmetic(
+                    arithmetic,
+                    &self.input_scratch,
+                    op.inputs.iter().map(|input| input.wires),
+                    &mut self.new_outputs[bytes.clone()],
+                    (op.outputs.clone()).map(|slot| self.program.slots[slot].wires),
+                );
+            }
+            OpLogic::Sequential { output, .. } => {
+                let state_range = self.program.ops[op].state.clone().unwrap();
+                output(
+                    BlockState::from(&self.states[state_range]),
+                    BlockOutputMut::from(&mut self.new_outputs[bytes.clone()]),
+                );
+            }
+            OpLogic::Wasm(wasm) => {
+                let state = self.program.ops[op].state.clone();
+                if state.is_none() {
+                    self.gather_inputs(op);
+                }
+                let outputs = &mut self.new_outputs[bytes.clone()];
+                let result = match state {
+                    Some(state) => self.wasm[wasm].output(&self.states[state], outputs),
+                    None => self.wasm[wasm].evaluate(&self.input_scratch, outputs),
+                };
+                if let Err(error) = result {
+                    self.fault(op, error);
+                }
+            }
+            OpLogic::Faulted => self.new_outputs[bytes.clone()].fill(0),
+        }
+
+        let outputs = &mut self.new_outputs[bytes.clone()];
+        for slot in &self.program.slots[self.program.ops[op].outputs.clone()] {
+            let offset = slot.offset - bytes.start;
+            mask_cable(&mut outputs[offset..][..slot.bytes()], slot.wires);
+        }
+    }
+
+    /// Stops evaluating a block that has failed
+    fn fault(&mut self, op: usize, error: WasmError) {
+        self.logic[op] = OpLogic::Faulted;
+        self.new_outputs[self.program.ops[op].bytes.clone()].fill(0);
+        self.faults.push(BlockFault {
+            block: self.program.ops[op].block,
+            error,
+        });
+        self.activate(op);
+    }
+
+    fn activate(&mut self, op: usize) {
+        if !self.is_active[op] {
+            self.is_active[op] = true;
+            self.active_ops.push(op);
+        }
+    }
+
+    fn activate_fanout(&mut self, slot: usize) {
+        for i in 0..self.program.fanout[slot].len() {
+            self.activate(self.program.fanout[slot][i]);
+        }
+    }
 }
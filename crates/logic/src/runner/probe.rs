@@ -0,0 +1,75 @@
+//! References to the cables of a solution, to read them while running
+
+use super::*;
+
+/// A cable of a solution that can be read while running
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Probe {
+    /// A chapter input
+    Input(usize),
+    /// A chapter output
+    Output(usize),
+    /// An output pin of a solution block
+    Block { block: usize, pin: usize },
+}
+
+impl Probe {
+    /// The cable carried by a wire of the solution
+    pub fn from_wire(wire: &Wire) -> Self {
+        match wire.source {
+            PinRef::Chapter { port } => Probe::Input(port),
+            PinRef::Block { block, pin } => Probe::Block { block, pin },
+        }
+    }
+
+    /// All the chapter ports and the outputs of all the solution blocks
+    pub fn all(chapter: &Chapter, solution: &ChapterSolution) -> Vec<Probe> {
+        let inputs = (0..chapter.inputs.len()).map(Probe::Input);
+        let outputs = (0..chapter.outputs.len()).map(Probe::Output);
+        let blocks = solution.blocks.iter().enumerate().flat_map(|(block, b)| {
+            (0..b.shape.outputs.len()).map(move |pin| Probe::Block { block, pin })
+        });
+        inputs.chain(outputs).chain(blocks).collect()
+    }
+
+    /// Finds a cable by name.
+    ///
+    /// A chapter port is named by its lable, and the outputs are searched before the inputs.
+    /// A block output is named `block.pin`, where `block` is the lable of the block
+    /// or `lable_index` if many blocks have the same lable, and `pin` is the lable
+    /// of the output cable or `out` followed by its number.
+    pub fn find(chapter: &Chapter, solution: &ChapterSolution, name: &str) -> Option<Probe> {
+        let Some((block_name, pin_name)) = name.rsplit_once('.') else {
+            let port = |ports: &[BlockCable]| ports.iter().position(|port| port.lable == name);
+            return (port(&chapter.outputs).map(Probe::Output))
+                .or_else(|| port(&chapter.inputs).map(Probe::Input));
+        };
+
+        let block = solution
+            .blocks
+            .iter()
+            .enumerate()
+            .position(|(index, block)| {
+                block.shape.lable == block_name
+                    || format!("{}_{index}", block.shape.lable) == block_name
+            })?;
+        let outputs = &solution.blocks[block].shape.outputs;
+        let pin = (outputs.iter().position(|output| output.lable == pin_name)).or_else(|| {
+            let pin = pin_name.strip_prefix("out")?.parse().ok()?;
+            (pin < outputs.len()).then_some(pin)
+        })?;
+        Some(Probe::Block { block, pin })
+    }
+}
+
+impl ChapterRunner {
+    /// Value of a cable.
+    /// Returns `None` for an output of a composite block that is not driven.
+    pub fn probe(&self, probe: Probe) -> Option<&[u8]> {
+        match probe {
+            Probe::Input(port) => Some(self.input(port)),
+            Probe::Output(port) => Some(self.output(port)),
+            Probe::Block { block, pin } => self.block_output(block, pin),
+        }
+    }
+}
@@ -0,0 +1,409 @@
+//! Compilation of a solution into a flat list of operations.
+//!
+//! The program knows where each signal is stored in the runner buffers,
+//! so the runner does not need to look at the blocks and wires while running.
+
+use super::flatten::*;
+use super::WasmBlock;
+use digolog_module_loader::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::DefaultHasher, HashMap};
+use std::fs::{read_to_string, write};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// A solution compiled to be run by a [`ChapterRunner`](crate::ChapterRunner).
+///
+/// It does not contain the block logic, so it can be cached alongside the solution
+/// and linked again with the module blocks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterProgram {
+    /// Hash of the chapter ports and the blocks and wires of the flattened solution.
+    /// It is signed because TOML integers are.
+    fingerprint: i64,
+    pub(crate) slots: Vec<CableSlot>,
+    /// Operations that read each slot
+    pub(crate) fanout: Vec<Vec<usize>>,
+    /// Operations ordered by level, if the solution has no combinational loops
+    pub(crate) ops: Vec<ProgramOp>,
+    /// Block descriptions used by the operations
+    pub(crate) descs: Vec<BlockDescId>,
+    /// Range of `ops` of each level.
+    /// The operations of a level only read the outputs of previous levels.
+    ///
+    /// It is `None` if the solution has a combinational loop.
+    pub(crate) levels: Option<Vec<Range<usize>>>,
+    /// Slot of each chapter input
+    pub(crate) inputs: Vec<usize>,
+    /// Slot that drives each chapter output.
+    /// A not connected output has its own slot that is never written.
+    pub(crate) outputs: Vec<usize>,
+    /// Slot of each output pin of each solution block.
+    /// It is `None` for the outputs of a composite block that are not driven.
+    #[serde(with = "optional_slots")]
+    pub(crate) block_outputs: Vec<Vec<Option<usize>>>,
+    pub(crate) buffer_size: usize,
+    pub(crate) state_size: usize,
+}
+
+/// The evaluation of a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ProgramOp {
+    /// Index of the block in the solution,
+    /// or of the composite block that contains it
+    pub block: usize,
+    /// Index of the block description in `ChapterProgram::descs`
+    pub desc: usize,
+    pub inputs: Vec<ProgramInput>,
+    /// Slots of the output cables. They are contiguous in the buffers.
+    pub outputs: Range<usize>,
+    /// Bytes of the buffers used by the outputs
+    pub bytes: Range<usize>,
+    /// Bytes of the state buffer used by a sequential block
+    pub state: Option<Range<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ProgramInput {
+    /// Slot that drives the input, `None` if it is not connected.
+    pub source: Option<usize>,
+    pub wires: u8,
+}
+
+/// A cable stored in the signal buffers
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(crate) struct CableSlot {
+    /// Position of the first byte in the buffers
+    pub offset: usize,
+    pub wires: u8,
+}
+
+impl CableSlot {
+    pub fn bytes(self) -> usize {
+        cable_bytes(self.wires)
+    }
+
+    pub fn range(self) -> Range<usize> {
+        self.offset..self.offset + self.bytes()
+    }
+}
+
+/// Number of bytes used to store a cable
+pub(crate) fn cable_bytes(wires: u8) -> usize {
+    (wires as usize).div_ceil(8).max(1)
+}
+
+/// Sets to 0 the bits of the cable that are not wires
+pub(crate) fn mask_cable(cable: &mut [u8], wires: u8) {
+    let bytes = cable_bytes(wires);
+    for byte in &mut cable[bytes..] {
+        *byte = 0;
+    }
+    let last_wires = wires % 8;
+    if last_wires != 0 {
+        cable[bytes - 1] &= (1 << last_wires) - 1;
+    } else if wires == 0 {
+        cable[0] = 0;
+    }
+}
+
+impl ChapterProgram {
+    /// Compiles `solution`. The blocks are searched by name in `blocks`.
+    ///
+    /// The composite blocks are replaced by the blocks they are made of.
+    ///
+    /// # Panic
+    /// If a wire refers to a missing pin, a block has no logic,
+    /// or a composite block contains itself.
+    pub fn compile(chapter: &Chapter, solution: &ChapterSolution, blocks: &ModuleBlocks) -> Self {
+        let netlist = flatten(&solution.blocks, &solution.wires, blocks);
+        let mut program = ChapterProgram {
+            fingerprint: fingerprint(chapter, &netlist),
+            slots: Vec::new(),
+            fanout: Vec::new(),
+            ops: Vec::with_capacity(netlist.blocks.len()),
+            descs: Vec::new(),
+            levels: None,
+            inputs: Vec::with_capacity(chapter.inputs.len()),
+            outputs: Vec::with_capacity(chapter.outputs.len()),
+            block_outputs: Vec::with_capacity(solution.blocks.len()),
+            buffer_size: 0,
+            state_size: 0,
+        };
+
+        for port in &chapter.inputs {
+            let slot = program.push_slot(port.wires);
+            program.inputs.push(slot);
+        }
+
+        // Instances of the WebAssembly modules, to ask them the state of each shape
+        let mut wasm_blocks: HashMap<&str, WasmBlock> = HashMap::new();
+
+        for &(block_index, block) in &netlist.blocks {
+            let desc_id = &block.shape.description;
+            let state_bytes = match blocks.get(&desc_id.name).and_then(|d| d.logic.as_ref()) {
+                Some(BlockLogic::Builtin(_) | BlockLogic::Gate(_) | BlockLogic::Arithmetic(_)) => {
+                    None
+                }
+                Some(BlockLogic::Sequential { state_bytes, .. }) => Some(state_bytes(&block.shape)),
+                // A module that fails here also fails when it is evaluated, and it is reported then
+                Some(BlockLogic::Wasm(logic)) => (wasm_blocks.entry(&desc_id.name))
+                    .or_insert_with(|| WasmBlock::new(logic))
+                    .state_bytes(&block.shape)
+                    .unwrap_or(None),
+                Some(BlockLogic::Composite(_)) => unreachable!("The netlist is flattened"),
+                None => panic!("The block {:?} has no logic", desc_id),
+            };
+
+            let desc = match program.descs.iter().position(|d| d == desc_id) {
+                Some(desc) => desc,
+                None => {
+                    program.descs.push(desc_id.clone());
+                    program.descs.len() - 1
+                }
+            };
+
+            let first_slot = program.slots.len();
+            let first_byte = program.buffer_size;
+            for output in &block.shape.outputs {
+                program.push_slot(output.wires);
+            }
+
+            let state = state_bytes.map(|bytes| {
+                program.state_size += bytes;
+                program.state_size - bytes..program.state_size
+            });
+
+            program.ops.push(ProgramOp {
+                block: block_index,
+                desc,
+                inputs: (block.shape.inputs.iter())
+                    .map(|input| ProgramInput {
+                        source: None,
+                        wires: input.wires,
+                    })
+                    .collect(),
+                outputs: first_slot..program.slots.len(),
+                bytes: first_byte..program.buffer_size,
+                state,
+            });
+        }
+
+        // Ops are still in the order of the flat blocks
+        let source_slot = |program: &ChapterProgram, source: PinRef| match source {
+            PinRef::Chapter { port } => program.inputs[port],
+            PinRef::Block { block, pin } => {
+                let slot = program.ops[block].outputs.start + pin;
+                assert!(
+                    slot < program.ops[block].outputs.end,
+                    "Invalid pin {source:?}"
+                );
+                slot
+            }
+        };
+
+        let mut outputs = vec![None; chapter.outputs.len()];
+        for wire in &netlist.wires {
+            let source = source_slot(&program, wire.source);
+            match wire.sink {
+                PinRef::Chapter { port } => outputs[port] = Some(source),
+                PinRef::Block { block, pin } => {
+                    program.ops[block].inputs[pin].source = Some(source)
+                }
+            }
+        }
+
+        for (port, source) in chapter.outputs.iter().zip(outputs) {
+            let slot = source.unwrap_or_else(|| program.push_slot(port.wires));
+            program.outputs.push(slot);
+        }
+
+        program.block_outputs = (netlist.block_outputs.iter())
+            .map(|pins| {
+                (pins.iter())
+                    .map(|source| source.map(|source| source_slot(&program, source)))
+                    .collect()
+            })
+            .collect();
+
+        program.levelize();
+        program.compute_fanout();
+        program
+    }
+
+    /// Loads a program saved with [`ChapterProgram::save`].
+    /// Returns `None` if it does not exist or it is invalid.
+    pub fn load(path: impl AsRef<Path>) -> Option<Self> {
+        let src = read_to_string(path).ok()?;
+        toml::from_str(&src).ok()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let program = (toml::to_string(self))
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        write(path, program)
+    }
+
+    /// Returns true if the program is the compilation of `solution`.
+    /// The position of the blocks is ignored.
+    ///
+    /// The composite blocks are searched by name in `blocks`,
+    /// so a change in the solution of a composite block is detected.
+    pub fn is_compiled_from(
+        &self,
+        chapter: &Chapter,
+        solution: &ChapterSolution,
+        blocks: &ModuleBlocks,
+    ) -> bool {
+        let netlist = flatten(&solution.blocks, &solution.wires, blocks);
+        self.fingerprint == fingerprint(chapter, &netlist)
+    }
+
+    /// Returns false if the solution has a combinational loop
+    pub fn is_levelized(&self) -> bool {
+        self.levels.is_some()
+    }
+
+    fn push_slot(&mut self, wires: u8) -> usize {
+        let slot = CableSlot {
+            offset: self.buffer_size,
+            wires,
+        };
+        self.buffer_size += slot.bytes();
+        self.slots.push(slot);
+        self.slots.len() - 1
+    }
+
+    /// Sorts the operations so that every operation is after the operations it depends on.
+    ///
+    /// The sequential blocks do not depend on their inputs, so they are on the first level
+    /// and they break the chains of dependencies.
+    fn levelize(&mut self) {
+        // Ops are still in the order of the flat blocks
+        let mut driver = vec![None; self.slots.len()];
+        for (op_index, op) in self.ops.iter().enumerate() {
+            for slot in op.outputs.clone() {
+                driver[slot] = Some(op_index);
+            }
+        }
+
+        let mut dependents = vec![Vec::new(); self.ops.len()];
+        let mut pending_dependencies = vec![0; self.ops.len()];
+        for (op_index, op) in self.ops.iter().enumerate() {
+            if op.state.is_some() {
+                continue;
+            }
+            for input in &op.inputs {
+                let Some(dependency) = input.source.and_then(|slot| driver[slot]) else {
+                    continue;
+                };
+                if self.ops[dependency].state.is_none() {
+                    dependents[dependency].push(op_index);
+                    pending_dependencies[op_index] += 1;
+                }
+            }
+        }
+
+        // Only the sequential blocks are on the first level,
+        // so every block is after the sequential blocks that it reads
+        let mut level: Vec<usize> = (self.ops.iter())
+            .map(|op| if op.state.is_some() { 0 } else { 1 })
+            .collect();
+        let mut ready: Vec<usize> = (0..self.ops.len())
+            .filter(|&op| pending_dependencies[op] == 0)
+            .collect();
+        let mut sorted = 0;
+
+        while let Some(op) = ready.pop() {
+            sorted += 1;
+            for &dependent in &dependents[op] {
+                level[dependent] = level[dependent].max(level[op] + 1);
+                pending_dependencies[dependent] -= 1;
+                if pending_dependencies[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+
+        if sorted != self.ops.len() {
+            return;
+        }
+
+        let mut ops: Vec<(usize, ProgramOp)> = level
+            .into_iter()
+            .zip(std::mem::take(&mut self.ops))
+            .collect();
+        ops.sort_by_key(|(level, _)| *level);
+
+        let mut levels: Vec<Range<usize>> = Vec::new();
+        for (op_index, (op_level, _)) in ops.iter().enumerate() {
+            match levels.last_mut() {
+                Some(range) if ops[range.start].0 == *op_level => {
+                    range.end = op_index + 1;
+                }
+                _ => levels.push(op_index..op_index + 1),
+            }
+        }
+        self.ops = ops.into_iter().map(|(_, op)| op).collect();
+        self.levels = Some(levels);
+    }
+
+    /// The sequential blocks are not in the fanout, as their outputs do not depend on the inputs
+    fn compute_fanout(&mut self) {
+        self.fanout = vec![Vec::new(); self.slots.len()];
+        for (op_index, op) in self.ops.iter().enumerate() {
+            if op.state.is_some() {
+                continue;
+            }
+            for source in op.inputs.iter().filter_map(|input| input.source) {
+                self.fanout[source].push(op_index);
+            }
+        }
+    }
+}
+
+fn fingerprint(chapter: &Chapter, netlist: &FlatNetlist) -> i64 {
+    let mut hasher = DefaultHasher::new();
+    chapter.inputs.hash(&mut hasher);
+    chapter.outputs.hash(&mut hasher);
+    for (block_index, block) in &netlist.blocks {
+        block_index.hash(&mut hasher);
+        block.shape.hash(&mut hasher);
+    }
+    netlist.wires.hash(&mut hasher);
+    hasher.finish() as i64
+}
+
+/// TOML has no `None`, so the outputs that are not driven are saved as -1
+mod optional_slots {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        block_outputs: &[Vec<Option<usize>>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let block_outputs: Vec<Vec<i64>> = (block_outputs.iter())
+            .map(|pins| {
+                (pins.iter())
+                    .map(|slot| slot.map_or(-1, |slot| slot as i64))
+                    .collect()
+            })
+            .collect();
+        block_outputs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<Option<usize>>>, D::Error> {
+        let block_outputs = Vec::<Vec<i64>>::deserialize(deserializer)?;
+        Ok((block_outputs.into_iter())
+            .map(|pins| {
+                (pins.into_iter())
+                    .map(|slot| usize::try_from(slot).ok())
+                    .collect()
+            })
+            .collect())
+    }
+}
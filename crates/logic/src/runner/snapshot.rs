@@ -0,0 +1,47 @@
+//! Copies of the state of a simulation, to go back in time
+
+use super::*;
+
+/// The signals and the state of the sequential blocks of a [`ChapterRunner`] at a tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub(crate) tick: u64,
+    pub(crate) outputs: Vec<u8>,
+    pub(crate) states: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+}
+
+impl ChapterRunner {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            tick: self.ticks,
+            outputs: self.old_outputs.clone(),
+            states: self.states.clone(),
+        }
+    }
+
+    /// Goes back (or forward) to the moment of the snapshot.
+    /// The failed blocks are not restored.
+    ///
+    /// # Panic
+    /// If the snapshot is from a runner of another program.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        assert_eq!(snapshot.outputs.len(), self.old_outputs.len());
+        assert_eq!(snapshot.states.len(), self.states.len());
+
+        self.ticks = snapshot.tick;
+        self.old_outputs.copy_from_slice(&snapshot.outputs);
+        self.new_outputs.copy_from_slice(&snapshot.outputs);
+        self.states.copy_from_slice(&snapshot.states);
+
+        // The snapshot may not be settled, so everything is evaluated again
+        for op in 0..self.program.ops.len() {
+            self.activate(op);
+        }
+    }
+}
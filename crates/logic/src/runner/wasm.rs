@@ -0,0 +1,209 @@
+//! Execution of the blocks written in WebAssembly.
+//!
+//! See [`WasmLogic`] for the functions that the modules export.
+
+use super::cable_bytes;
+use digolog_module_loader::*;
+use std::fmt;
+use wasmi::core::TrapCode;
+use wasmi::{Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+/// Fuel that each call to a block can consume.
+/// Roughly the number of WebAssembly instructions it can execute.
+pub const WASM_FUEL_PER_CALL: u64 = 1_000_000;
+
+/// Maximum bytes of inputs, outputs and state of a block
+const BUFFER_SIZE: i32 = 1 << 16;
+
+/// An error of a block written in WebAssembly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WasmError {
+    /// The module could not be instantiated, or it does not follow the ABI
+    InvalidModule(String),
+    /// A call consumed more than [`WASM_FUEL_PER_CALL`]
+    OutOfFuel,
+    /// A call trapped
+    Trap(String),
+}
+
+impl fmt::Display for WasmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidModule(error) => write!(f, "invalid WebAssembly module: {error}"),
+            Self::OutOfFuel => write!(f, "the block ran out of fuel"),
+            Self::Trap(error) => write!(f, "the block trapped: {error}"),
+        }
+    }
+}
+
+impl From<wasmi::Error> for WasmError {
+    fn from(error: wasmi::Error) -> Self {
+        match error.as_trap_code() {
+            Some(TrapCode::OutOfFuel) => Self::OutOfFuel,
+            _ => Self::Trap(error.to_string()),
+        }
+    }
+}
+
+/// An instance of a WebAssembly module, shared by all the blocks of the same description
+pub(crate) struct WasmBlock {
+    instance: Result<WasmInstance, WasmError>,
+}
+
+struct WasmInstance {
+    store: Store<()>,
+    memory: Memory,
+    /// Address of the memory that the host uses for the arguments
+    buffer: usize,
+    evaluate: Option<TypedFunc<(i32, i32, i32, i32), ()>>,
+    sequential: Option<SequentialFuncs>,
+}
+
+struct SequentialFuncs {
+    state_bytes: TypedFunc<(i32, i32), i32>,
+    output: TypedFunc<(i32, i32, i32, i32), ()>,
+    update: TypedFunc<(i32, i32, i32, i32), ()>,
+}
+
+impl WasmBlock {
+    /// Instantiates the module.
+    /// If it fails, the error is returned by every call.
+    pub fn new(logic: &WasmLogic) -> Self {
+        Self {
+            instance: WasmInstance::new(logic),
+        }
+    }
+
+    /// Bytes of state needed by a shape of the block, `None` if the block has no memory
+    pub fn state_bytes(&mut self, shape: &BlockShape) -> Result<Option<usize>, WasmError> {
+        let instance = self.instance.as_mut().map_err(|error| error.clone())?;
+        let Some(sequential) = &instance.sequential else {
+            return Ok(None);
+        };
+
+        let input_len = shape
+            .inputs
+            .iter()
+            .map(|i| cable_bytes(i.wires))
+            .sum::<usize>();
+        let output_len = shape
+            .outputs
+            .iter()
+            .map(|o| cable_bytes(o.wires))
+            .sum::<usize>();
+        instance.store.set_fuel(WASM_FUEL_PER_CALL).unwrap();
+        let bytes = (sequential.state_bytes)
+            .call(&mut instance.store, (input_len as i32, output_len as i32))?;
+        Ok(Some(bytes.max(0) as usize))
+    }
+
+    /// Writes the outputs of a block without memory from its inputs
+    pub fn evaluate(&mut self, inputs: &[u8], outputs: &mut [u8]) -> Result<(), WasmError> {
+        let instance = self.instance.as_mut().map_err(|error| error.clone())?;
+        let Some(evaluate) = instance.evaluate else {
+            return Err(WasmError::InvalidModule(
+                "`evaluate` is not exported".into(),
+            ));
+        };
+        instance.call(evaluate, inputs, outputs)
+    }
+
+    /// Writes the outputs of a block with memory from its state
+    pub fn output(&mut self, state: &[u8], outputs: &mut [u8]) -> Result<(), WasmError> {
+        let instance = self.instance.as_mut().map_err(|error| error.clone())?;
+        let Some(sequential) = &instance.sequential else {
+            return Err(WasmError::InvalidModule("`output` is not exported".into()));
+        };
+        instance.call(sequential.output, state, outputs)
+    }
+
+    /// Updates the state of a block with memory from its inputs
+    pub fn update(&mut self, inputs: &[u8], state: &mut [u8]) -> Result<(), WasmError> {
+        let instance = self.instance.as_mut().map_err(|error| error.clone())?;
+        let Some(sequential) = &instance.sequential else {
+            return Err(WasmError::InvalidModule("`update` is not exported".into()));
+        };
+        instance.call(sequential.update, inputs, state)
+    }
+}
+
+impl WasmInstance {
+    fn new(logic: &WasmLogic) -> Result<Self, WasmError> {
+        let invalid = |error: wasmi::Error| WasmError::InvalidModule(error.to_string());
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &logic.code).map_err(invalid)?;
+        let mut store = Store::new(&engine, ());
+        store.set_fuel(WASM_FUEL_PER_CALL).unwrap();
+
+        // The blocks can not import anything, so they can only compute
+        let linker = Linker::new(&engine);
+        let instance = (linker.instantiate(&mut store, &module))
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(invalid)?;
+
+        let memory = (instance.get_memory(&store, "memory"))
+            .ok_or_else(|| WasmError::InvalidModule("`memory` is not exported".into()))?;
+        let buffer = (instance.get_typed_func::<i32, i32>(&store, "buffer")).map_err(invalid)?;
+        store.set_fuel(WASM_FUEL_PER_CALL).unwrap();
+        let buffer = buffer.call(&mut store, BUFFER_SIZE)? as u32 as usize;
+
+        let func = |name| instance.get_typed_func(&store, name).ok();
+        let sequential = (|| {
+            Some(SequentialFuncs {
+                state_bytes: instance.get_typed_func(&store, "state_bytes").ok()?,
+                output: func("output")?,
+                update: func("update")?,
+            })
+        })();
+
+        Ok(Self {
+            evaluate: func("evaluate"),
+            sequential,
+            store,
+            memory,
+            buffer,
+        })
+    }
+
+    /// Copies `args` and `results` to the buffer, calls `func` with their addresses
+    /// and lengths, and copies back `results`.
+    fn call(
+        &mut self,
+        func: TypedFunc<(i32, i32, i32, i32), ()>,
+        args: &[u8],
+        results: &mut [u8],
+    ) -> Result<(), WasmError> {
+        if args.len() + results.len() > BUFFER_SIZE as usize {
+            return Err(WasmError::Trap(
+                "the cables do not fit in the buffer".into(),
+            ));
+        }
+        let results_address = self.buffer + args.len();
+        let out_of_bounds = |error| WasmError::Trap(format!("{error}"));
+
+        (self.memory)
+            .write(&mut self.store, self.buffer, args)
+            .map_err(out_of_bounds)?;
+        (self.memory)
+            .write(&mut self.store, results_address, results)
+            .map_err(out_of_bounds)?;
+
+        self.store.set_fuel(WASM_FUEL_PER_CALL).unwrap();
+        func.call(
+            &mut self.store,
+            (
+                self.buffer as i32,
+                args.len() as i32,
+                results_address as i32,
+                results.len() as i32,
+            ),
+        )?;
+
+        (self.memory)
+            .read(&self.store, results_address, results)
+            .map_err(out_of_bounds)
+    }
+}
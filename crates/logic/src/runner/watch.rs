@@ -0,0 +1,88 @@
+//! Conditions on the signals that pause a running simulation
+
+use super::*;
+
+/// A condition on a cable, checked after each tick by [`ChapterRunner::run_until`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Watch {
+    pub probe: Probe,
+    pub condition: Condition,
+}
+
+/// The values are the first 64 wires of the cable
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// The value is equal
+    Equals(u64),
+    /// The value is different
+    NotEquals(u64),
+    /// The value has become equal on this tick
+    Becomes(u64),
+    /// The value is different from the previous tick
+    Changes,
+}
+
+/// A watch whose condition holds
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WatchHit {
+    /// Index of the watch in `ChapterRunner::watches`
+    pub watch: usize,
+    /// The tick where the condition holds
+    pub tick: u64,
+    pub value: u64,
+}
+
+impl Condition {
+    /// `previous` is the value before the tick.
+    /// A cable that is not driven never satisfies a condition.
+    fn holds(self, previous: Option<u64>, value: Option<u64>) -> bool {
+        let Some(value) = value else {
+            return false;
+        };
+        match self {
+            Condition::Equals(expected) => value == expected,
+            Condition::NotEquals(expected) => value != expected,
+            Condition::Becomes(expected) => value == expected && previous != Some(expected),
+            Condition::Changes => previous != Some(value),
+        }
+    }
+}
+
+impl ChapterRunner {
+    /// Ticks until the condition of a watch holds, at most `max_ticks` times.
+    /// Returns the watches that hold on the last tick, which is empty if none did.
+    pub fn run_until(&mut self, max_ticks: u64) -> Result<Vec<WatchHit>, Unsettled> {
+        let mut previous = self.watch_values();
+
+        for _ in 0..max_ticks {
+            self.tick()?;
+
+            let values = self.watch_values();
+            let hits: Vec<WatchHit> = (self.watches.iter().enumerate())
+                .filter(|(index, watch)| watch.condition.holds(previous[*index], values[*index]))
+                .map(|(index, _)| WatchHit {
+                    watch: index,
+                    tick: self.ticks,
+                    value: values[index].unwrap(),
+                })
+                .collect();
+
+            if !hits.is_empty() {
+                return Ok(hits);
+            }
+            previous = values;
+        }
+        Ok(Vec::new())
+    }
+
+    fn watch_values(&self) -> Vec<Option<u64>> {
+        (self.watches.iter())
+            .map(|watch| self.probe(watch.probe).map(cable_value))
+            .collect()
+    }
+}
+
+/// The first 64 wires of a cable
+fn cable_value(cable: &[u8]) -> u64 {
+    (cable.iter().take(8).enumerate()).fold(0, |value, (i, byte)| value | (*byte as u64) << (i * 8))
+}
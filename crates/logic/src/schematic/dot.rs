@@ -0,0 +1,100 @@
+//! Export to the DOT language of Graphviz
+
+use super::*;
+use std::fmt::Write;
+
+/// Writes a solution as a Graphviz graph.
+///
+/// The blocks are nodes with a port for each pin, and the chapter ports are nodes too.
+/// Each wire is an edge labelled with the number of wires of its source.
+/// Render it with `dot -Tsvg`.
+pub fn export_dot(chapter: &Chapter, solution: &ChapterSolution, blocks: &ModuleBlocks) -> String {
+    let mut dot = String::new();
+    writeln!(dot, "digraph {} {{", quote(&chapter.id.title)).unwrap();
+    writeln!(dot, "    rankdir=LR;").unwrap();
+    writeln!(dot, "    node [fontname=\"sans-serif\", fontsize=11];").unwrap();
+    writeln!(dot, "    edge [fontname=\"sans-serif\", fontsize=9];").unwrap();
+
+    for (port, cable) in chapter.inputs.iter().enumerate() {
+        writeln!(
+            dot,
+            "    in{port} [shape=rarrow, label={}];",
+            quote(&cable.lable)
+        )
+        .unwrap();
+    }
+    for (port, cable) in chapter.outputs.iter().enumerate() {
+        writeln!(
+            dot,
+            "    out{port} [shape=rarrow, label={}];",
+            quote(&cable.lable)
+        )
+        .unwrap();
+    }
+
+    for (index, block) in solution.blocks.iter().enumerate() {
+        let shape = &block.shape;
+        let pins = |cables: &[BlockCable], prefix: &str| -> String {
+            let pins: Vec<String> = (cables.iter().enumerate())
+                .map(|(pin, cable)| format!("<{prefix}{pin}> {}", record(&pin_lable(cable, pin))))
+                .collect();
+            pins.join(" | ")
+        };
+        let mut fields = Vec::new();
+        if !shape.inputs.is_empty() {
+            fields.push(format!("{{ {} }}", pins(&shape.inputs, "i")));
+        }
+        fields.push(record(&shape.lable));
+        if !shape.outputs.is_empty() {
+            fields.push(format!("{{ {} }}", pins(&shape.outputs, "o")));
+        }
+
+        let color = block_color(blocks, shape);
+        writeln!(
+            dot,
+            "    b{index} [shape=record, style=filled, fillcolor=\"{color}\", fontcolor=\"{}\", label=\"{}\"];",
+            text_color(&color),
+            fields.join(" | ")
+        )
+        .unwrap();
+    }
+
+    for wire in &solution.wires {
+        let wires = match wire.source {
+            PinRef::Chapter { port } => chapter.inputs.get(port).map(|c| c.wires),
+            PinRef::Block { block, pin } => (solution.blocks.get(block))
+                .and_then(|b| b.shape.outputs.get(pin))
+                .map(|c| c.wires),
+        };
+        let source = match wire.source {
+            PinRef::Chapter { port } => format!("in{port}"),
+            PinRef::Block { block, pin } => format!("b{block}:o{pin}:e"),
+        };
+        let sink = match wire.sink {
+            PinRef::Chapter { port } => format!("out{port}"),
+            PinRef::Block { block, pin } => format!("b{block}:i{pin}:w"),
+        };
+        let wires = wires.unwrap_or(1);
+        let pen = if wires > 1 { ", penwidth=2.5" } else { "" };
+        writeln!(dot, "    {source} -> {sink} [label=\"{wires}\"{pen}];").unwrap();
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+/// A DOT string
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A text of a record label, where the characters of the record syntax are escaped
+fn record(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "{}|<>\\\" ".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
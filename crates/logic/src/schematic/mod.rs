@@ -0,0 +1,45 @@
+//! Drawings of solutions, to show circuits outside of the app
+//!
+//! - [`export_dot`] writes a Graphviz graph, where Graphviz places the blocks.
+//! - [`export_svg`] writes an image with the blocks at their positions in the solution.
+
+mod dot;
+mod svg;
+
+use crate::*;
+use digolog_math::*;
+use digolog_module_loader::*;
+pub use dot::*;
+pub use svg::*;
+
+/// Color of the blocks without description
+const DEFAULT_COLOR: &str = "#cccccc";
+
+/// The color of a block as `#rrggbb`
+fn block_color(blocks: &ModuleBlocks, shape: &BlockShape) -> String {
+    match blocks.get(&shape.description.name) {
+        Some(desc) => {
+            let [r, g, b, _] = u32::from(desc.color).to_le_bytes();
+            format!("#{r:02x}{g:02x}{b:02x}")
+        }
+        None => DEFAULT_COLOR.into(),
+    }
+}
+
+/// Black or white, whatever can be read on the background color `#rrggbb`
+fn text_color(background: &str) -> &'static str {
+    let channel = |i: usize| u8::from_str_radix(&background[i..i + 2], 16).unwrap_or(0) as u32;
+    let luminance = 299 * channel(1) + 587 * channel(3) + 114 * channel(5);
+    match luminance > 128_000 {
+        true => "#000000",
+        false => "#ffffff",
+    }
+}
+
+/// The name of a pin, or its number if it has no lable
+fn pin_lable(cable: &BlockCable, pin: usize) -> String {
+    match cable.lable.as_str() {
+        "" => pin.to_string(),
+        lable => lable.into(),
+    }
+}
@@ -0,0 +1,189 @@
+//! Export to a standalone SVG image
+
+use super::*;
+use std::fmt::Write;
+
+/// Pixels of a grid unit of the block positions
+const UNIT: i32 = 20;
+/// Grid units around the drawing
+const MARGIN: i32 = 2;
+
+/// Writes a solution as an SVG image.
+///
+/// The blocks are drawn at their positions and orientations,
+/// with the color of their description and their lable.
+/// The chapter inputs are drawn left of all blocks and the chapter outputs right of them.
+/// The wires are routed around the blocks by a [`Router`].
+/// Wires of more than one bit are thicker and labelled with their width.
+pub fn export_svg(chapter: &Chapter, solution: &ChapterSolution, blocks: &ModuleBlocks) -> String {
+    let rects: Vec<[i32; 4]> = (solution.blocks.iter())
+        .map(|block| {
+            let ([x, y], [w, h]) = (block.pos.into(), block.size().into());
+            [x, y, w, h]
+        })
+        .collect();
+
+    let router = Router::route(solution, ChapterPorts::beside(chapter, solution));
+    let ports = router.ports();
+
+    let corners = rects
+        .iter()
+        .flat_map(|[x, y, w, h]| [[*x, *y], [x + w, y + h]]);
+    let port_points = ports
+        .inputs
+        .iter()
+        .chain(&ports.outputs)
+        .map(|p| (*p).into());
+    let path_points = (solution.wires.iter())
+        .filter_map(|wire| router.path(wire))
+        .flat_map(|path| path.points.iter().map(|p| (*p).into()));
+    let mut points = corners.chain(port_points).chain(path_points).peekable();
+    let mut min = points.peek().copied().unwrap_or([0, 0]);
+    let mut max = min;
+    for [x, y] in points {
+        min = [min[0].min(x), min[1].min(y)];
+        max = [max[0].max(x), max[1].max(y)];
+    }
+
+    // Room for the lables of the chapter ports
+    let view = [
+        min[0] - MARGIN - 1,
+        min[1] - MARGIN,
+        max[0] - min[0] + 2 * MARGIN + 2,
+        max[1] - min[1] + 2 * MARGIN,
+    ]
+    .map(|v| v * UNIT);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"12\">",
+        view[0], view[1], view[2], view[3], view[2], view[3]
+    )
+    .unwrap();
+    writeln!(svg, "<title>{}</title>", escape(&chapter.id.title)).unwrap();
+    writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>",
+        view[0], view[1], view[2], view[3]
+    )
+    .unwrap();
+
+    writeln!(svg, "<g fill=\"none\" stroke=\"#333333\">").unwrap();
+    for wire in &solution.wires {
+        let Some(path) = router.path(wire) else {
+            continue;
+        };
+        let wires = match wire.source {
+            PinRef::Chapter { port } => chapter.inputs.get(port).map(|c| c.wires),
+            PinRef::Block { block, pin } => (solution.blocks.get(block))
+                .and_then(|b| b.shape.outputs.get(pin))
+                .map(|c| c.wires),
+        }
+        .unwrap_or(1);
+        let points: Vec<[i32; 2]> = (path.points.iter())
+            .map(|p| <[i32; 2]>::from(*p).map(|v| v * UNIT))
+            .collect();
+        let [sx, sy] = points[0];
+        let mut d = format!("M {sx} {sy}");
+        for [x, y] in &points[1..] {
+            write!(d, " L {x} {y}").unwrap();
+        }
+        let width = if wires > 1 { 3 } else { 1 };
+        writeln!(svg, "<path d=\"{d}\" stroke-width=\"{width}\"/>").unwrap();
+        if wires > 1 {
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" fill=\"#333333\" stroke=\"none\" font-size=\"9\">{wires}</text>",
+                sx + 4,
+                sy - 4
+            )
+            .unwrap();
+        }
+    }
+    writeln!(svg, "</g>").unwrap();
+
+    for (index, block) in solution.blocks.iter().enumerate() {
+        let [x, y, w, h] = rects[index].map(|v| v * UNIT);
+        let shape = &block.shape;
+        let color = block_color(blocks, shape);
+        writeln!(svg, "<g>").unwrap();
+        writeln!(
+            svg,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" rx=\"4\" fill=\"{color}\" stroke=\"#333333\"/>"
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\" fill=\"{}\" font-weight=\"bold\">{}</text>",
+            x + w / 2,
+            y + h / 2,
+            text_color(&color),
+            escape(&shape.lable)
+        )
+        .unwrap();
+        let pins = (shape
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(pin, cable)| (false, pin, cable)))
+        .chain(
+            shape
+                .outputs
+                .iter()
+                .enumerate()
+                .map(|(pin, cable)| (true, pin, cable)),
+        );
+        for (output, pin, cable) in pins {
+            let Some(pos) = block.pin_pos(output, pin) else {
+                continue;
+            };
+            let [px, py] = <[i32; 2]>::from(pos).map(|v| v * UNIT);
+            // The lable is inside the block, next to the pin
+            let (tx, ty, anchor) = match block.pin_side(output, pin) {
+                Side::Left => (px + 4, py, "start"),
+                Side::Right => (px - 4, py, "end"),
+                Side::Top => (px, py + 8, "middle"),
+                Side::Bottom => (px, py - 8, "middle"),
+            };
+            writeln!(
+                svg,
+                "<circle cx=\"{px}\" cy=\"{py}\" r=\"2.5\" fill=\"#333333\"/><text x=\"{tx}\" y=\"{ty}\" text-anchor=\"{anchor}\" dominant-baseline=\"middle\" fill=\"{}\" font-size=\"9\">{}</text>",
+                text_color(&color),
+                escape(&cable.lable)
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</g>").unwrap();
+    }
+
+    let columns = [
+        (&chapter.inputs, &ports.inputs, "end", -6),
+        (&chapter.outputs, &ports.outputs, "start", 6),
+    ];
+    for (cables, positions, anchor, offset) in columns {
+        for (cable, pos) in cables.iter().zip(positions) {
+            let [x, y] = <[i32; 2]>::from(*pos).map(|v| v * UNIT);
+            let lable = match cable.wires {
+                1 => escape(&cable.lable),
+                wires => format!("{}[{wires}]", escape(&cable.lable)),
+            };
+            writeln!(
+                svg,
+                "<circle cx=\"{x}\" cy=\"{y}\" r=\"4\" fill=\"#ffffff\" stroke=\"#333333\"/><text x=\"{}\" y=\"{y}\" text-anchor=\"{anchor}\" dominant-baseline=\"middle\">{lable}</text>",
+                x + offset
+            )
+            .unwrap();
+        }
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+/// Text escaped for XML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
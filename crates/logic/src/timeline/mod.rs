@@ -0,0 +1,220 @@
+//! History of a simulation, to step backwards and forwards in time
+
+use crate::*;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+/// Keeps the snapshots of the last ticks of a [`ChapterRunner`].
+///
+/// Only one snapshot every `keyframe_interval` is a full copy,
+/// the others only store the bytes that changed since the previous one.
+pub struct Timeline {
+    /// Maximum number of snapshots kept
+    capacity: usize,
+    keyframe_interval: usize,
+    /// The first frame is always a key frame
+    frames: VecDeque<Frame>,
+    /// Frames recorded since the last key frame
+    since_key: usize,
+    /// Snapshot of the last frame, to find what changes on the next one
+    last: Option<Snapshot>,
+}
+
+enum Frame {
+    Key(Snapshot),
+    Delta {
+        tick: u64,
+        outputs: Vec<Patch>,
+        states: Vec<Patch>,
+    },
+}
+
+/// Bytes that changed in a buffer
+struct Patch {
+    offset: usize,
+    bytes: Vec<u8>,
+}
+
+impl Timeline {
+    /// Keeps the last `capacity` ticks, with a full copy every 64 ticks
+    pub fn new(capacity: usize) -> Self {
+        Self::with_keyframe_interval(capacity, 64)
+    }
+
+    /// Keeps the last `capacity` ticks, with a full copy every `keyframe_interval` ticks.
+    /// A longer interval uses less memory, but it is slower to jump to a tick.
+    pub fn with_keyframe_interval(capacity: usize, keyframe_interval: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            keyframe_interval: keyframe_interval.max(1),
+            frames: VecDeque::new(),
+            since_key: 0,
+            last: None,
+        }
+    }
+
+    /// Records the current state of the runner. Call it after each tick.
+    ///
+    /// If the runner has gone back in time, the recorded future is discarded.
+    pub fn record(&mut self, runner: &ChapterRunner) {
+        let snapshot = runner.snapshot();
+
+        if self
+            .ticks()
+            .is_some_and(|ticks| snapshot.tick <= *ticks.end())
+        {
+            self.truncate(snapshot.tick);
+        }
+
+        let frame = match &self.last {
+            Some(last) if self.since_key < self.keyframe_interval => {
+                self.since_key += 1;
+                Frame::Delta {
+                    tick: snapshot.tick,
+                    outputs: diff(&last.outputs, &snapshot.outputs),
+                    states: diff(&last.states, &snapshot.states),
+                }
+            }
+            _ => {
+                self.since_key = 1;
+                Frame::Key(snapshot.clone())
+            }
+        };
+        self.frames.push_back(frame);
+        self.last = Some(snapshot);
+
+        while self.frames.len() > self.capacity {
+            let Some(Frame::Key(mut key)) = self.frames.pop_front() else {
+                unreachable!("The first frame is a key frame");
+            };
+            // The next frame becomes the first one, so it has to be a full copy
+            if let Some(next) = self.frames.front_mut() {
+                if let Frame::Delta { .. } = next {
+                    apply(&mut key, next);
+                    *next = Frame::Key(key);
+                }
+            }
+        }
+    }
+
+    /// The first and last recorded ticks
+    pub fn ticks(&self) -> Option<RangeInclusive<u64>> {
+        let first = self.frames.front()?.tick();
+        let last = self.frames.back()?.tick();
+        Some(first..=last)
+    }
+
+    /// Rebuilds the snapshot of a tick, if it is recorded
+    pub fn snapshot_at(&self, tick: u64) -> Option<Snapshot> {
+        let index = (self.frames)
+            .binary_search_by_key(&tick, |frame| frame.tick())
+            .ok()?;
+        let key = (0..=index)
+            .rev()
+            .find(|&i| matches!(self.frames[i], Frame::Key(_)))
+            .unwrap();
+
+        let Frame::Key(snapshot) = &self.frames[key] else {
+            unreachable!()
+        };
+        let mut snapshot = snapshot.clone();
+        for frame in self.frames.range(key + 1..=index) {
+            apply(&mut snapshot, frame);
+        }
+        Some(snapshot)
+    }
+
+    /// Restores the runner to a recorded tick.
+    /// Returns false if the tick is not recorded.
+    pub fn jump(&self, runner: &mut ChapterRunner, tick: u64) -> bool {
+        match self.snapshot_at(tick) {
+            Some(snapshot) => {
+                runner.restore(&snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Restores the runner to the recorded tick before the current one.
+    /// Returns false if there is none.
+    pub fn step_back(&self, runner: &mut ChapterRunner) -> bool {
+        let current = runner.ticks();
+        let previous = (self.frames.iter().rev())
+            .map(Frame::tick)
+            .find(|&tick| tick < current);
+        previous.is_some_and(|tick| self.jump(runner, tick))
+    }
+
+    /// Restores the runner to the recorded tick after the current one.
+    /// Returns false if there is none, then the runner has to tick to go forward.
+    pub fn step_forward(&self, runner: &mut ChapterRunner) -> bool {
+        let current = runner.ticks();
+        let next = (self.frames.iter())
+            .map(Frame::tick)
+            .find(|&tick| tick > current);
+        next.is_some_and(|tick| self.jump(runner, tick))
+    }
+
+    /// Removes the frames of `tick` and later
+    fn truncate(&mut self, tick: u64) {
+        let keep = self.frames.partition_point(|frame| frame.tick() < tick);
+        self.frames.truncate(keep);
+        self.last = self
+            .ticks()
+            .and_then(|ticks| self.snapshot_at(*ticks.end()));
+
+        let last_key = (self.frames.iter()).rposition(|frame| matches!(frame, Frame::Key(_)));
+        self.since_key = last_key.map_or(0, |key| self.frames.len() - key);
+    }
+}
+
+impl Frame {
+    fn tick(&self) -> u64 {
+        match self {
+            Frame::Key(snapshot) => snapshot.tick,
+            Frame::Delta { tick, .. } => *tick,
+        }
+    }
+}
+
+/// The runs of bytes of `new` that are different in `old`
+fn diff(old: &[u8], new: &[u8]) -> Vec<Patch> {
+    let mut patches = Vec::new();
+    let mut i = 0;
+    while i < new.len() {
+        if old[i] == new[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < new.len() && old[i] != new[i] {
+            i += 1;
+        }
+        patches.push(Patch {
+            offset: start,
+            bytes: new[start..i].to_vec(),
+        });
+    }
+    patches
+}
+
+/// Moves `snapshot` to the tick of a frame that follows it
+fn apply(snapshot: &mut Snapshot, frame: &Frame) {
+    match frame {
+        Frame::Key(key) => snapshot.clone_from(key),
+        Frame::Delta {
+            tick,
+            outputs,
+            states,
+        } => {
+            snapshot.tick = *tick;
+            for patch in outputs {
+                snapshot.outputs[patch.offset..][..patch.bytes.len()].copy_from_slice(&patch.bytes);
+            }
+            for patch in states {
+                snapshot.states[patch.offset..][..patch.bytes.len()].copy_from_slice(&patch.bytes);
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Completion of a chapter when a solution passes its checks
+
+use super::*;
+
+/// The result of checking a solution to complete its chapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Completion {
+    /// The solution does not pass the truth table or the sequence test
+    Failed,
+    /// The solution and its chapter are completed.
+    /// `best` is true if the metrics of the solution are the new best metrics of the chapter.
+    Completed {
+        metrics: SolutionMetrics,
+        best: bool,
+    },
+}
+
+/// Checks a solution against the truth table and the sequence test of its chapter.
+///
+/// If it passes, the solution and the chapter are completed, and the metrics of the
+/// solution are recorded in the chapter. Otherwise the solution is in progress,
+/// and the chapter keeps its status.
+///
+/// # Panic
+/// If a wire refers to a missing pin, or a block has no logic.
+pub fn complete_chapter(
+    chapter: &mut Chapter,
+    solution: &mut ChapterSolution,
+    blocks: &ModuleBlocks,
+) -> Result<Completion, VerifyError> {
+    let verifier = ChapterVerifier::new(chapter, solution, blocks);
+    // A chapter without a truth table or a sequence test has nothing to pass
+    let table_passed = match verifier.verify_truth_table() {
+        Ok(report) => report.passed(),
+        Err(VerifyError::NoTruthTable) => true,
+        Err(error) => return Err(error),
+    };
+    let test_passed = match verifier.verify_sequence_test() {
+        Ok(report) => report.passed(),
+        Err(VerifyError::NoSequenceTest) => true,
+        Err(error) => return Err(error),
+    };
+
+    if !(table_passed && test_passed) {
+        solution.completion_status = ChapterCompletionStatus::InProgress;
+        return Ok(Completion::Failed);
+    }
+    let metrics = solution_metrics(chapter, solution, blocks);
+    solution.completion_status = ChapterCompletionStatus::Completed;
+    chapter.completion_status = ChapterCompletionStatus::Completed;
+    let best = chapter.record_metrics(metrics.clone());
+    Ok(Completion::Completed { metrics, best })
+}
@@ -0,0 +1,154 @@
+//! Reduced ordered binary decision diagrams
+
+use std::collections::HashMap;
+
+/// A node of a [`Bdd`]: a boolean function of the variables
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Node(u32);
+
+impl Node {
+    pub const FALSE: Node = Node(0);
+    pub const TRUE: Node = Node(1);
+}
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+enum Op {
+    And,
+    Or,
+    XOr,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct NodeData {
+    var: u32,
+    low: Node,
+    high: Node,
+}
+
+/// Stores the nodes of many functions, sharing the equal parts.
+///
+/// Two equal functions are always the same node,
+/// and a lower variable is always closer to the root.
+pub(crate) struct Bdd {
+    nodes: Vec<NodeData>,
+    unique: HashMap<(u32, Node, Node), Node>,
+    cache: HashMap<(Op, Node, Node), Node>,
+}
+
+impl Bdd {
+    pub fn new() -> Self {
+        // The terminals have a variable after all the others
+        let terminal = |value| NodeData {
+            var: u32::MAX,
+            low: Node(value),
+            high: Node(value),
+        };
+        Self {
+            nodes: vec![terminal(0), terminal(1)],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Number of nodes, to stop before running out of memory
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The function that is true when `var` is true
+    pub fn var(&mut self, var: u32) -> Node {
+        self.node(var, Node::FALSE, Node::TRUE)
+    }
+
+    pub fn not(&mut self, a: Node) -> Node {
+        self.xor(a, Node::TRUE)
+    }
+
+    pub fn and(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::And, a, b)
+    }
+
+    pub fn or(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::Or, a, b)
+    }
+
+    pub fn xor(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::XOr, a, b)
+    }
+
+    /// Value of the function with the given values of the variables
+    pub fn evaluate(&self, mut node: Node, values: impl Fn(u32) -> bool) -> bool {
+        while node != Node::FALSE && node != Node::TRUE {
+            let data = self.nodes[node.0 as usize];
+            node = if values(data.var) {
+                data.high
+            } else {
+                data.low
+            };
+        }
+        node == Node::TRUE
+    }
+
+    /// Values of some variables that make the function true, `None` if it is always false.
+    /// The other variables can have any value.
+    pub fn satisfy(&self, mut node: Node) -> Option<Vec<(u32, bool)>> {
+        let mut values = Vec::new();
+        while node != Node::TRUE {
+            if node == Node::FALSE {
+                return None;
+            }
+            // Only FALSE has no path to TRUE, so a child that is not FALSE leads to it
+            let data = self.nodes[node.0 as usize];
+            let value = data.low == Node::FALSE;
+            values.push((data.var, value));
+            node = if value { data.high } else { data.low };
+        }
+        Some(values)
+    }
+
+    fn node(&mut self, var: u32, low: Node, high: Node) -> Node {
+        if low == high {
+            return low;
+        }
+        if let Some(node) = self.unique.get(&(var, low, high)) {
+            return *node;
+        }
+        let node = Node(self.nodes.len() as u32);
+        self.nodes.push(NodeData { var, low, high });
+        self.unique.insert((var, low, high), node);
+        node
+    }
+
+    fn apply(&mut self, op: Op, a: Node, b: Node) -> Node {
+        match (op, a, b) {
+            (Op::And, Node::FALSE, _) | (Op::And, _, Node::FALSE) => return Node::FALSE,
+            (Op::And, Node::TRUE, x) | (Op::And, x, Node::TRUE) => return x,
+            (Op::Or, Node::TRUE, _) | (Op::Or, _, Node::TRUE) => return Node::TRUE,
+            (Op::Or, Node::FALSE, x) | (Op::Or, x, Node::FALSE) => return x,
+            (Op::XOr, Node::FALSE, x) | (Op::XOr, x, Node::FALSE) => return x,
+            (Op::XOr, x, y) if x == y => return Node::FALSE,
+            (Op::And | Op::Or, x, y) if x == y => return x,
+            _ => {}
+        }
+        // The operations are commutative
+        let key = (op, a.min(b), a.max(b));
+        if let Some(node) = self.cache.get(&key) {
+            return *node;
+        }
+
+        let (a_data, b_data) = (self.nodes[a.0 as usize], self.nodes[b.0 as usize]);
+        let var = a_data.var.min(b_data.var);
+        let cofactors = |data: NodeData, node: Node| match data.var == var {
+            true => (data.low, data.high),
+            false => (node, node),
+        };
+        let (a_low, a_high) = cofactors(a_data, a);
+        let (b_low, b_high) = cofactors(b_data, b);
+        let low = self.apply(op, a_low, b_low);
+        let high = self.apply(op, a_high, b_high);
+        let node = self.node(var, low, high);
+
+        self.cache.insert(key, node);
+        node
+    }
+}
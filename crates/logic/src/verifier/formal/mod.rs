@@ -0,0 +1,376 @@
+//! Proves that a solution computes the reference function of its chapter
+//! for all the inputs, without simulating each row.
+//!
+//! The wires of the solution and of the reference function are converted to
+//! binary decision diagrams, where two equal functions are the same node.
+
+mod bdd;
+mod symbolic;
+
+use super::*;
+use crate::runner::{cable_bytes, link, mask_cable, OpLogic, WasmBlock};
+use bdd::*;
+use symbolic::*;
+
+/// Stop when the diagrams have more nodes, they would use too much memory
+pub const MAX_BDD_NODES: usize = 1 << 22;
+
+/// Blocks that are not logic gates, additions, subtractions or multiplications
+/// are converted by evaluating them on all the
+/// combinations of their inputs, so they can not have more input wires
+pub const MAX_ENUMERATED_WIRES: u32 = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    /// The solution has the expected outputs for all the inputs
+    Proven,
+    /// Inputs where the solution has an unexpected output
+    Counterexample(Mismatch),
+}
+
+/// Why a solution can not be compared with the reference function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormalError {
+    /// The truth table is not a function, or there is no truth table
+    NoFunction,
+    /// The reference function does not exist, or it has no symbolic version
+    UnknownFunction(String),
+    /// The chapter does not have the number of input and output ports
+    /// that the reference function reads and writes
+    WrongPorts { inputs: usize, outputs: usize },
+    /// The solution has a sequential block, or a block that is not a logic gate,
+    /// an addition, a subtraction or a multiplication
+    /// with more than [`MAX_ENUMERATED_WIRES`] input wires.
+    /// Index of the block in the solution, or of the composite block that contains it
+    UnsupportedBlock { block: usize },
+    /// The solution has a combinational loop
+    CombinationalLoop,
+    /// The diagrams have more than [`MAX_BDD_NODES`] nodes
+    TooLarge,
+}
+
+impl ChapterVerifier<'_> {
+    /// Proves that the solution computes the reference function of the truth table,
+    /// or finds inputs where it does not.
+    ///
+    /// Only solutions without combinational loops and sequential blocks can be proven.
+    pub fn prove_equivalence(&self) -> Result<Equivalence, FormalError> {
+        let truth_table = self.chapter.truth_table.as_ref();
+        let Some(TruthTableRows::Function(name)) = truth_table.map(|t| &t.rows) else {
+            return Err(FormalError::NoFunction);
+        };
+        let unknown = || FormalError::UnknownFunction(name.clone());
+        let function = reference_function(name).ok_or_else(unknown)?;
+        let symbolic = symbolic_function(name).ok_or_else(unknown)?;
+        if self.chapter.inputs.len() != symbolic.inputs
+            || self.chapter.outputs.len() != symbolic.outputs
+        {
+            return Err(FormalError::WrongPorts {
+                inputs: symbolic.inputs,
+                outputs: symbolic.outputs,
+            });
+        }
+
+        let program = &self.program;
+        if program.levels.is_none() {
+            return Err(FormalError::CombinationalLoop);
+        }
+        let (logic, mut wasm) = link(program, self.blocks);
+
+        let mut bdd = Bdd::new();
+        let mut slots: Vec<Bits> = vec![Vec::new(); program.slots.len()];
+
+        // The wires of the inputs are interleaved, so the diagrams of
+        // the arithmetic functions stay small
+        let mut variables = Vec::new();
+        let max_wires = self
+            .chapter
+            .inputs
+            .iter()
+            .map(|p| p.wires)
+            .max()
+            .unwrap_or(0);
+        for wire in 0..max_wires {
+            for (port, cable) in self.chapter.inputs.iter().enumerate() {
+                if wire < cable.wires {
+                    let node = bdd.var(variables.len() as u32);
+                    slots[program.inputs[port]].push(node);
+                    variables.push((port, wire));
+                }
+            }
+        }
+
+        for (op, program_op) in program.ops.iter().enumerate() {
+            let inputs: Vec<Bits> = (program_op.inputs.iter())
+                .map(|input| match input.source {
+                    Some(source) => (slots[source].iter().copied())
+                        .take(input.wires as usize)
+                        .collect(),
+                    None => Vec::new(),
+                })
+                .collect();
+            let input_wires: u32 = program_op.inputs.iter().map(|i| i.wires as u32).sum();
+            match logic[op] {
+                OpLogic::Gate(gate) => {
+                    let output = program_op.outputs.start;
+                    let wires = program.slots[output].wires;
+                    slots[output] = evaluate_gate(&mut bdd, gate, &inputs, wires);
+                }
+                OpLogic::Arithmetic(
+                    arithmetic @ (Arithmetic::Add | Arithmetic::Sub | Arithmetic::Mul),
+                ) => {
+                    let output_wires: Vec<u8> = (program_op.outputs.clone())
+                        .map(|slot| program.slots[slot].wires)
+                        .collect();
+                    let outputs = evaluate_arithmetic(&mut bdd, arithmetic, &inputs, &output_wires);
+                    for (slot, bits) in program_op.outputs.clone().zip(outputs) {
+                        slots[slot] = bits;
+                    }
+                }
+                OpLogic::Builtin(_)
+                | OpLogic::Arithmetic(_)
+                | OpLogic::Wasm(_)
+                | OpLogic::Faulted
+                    if program_op.state.is_none() && input_wires <= MAX_ENUMERATED_WIRES =>
+                {
+                    let outputs =
+                        enumerate_block(&mut bdd, program, op, logic[op], &mut wasm, &inputs);
+                    for (slot, bits) in program_op.outputs.clone().zip(outputs) {
+                        slots[slot] = bits;
+                    }
+                }
+                _ => {
+                    return Err(FormalError::UnsupportedBlock {
+                        block: program_op.block,
+                    })
+                }
+            }
+
+            if bdd.len() > MAX_BDD_NODES {
+                return Err(FormalError::TooLarge);
+            }
+        }
+
+        let input_bits: Vec<Bits> = (self.chapter.inputs.iter().enumerate())
+            .map(|(port, _)| slots[program.inputs[port]].clone())
+            .collect();
+        let output_wires: Vec<u8> = self.chapter.outputs.iter().map(|p| p.wires).collect();
+        let expected = (symbolic.function)(&mut bdd, &input_bits, &output_wires);
+        if bdd.len() > MAX_BDD_NODES {
+            return Err(FormalError::TooLarge);
+        }
+
+        for (port, expected) in expected.iter().enumerate() {
+            let outputs = &slots[program.outputs[port]];
+            for i in 0..output_wires[port] as usize {
+                let (output, expected) = (wire(outputs, i), expected[i]);
+                let difference = bdd.xor(output, expected);
+                let Some(values) = bdd.satisfy(difference) else {
+                    continue;
+                };
+
+                let mut inputs = vec![0; self.chapter.inputs.len()];
+                for (var, value) in values {
+                    let (port, wire) = variables[var as usize];
+                    inputs[port] |= (value as u64) << wire;
+                }
+                let value = |var: u32| {
+                    let (port, wire) = variables[var as usize];
+                    inputs[port] >> wire & 1 == 1
+                };
+                let outputs: Vec<u64> = (program.outputs.iter())
+                    .map(|&slot| {
+                        (slots[slot].iter().take(64).enumerate())
+                            .map(|(wire, node)| (bdd.evaluate(*node, value) as u64) << wire)
+                            .fold(0, |a, b| a | b)
+                    })
+                    .collect();
+
+                return Ok(Equivalence::Counterexample(Mismatch {
+                    expected: function_outputs(self.chapter, function, &inputs),
+                    inputs,
+                    outputs,
+                }));
+            }
+        }
+        Ok(Equivalence::Proven)
+    }
+}
+
+/// A wire of a cable. The wires after the end are false.
+fn wire(bits: &[Node], i: usize) -> Node {
+    bits.get(i).copied().unwrap_or(Node::FALSE)
+}
+
+/// The output wires of a gate, like the runner evaluates them on bytes
+fn evaluate_gate(bdd: &mut Bdd, gate: Gate, inputs: &[Bits], output_wires: u8) -> Bits {
+    let mut outputs = vec![Node::FALSE; output_wires as usize];
+    match gate {
+        Gate::Not => {
+            let cable = inputs.first().map_or(&[][..], Vec::as_slice);
+            for (i, out) in outputs.iter_mut().enumerate() {
+                *out = bdd.not(wire(cable, i));
+            }
+        }
+        Gate::And | Gate::Or | Gate::XOr => {
+            for (i, out) in outputs.iter_mut().enumerate() {
+                let wires = inputs.iter().map(|cable| wire(cable, i));
+                *out = match gate {
+                    Gate::And => wires.fold(Node::TRUE, |a, b| bdd.and(a, b)),
+                    Gate::Or => wires.fold(Node::FALSE, |a, b| bdd.or(a, b)),
+                    _ => wires.fold(Node::FALSE, |a, b| bdd.xor(a, b)),
+                };
+            }
+        }
+        Gate::Equal => {
+            let first = inputs.first().map_or(&[][..], Vec::as_slice);
+            let mut equal = Node::TRUE;
+            for cable in inputs {
+                for i in 0..cable.len().max(first.len()) {
+                    let difference = bdd.xor(wire(cable, i), wire(first, i));
+                    let same = bdd.not(difference);
+                    equal = bdd.and(equal, same);
+                }
+            }
+            if let Some(out) = outputs.first_mut() {
+                *out = equal;
+            }
+        }
+    }
+    outputs
+}
+
+/// The output wires of an arithmetic block, like the runner evaluates them on bytes.
+/// Divisions are not converted, their diagrams are too large.
+fn evaluate_arithmetic(
+    bdd: &mut Bdd,
+    arithmetic: Arithmetic,
+    inputs: &[Bits],
+    output_wires: &[u8],
+) -> Vec<Bits> {
+    let cable = |i: usize| inputs.get(i).map_or(&[][..], Vec::as_slice);
+    let wires = output_wires.first().copied().unwrap_or(0) as usize;
+    match arithmetic {
+        Arithmetic::Add => {
+            let sum = (inputs.iter()).fold(Vec::new(), |sum, cable| {
+                add(bdd, &sum, cable, Node::FALSE, wires).0
+            });
+            vec![sum]
+        }
+        Arithmetic::Sub => {
+            // a - b = a + !b + 1, and a < b when it does not carry out
+            let len = wires.max(cable(0).len()).max(cable(1).len());
+            let not_b: Bits = (0..len).map(|i| bdd.not(wire(cable(1), i))).collect();
+            let (mut difference, carry) = add(bdd, cable(0), &not_b, Node::TRUE, len);
+            difference.truncate(wires);
+            let mut sign = vec![bdd.not(carry)];
+            sign.resize(
+                output_wires.get(1).copied().unwrap_or(0) as usize,
+                Node::FALSE,
+            );
+            vec![difference, sign]
+        }
+        Arithmetic::Mul => {
+            let mut one = vec![Node::TRUE];
+            one.resize(wires, Node::FALSE);
+            let product = (inputs.iter()).fold(one, |product, cable| {
+                // The sum of `product` shifted by each wire of the cable that is 1
+                let mut sum = vec![Node::FALSE; wires];
+                for (shift, &bit) in cable.iter().enumerate().take(wires) {
+                    let partial: Bits = (0..wires)
+                        .map(|i| match i.checked_sub(shift) {
+                            Some(i) => bdd.and(product[i], bit),
+                            None => Node::FALSE,
+                        })
+                        .collect();
+                    sum = add(bdd, &sum, &partial, Node::FALSE, wires).0;
+                }
+                sum
+            });
+            vec![product]
+        }
+        Arithmetic::Div | Arithmetic::Mod => unreachable!("Divisions are enumerated"),
+    }
+}
+
+/// The output wires of a block that is not a gate,
+/// from its outputs on each combination of the input wires
+fn enumerate_block(
+    bdd: &mut Bdd,
+    program: &ChapterProgram,
+    op: usize,
+    logic: OpLogic,
+    wasm: &mut [WasmBlock],
+    inputs: &[Bits],
+) -> Vec<Bits> {
+    let program_op = &program.ops[op];
+    let input_bytes: usize = program_op.inputs.iter().map(|i| cable_bytes(i.wires)).sum();
+    let input_wires: u32 = program_op.inputs.iter().map(|i| i.wires as u32).sum();
+    let mut input_buffer = vec![0; input_bytes];
+    let mut output_buffer = vec![0; program_op.bytes.len()];
+    let mut outputs: Vec<Bits> = (program_op.outputs.clone())
+        .map(|slot| vec![Node::FALSE; program.slots[slot].wires as usize])
+        .collect();
+
+    for row in 0..1u64 << input_wires {
+        input_buffer.fill(0);
+        // The function that is true on the inputs of this row
+        let mut row_inputs = Node::TRUE;
+        let (mut byte, mut bit) = (0, 0);
+        for (cable, input) in inputs.iter().zip(&program_op.inputs) {
+            for i in 0..input.wires as usize {
+                let value = row >> bit & 1;
+                input_buffer[byte + i / 8] |= (value as u8) << (i % 8);
+                let literal = match value {
+                    1 => wire(cable, i),
+                    _ => bdd.not(wire(cable, i)),
+                };
+                row_inputs = bdd.and(row_inputs, literal);
+                bit += 1;
+            }
+            byte += cable_bytes(input.wires);
+        }
+        // The inputs never have these values, for example because they are not connected
+        if row_inputs == Node::FALSE {
+            continue;
+        }
+
+        output_buffer.fill(0);
+        match logic {
+            OpLogic::Builtin(logic) => logic(
+                BlockInput::from(&input_buffer[..]),
+                BlockOutputMut::from(&mut output_buffer[..]),
+            ),
+            // A block that fails outputs 0, like in the runner
+            OpLogic::Wasm(instance)
+                if wasm[instance]
+                    .evaluate(&input_buffer, &mut output_buffer)
+                    .is_err() =>
+            {
+                output_buffer.fill(0)
+            }
+            OpLogic::Arithmetic(arithmetic) => crate::runner::evaluate_arithmetic(
+                arithmetic,
+                &input_buffer,
+                program_op.inputs.iter().map(|input| input.wires),
+                &mut output_buffer,
+                (program_op.outputs.clone()).map(|slot| program.slots[slot].wires),
+            ),
+            _ => {}
+        }
+
+        let mut byte = 0;
+        for (slot, bits) in program_op.outputs.clone().zip(&mut outputs) {
+            let wires = program.slots[slot].wires;
+            let cable = &mut output_buffer[byte..][..cable_bytes(wires)];
+            mask_cable(cable, wires);
+            for (i, bit) in bits.iter_mut().enumerate() {
+                if cable[i / 8] >> (i % 8) & 1 == 1 {
+                    *bit = bdd.or(*bit, row_inputs);
+                }
+            }
+            byte += cable.len();
+        }
+    }
+    outputs
+}
@@ -0,0 +1,206 @@
+//! The reference functions on the wires of the cables, to compare them with a solution
+
+use super::*;
+
+/// A cable as the function of each wire, first wire first.
+/// The wires after the end are false.
+pub(crate) type Bits = Vec<Node>;
+
+/// Computes the wires of each chapter output, with `output_wires` wires each,
+/// like the [`ReferenceFn`] with the same name.
+pub(crate) type SymbolicFn = fn(&mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits>;
+
+/// A symbolic function, and the number of chapter ports that it reads and writes
+pub(crate) struct Symbolic {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub function: SymbolicFn,
+}
+
+/// Finds the symbolic version of a reference function
+pub(crate) fn symbolic_function(name: &str) -> Option<Symbolic> {
+    let (inputs, outputs, function): (usize, usize, SymbolicFn) = match name {
+        "And" => (2, 1, and),
+        "Or" => (2, 1, or),
+        "Equal" => (2, 1, equal),
+        "HalfAdder" => (2, 2, half_adder),
+        "FullAdder" => (3, 2, full_adder),
+        "Adder" => (2, 1, adder),
+        "Subtractor" => (2, 2, subtractor),
+        _ => return None,
+    };
+    Some(Symbolic {
+        inputs,
+        outputs,
+        function,
+    })
+}
+
+/// Applies `op` to each wire of the cables
+fn bitwise(
+    bdd: &mut Bdd,
+    a: &[Node],
+    b: &[Node],
+    wires: u8,
+    op: fn(&mut Bdd, Node, Node) -> Node,
+) -> Bits {
+    (0..wires as usize)
+        .map(|i| op(bdd, wire(a, i), wire(b, i)))
+        .collect()
+}
+
+/// `a + b + carry` with `wires` wires, and the carry out of the last wire
+pub(super) fn add(
+    bdd: &mut Bdd,
+    a: &[Node],
+    b: &[Node],
+    mut carry: Node,
+    wires: usize,
+) -> (Bits, Node) {
+    let mut sum = Vec::with_capacity(wires);
+    for i in 0..wires {
+        let (a, b) = (wire(a, i), wire(b, i));
+        let half = bdd.xor(a, b);
+        sum.push(bdd.xor(half, carry));
+        let generate = bdd.and(a, b);
+        let propagate = bdd.and(half, carry);
+        carry = bdd.or(generate, propagate);
+    }
+    (sum, carry)
+}
+
+fn and(bdd: &mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits> {
+    vec![bitwise(
+        bdd,
+        &inputs[0],
+        &inputs[1],
+        output_wires[0],
+        Bdd::and,
+    )]
+}
+
+fn or(bdd: &mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits> {
+    vec![bitwise(
+        bdd,
+        &inputs[0],
+        &inputs[1],
+        output_wires[0],
+        Bdd::or,
+    )]
+}
+
+fn equal(bdd: &mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits> {
+    let differences = bitwise(bdd, &inputs[0], &inputs[1], 64, Bdd::xor);
+    let different = (differences.into_iter()).fold(Node::FALSE, |a, b| bdd.or(a, b));
+    let mut equal = vec![bdd.not(different)];
+    equal.resize(output_wires[0] as usize, Node::FALSE);
+    vec![equal]
+}
+
+/// Outputs `[sum, carry]`
+fn half_adder(bdd: &mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits> {
+    vec![
+        bitwise(bdd, &inputs[0], &inputs[1], output_wires[0], Bdd::xor),
+        bitwise(bdd, &inputs[0], &inputs[1], output_wires[1], Bdd::and),
+    ]
+}
+
+/// Outputs `[sum, carry]`
+fn full_adder(bdd: &mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits> {
+    let (ab, _) = add(bdd, &inputs[0], &inputs[1], Node::FALSE, 64);
+    let (sum, _) = add(bdd, &ab, &inputs[2], Node::FALSE, 64);
+    let mut low = vec![sum[0]];
+    low.resize(output_wires[0] as usize, Node::FALSE);
+    let high = (1..=output_wires[1] as usize)
+        .map(|i| wire(&sum, i))
+        .collect();
+    vec![low, high]
+}
+
+fn adder(bdd: &mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits> {
+    let (sum, _) = add(
+        bdd,
+        &inputs[0],
+        &inputs[1],
+        Node::FALSE,
+        output_wires[0] as usize,
+    );
+    vec![sum]
+}
+
+/// Outputs `[difference, sign]`
+fn subtractor(bdd: &mut Bdd, inputs: &[Bits], output_wires: &[u8]) -> Vec<Bits> {
+    // a - b = a + !b + 1, and a < b when it does not carry out of the 64 wires
+    let not_b: Bits = (0..64)
+        .map(|i| wire(&inputs[1], i))
+        .map(|b| bdd.not(b))
+        .collect();
+    let (difference, carry) = add(bdd, &inputs[0], &not_b, Node::TRUE, 64);
+    let mut difference = difference;
+    difference.truncate(output_wires[0] as usize);
+    let mut sign = vec![bdd.not(carry)];
+    sign.resize(output_wires[1] as usize, Node::FALSE);
+    vec![difference, sign]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::random::split_mix;
+
+    /// The wires of the chapter ports used to compare each function
+    const PORTS: [(&str, &[u8], &[u8]); 7] = [
+        ("And", &[8, 8], &[8]),
+        ("Or", &[8, 8], &[8]),
+        ("Equal", &[4, 4], &[1]),
+        ("HalfAdder", &[1, 1], &[1, 1]),
+        ("FullAdder", &[1, 1, 1], &[1, 1]),
+        ("Adder", &[8, 8], &[9]),
+        ("Subtractor", &[8, 8], &[8, 1]),
+    ];
+
+    #[test]
+    fn symbolic_functions_agree_with_the_reference_functions() {
+        for (name, input_wires, output_wires) in PORTS {
+            let reference = reference_function(name).unwrap();
+            let symbolic = symbolic_function(name).unwrap();
+            assert_eq!(symbolic.inputs, input_wires.len(), "{name}");
+            assert_eq!(symbolic.outputs, output_wires.len(), "{name}");
+
+            let mut bdd = Bdd::new();
+            let mut variables = Vec::new();
+            let mut inputs: Vec<Bits> = Vec::new();
+            for (port, &wires) in input_wires.iter().enumerate() {
+                let mut bits = Vec::new();
+                for wire in 0..wires {
+                    bits.push(bdd.var(variables.len() as u32));
+                    variables.push((port, wire));
+                }
+                inputs.push(bits);
+            }
+            let outputs = (symbolic.function)(&mut bdd, &inputs, output_wires);
+
+            let mut state = 0;
+            for _ in 0..256 {
+                let values: Vec<u64> = (input_wires.iter())
+                    .map(|&wires| {
+                        state = split_mix(state);
+                        state & port_mask(wires)
+                    })
+                    .collect();
+                let value = |var: u32| {
+                    let (port, wire) = variables[var as usize];
+                    values[port] >> wire & 1 == 1
+                };
+                let expected = reference(&values);
+                for (port, bits) in outputs.iter().enumerate() {
+                    let output = (bits.iter().enumerate())
+                        .map(|(wire, node)| (bdd.evaluate(*node, value) as u64) << wire)
+                        .fold(0, |a, b| a | b);
+                    let expected = expected[port] & port_mask(output_wires[port]);
+                    assert_eq!(output, expected, "{name}{values:?}, output {port}");
+                }
+            }
+        }
+    }
+}
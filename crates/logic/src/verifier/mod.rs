@@ -0,0 +1,279 @@
+mod completion;
+mod formal;
+mod packed;
+mod random;
+mod reference;
+mod reference_solution;
+mod sequence;
+
+use crate::*;
+use digolog_module_loader::*;
+use std::collections::HashMap;
+
+pub use completion::*;
+pub use formal::*;
+pub use reference::*;
+pub use reference_solution::*;
+pub use sequence::*;
+
+/// Chapters with more input wires check edge cases and random combinations of the inputs,
+/// unless their truth table sets another limit
+pub const MAX_EXHAUSTIVE_WIRES: u32 = 24;
+
+/// Random rows that are checked when the inputs have too many wires,
+/// unless the truth table sets another number
+pub const DEFAULT_RANDOM_ROWS: u64 = 1 << 16;
+
+/// Checks if a solution behaves as its chapter expects.
+pub struct ChapterVerifier<'a> {
+    chapter: &'a Chapter,
+    blocks: &'a ModuleBlocks,
+    program: ChapterProgram,
+    /// Simulate many rows of the truth table at once when the solution has no
+    /// combinational loops. The rows are packed on SIMD lanes, one row on each bit.
+    pub bit_parallel: bool,
+    /// Seed of the random rows, to repeat a previous verification.
+    /// A new seed is generated if it is `None`.
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTableReport {
+    /// Number of rows that have been checked
+    pub checked_rows: u64,
+    /// First row with an unexpected output
+    pub mismatch: Option<Mismatch>,
+    /// Seed of the random rows, `None` if all the combinations of the inputs have been checked
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Value of each chapter input
+    pub inputs: Vec<u64>,
+    /// Expected value of each chapter output, `None` if it is not checked
+    pub expected: Vec<Option<u64>>,
+    /// Value of each chapter output
+    pub outputs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    NoTruthTable,
+    NoSequenceTest,
+    /// The truth table uses a function that does not exist
+    UnknownFunction(String),
+    /// The signals have not settled with the given chapter inputs
+    NotSettled {
+        inputs: Vec<u64>,
+        unsettled: Unsettled,
+    },
+}
+
+/// The rows of a truth table, generated when needed
+pub(crate) enum Rows<'a> {
+    /// All the combinations of the inputs
+    Function {
+        function: ReferenceFn,
+        input_wires: u32,
+    },
+    /// Edge cases, and then random combinations of the inputs
+    Random {
+        function: ReferenceFn,
+        edge_cases: Vec<Vec<u64>>,
+        random_rows: u64,
+        seed: u64,
+    },
+    Table(&'a [HashMap<String, u64>]),
+}
+
+impl TruthTableReport {
+    pub fn passed(&self) -> bool {
+        self.mismatch.is_none()
+    }
+}
+
+impl<'a> ChapterVerifier<'a> {
+    /// # Panic
+    /// If a wire refers to a missing pin, or a block has no logic.
+    pub fn new(chapter: &'a Chapter, solution: &ChapterSolution, blocks: &'a ModuleBlocks) -> Self {
+        Self {
+            program: ChapterProgram::compile(chapter, solution, blocks),
+            chapter,
+            blocks,
+            bit_parallel: true,
+            seed: None,
+        }
+    }
+
+    /// Checks the rows of the truth table in order, and stops on the first mismatch.
+    ///
+    /// If the inputs have too many wires to check all their combinations,
+    /// it checks edge cases and random rows.
+    pub fn verify_truth_table(&self) -> Result<TruthTableReport, VerifyError> {
+        let rows = self.rows()?;
+        let mut report = if self.bit_parallel && self.program.is_levelized() {
+            packed::verify(self, &rows)
+        } else {
+            self.verify_scalar(&rows)?
+        };
+        if let Rows::Random { seed, .. } = rows {
+            report.seed = Some(seed);
+        }
+        Ok(report)
+    }
+
+    fn rows(&self) -> Result<Rows<'a>, VerifyError> {
+        let truth_table = (self.chapter.truth_table.as_ref()).ok_or(VerifyError::NoTruthTable)?;
+
+        match &truth_table.rows {
+            TruthTableRows::Function(name) => {
+                let function = reference_function(name)
+                    .ok_or_else(|| VerifyError::UnknownFunction(name.clone()))?;
+                let input_wires = self.chapter.inputs.iter().map(|p| p.wires as u32).sum();
+                let max_wires = (truth_table.max_exhaustive_wires)
+                    .unwrap_or(MAX_EXHAUSTIVE_WIRES)
+                    .min(63);
+                if input_wires > max_wires {
+                    return Ok(Rows::Random {
+                        function,
+                        edge_cases: random::edge_cases(self.chapter),
+                        random_rows: truth_table.random_rows.unwrap_or(DEFAULT_RANDOM_ROWS),
+                        seed: self.seed.unwrap_or_else(random::new_seed),
+                    });
+                }
+                Ok(Rows::Function {
+                    function,
+                    input_wires,
+                })
+            }
+            TruthTableRows::Table(table) => Ok(Rows::Table(table)),
+        }
+    }
+
+    fn verify_scalar(&self, rows: &Rows) -> Result<TruthTableReport, VerifyError> {
+        let mut runner = ChapterRunner::from_program(self.program.clone(), self.blocks);
+
+        for row in 0..rows.len() {
+            let (inputs, expected) = rows.row(self.chapter, row);
+            for (port, value) in inputs.iter().enumerate() {
+                runner.set_input(port, &value.to_le_bytes());
+            }
+
+            if let Err(unsettled) = runner.settle() {
+                return Err(VerifyError::NotSettled { inputs, unsettled });
+            }
+
+            let outputs: Vec<u64> = (0..self.chapter.outputs.len())
+                .map(|port| port_value(runner.output(port)))
+                .collect();
+
+            if !outputs_match(&expected, &outputs) {
+                return Ok(TruthTableReport {
+                    checked_rows: row + 1,
+                    mismatch: Some(Mismatch {
+                        inputs,
+                        expected,
+                        outputs,
+                    }),
+                    seed: None,
+                });
+            }
+        }
+
+        Ok(TruthTableReport {
+            checked_rows: rows.len(),
+            mismatch: None,
+            seed: None,
+        })
+    }
+}
+
+impl Rows<'_> {
+    pub fn len(&self) -> u64 {
+        match self {
+            Rows::Function { input_wires, .. } => 1 << input_wires,
+            Rows::Random {
+                edge_cases,
+                random_rows,
+                ..
+            } => edge_cases.len() as u64 + random_rows,
+            Rows::Table(table) => table.len() as u64,
+        }
+    }
+
+    /// Returns the value of the inputs and the expected outputs of a row
+    pub fn row(&self, chapter: &Chapter, row: u64) -> (Vec<u64>, Vec<Option<u64>>) {
+        match self {
+            Rows::Function { function, .. } => {
+                let mut offset = 0;
+                let inputs: Vec<u64> = (chapter.inputs.iter())
+                    .map(|port| {
+                        let value = (row >> offset) & port_mask(port.wires);
+                        offset += port.wires as u32;
+                        value
+                    })
+                    .collect();
+
+                let expected = function_outputs(chapter, *function, &inputs);
+                (inputs, expected)
+            }
+            Rows::Random {
+                function,
+                edge_cases,
+                seed,
+                ..
+            } => {
+                let inputs = match edge_cases.get(row as usize) {
+                    Some(inputs) => inputs.clone(),
+                    None => random::random_inputs(chapter, *seed, row),
+                };
+                let expected = function_outputs(chapter, *function, &inputs);
+                (inputs, expected)
+            }
+            Rows::Table(table) => {
+                let values = &table[row as usize];
+                let inputs = (chapter.inputs.iter())
+                    .map(|port| {
+                        values.get(&port.lable).copied().unwrap_or(0) & port_mask(port.wires)
+                    })
+                    .collect();
+                let expected = (chapter.outputs.iter())
+                    .map(|port| Some(values.get(&port.lable)? & port_mask(port.wires)))
+                    .collect();
+
+                (inputs, expected)
+            }
+        }
+    }
+}
+
+/// The expected outputs of a reference function
+fn function_outputs(chapter: &Chapter, function: ReferenceFn, inputs: &[u64]) -> Vec<Option<u64>> {
+    (chapter.outputs.iter())
+        .zip(function(inputs))
+        .map(|(port, value)| Some(value & port_mask(port.wires)))
+        .collect()
+}
+
+pub(crate) fn outputs_match(expected: &[Option<u64>], outputs: &[u64]) -> bool {
+    (expected.iter().zip(outputs))
+        .all(|(expected, output)| expected.is_none() || *expected == Some(*output))
+}
+
+/// The bits of a port with `wires` wires
+pub(crate) fn port_mask(wires: u8) -> u64 {
+    if wires >= 64 {
+        !0
+    } else {
+        (1 << wires) - 1
+    }
+}
+
+/// Reads a cable from the runner as a number
+pub(crate) fn port_value(cable: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let len = cable.len().min(8);
+    bytes[..len].copy_from_slice(&cable[..len]);
+    u64::from_le_bytes(bytes)
+}
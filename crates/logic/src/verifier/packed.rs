@@ -0,0 +1,277 @@
+//! Bit-parallel simulation of levelized programs.
+//!
+//! Every wire holds the value of [`ROWS_PER_PASS`] rows of the truth table, one on each bit.
+//! The logic gates are evaluated on all the rows at once with SIMD operations,
+//! and the other blocks are evaluated row by row.
+
+use super::*;
+use crate::runner::{cable_bytes, evaluate_arithmetic, link, mask_cable, OpLogic, WasmBlock};
+use std::simd::Simd;
+
+type Lanes = Simd<u64, 4>;
+
+/// Rows of the truth table simulated at once
+pub(super) const ROWS_PER_PASS: u64 = Lanes::LEN as u64 * 64;
+
+struct PackedRunner<'a> {
+    program: &'a ChapterProgram,
+    logic: Vec<OpLogic>,
+    wasm: Vec<WasmBlock>,
+    /// Index in `wires` of the first wire of each slot
+    slot_wires: Vec<usize>,
+    /// Value of each wire on every row of the pass
+    wires: Vec<Lanes>,
+    /// Wires of the inputs of the block that is being evaluated
+    input_scratch: Vec<Lanes>,
+}
+
+/// Checks the rows of the truth table with the solution program
+///
+/// # Panic
+/// If the program is not levelized.
+pub(super) fn verify(verifier: &ChapterVerifier, rows: &Rows) -> TruthTableReport {
+    assert!(verifier.program.is_levelized());
+    let chapter = verifier.chapter;
+    let mut runner = PackedRunner::new(&verifier.program, verifier.blocks);
+    let mut expected = Vec::with_capacity(ROWS_PER_PASS as usize);
+
+    for first_row in (0..rows.len()).step_by(ROWS_PER_PASS as usize) {
+        let pass_rows = (rows.len() - first_row).min(ROWS_PER_PASS) as usize;
+
+        let mut input_wires: Vec<Vec<[u64; Lanes::LEN]>> = (chapter.inputs.iter())
+            .map(|port| vec![[0; Lanes::LEN]; port.wires as usize])
+            .collect();
+        expected.clear();
+
+        for lane_row in 0..pass_rows {
+            let (inputs, row_expected) = rows.row(chapter, first_row + lane_row as u64);
+            for (port_wires, value) in input_wires.iter_mut().zip(inputs) {
+                // The values have 64 bits, the wires after them are 0
+                for (wire, lanes) in port_wires.iter_mut().enumerate().take(64) {
+                    lanes[lane_row / 64] |= (value >> wire & 1) << (lane_row % 64);
+                }
+            }
+            expected.push(row_expected);
+        }
+
+        for (port, port_wires) in input_wires.iter().enumerate() {
+            let first_wire = runner.slot_wires[verifier.program.inputs[port]];
+            for (wire, lanes) in port_wires.iter().enumerate() {
+                runner.wires[first_wire + wire] = Lanes::from_array(*lanes);
+            }
+        }
+
+        runner.run_pass();
+
+        for (lane_row, expected) in expected.iter().enumerate() {
+            let outputs: Vec<u64> = (verifier.program.outputs.iter())
+                .map(|&slot| runner.read_slot(slot, lane_row))
+                .collect();
+
+            if !outputs_match(expected, &outputs) {
+                let row = first_row + lane_row as u64;
+                return TruthTableReport {
+                    checked_rows: row + 1,
+                    mismatch: Some(Mismatch {
+                        inputs: rows.row(chapter, row).0,
+                        expected: expected.clone(),
+                        outputs,
+                    }),
+                    seed: None,
+                };
+            }
+        }
+    }
+
+    TruthTableReport {
+        checked_rows: rows.len(),
+        mismatch: None,
+        seed: None,
+    }
+}
+
+impl<'a> PackedRunner<'a> {
+    fn new(program: &'a ChapterProgram, blocks: &ModuleBlocks) -> Self {
+        let mut slot_wires = Vec::with_capacity(program.slots.len());
+        let mut wire_count = 0;
+        for slot in &program.slots {
+            slot_wires.push(wire_count);
+            wire_count += slot.wires as usize;
+        }
+
+        let (logic, wasm) = link(program, blocks);
+        Self {
+            logic,
+            wasm,
+            program,
+            slot_wires,
+            wires: vec![Lanes::splat(0); wire_count],
+            input_scratch: Vec::new(),
+        }
+    }
+
+    /// Evaluates all the operations in level order, so the signals settle in one pass
+    fn run_pass(&mut self) {
+        for op in 0..self.program.ops.len() {
+            match self.logic[op] {
+                OpLogic::Gate(gate) => self.evaluate_gate(op, gate),
+                _ => self.evaluate_rows(op),
+            }
+        }
+    }
+
+    /// Value of a slot on one row of the pass
+    fn read_slot(&self, slot: usize, lane_row: usize) -> u64 {
+        let first_wire = self.slot_wires[slot];
+        let wires = &self.wires[first_wire..][..self.program.slots[slot].wires.min(64) as usize];
+        (wires.iter().enumerate())
+            .map(|(wire, lanes)| (lanes.as_array()[lane_row / 64] >> (lane_row % 64) & 1) << wire)
+            .fold(0, |a, b| a | b)
+    }
+
+    /// Copies the input wires of `op` to `input_scratch`
+    fn gather_inputs(&mut self, op: usize) {
+        self.input_scratch.clear();
+        for input in &self.program.ops[op].inputs {
+            let start = self.input_scratch.len();
+            self.input_scratch
+                .resize(start + input.wires as usize, Lanes::splat(0));
+
+            if let Some(source) = input.source {
+                let wires = (input.wires).min(self.program.slots[source].wires) as usize;
+                let first_wire = self.slot_wires[source];
+                self.input_scratch[start..][..wires]
+                    .copy_from_slice(&self.wires[first_wire..][..wires]);
+            }
+        }
+    }
+
+    fn evaluate_gate(&mut self, op: usize, gate: Gate) {
+        self.gather_inputs(op);
+        let program_op = &self.program.ops[op];
+        let output_slot = program_op.outputs.start;
+        let first_output = self.slot_wires[output_slot];
+        let output_wires = self.program.slots[output_slot].wires as usize;
+
+        let cables = || {
+            program_op.inputs.iter().scan(0, |offset, input| {
+                let cable = &self.input_scratch[*offset..][..input.wires as usize];
+                *offset += cable.len();
+                Some(cable)
+            })
+        };
+        let wire = |cable: &[Lanes], i: usize| cable.get(i).copied().unwrap_or(Lanes::splat(0));
+
+        let mut outputs = vec![Lanes::splat(0); output_wires];
+        match gate {
+            Gate::Not => {
+                let cable = cables().next().unwrap_or(&[]);
+                for (i, out) in outputs.iter_mut().enumerate() {
+                    *out = !wire(cable, i);
+                }
+            }
+            Gate::And | Gate::Or | Gate::XOr => {
+                for (i, out) in outputs.iter_mut().enumerate() {
+                    let mut wires = cables().map(|cable| wire(cable, i));
+                    *out = match gate {
+                        Gate::And => wires.fold(Lanes::splat(!0), |a, b| a & b),
+                        Gate::Or => wires.fold(Lanes::splat(0), |a, b| a | b),
+                        _ => wires.fold(Lanes::splat(0), |a, b| a ^ b),
+                    };
+                }
+            }
+            Gate::Equal => {
+                let first = cables().next().unwrap_or(&[]);
+                let mut equal = Lanes::splat(!0);
+                for cable in cables() {
+                    for i in 0..cable.len().max(first.len()) {
+                        equal &= !(wire(cable, i) ^ wire(first, i));
+                    }
+                }
+                if let Some(out) = outputs.first_mut() {
+                    *out = equal;
+                }
+            }
+        }
+
+        self.wires[first_output..][..output_wires].copy_from_slice(&outputs);
+    }
+
+    /// Evaluates a block that is not a gate on each row of the pass
+    fn evaluate_rows(&mut self, op: usize) {
+        self.gather_inputs(op);
+        let program_op = &self.program.ops[op];
+        let state = vec![0; program_op.state.clone().map_or(0, |state| state.len())];
+
+        let input_bytes: usize = program_op.inputs.iter().map(|i| cable_bytes(i.wires)).sum();
+        let mut inputs = vec![0; input_bytes];
+        let mut outputs = vec![0; program_op.bytes.len()];
+        let first_output = self.slot_wires[program_op.outputs.start];
+        let output_wires: usize = (program_op.outputs.clone())
+            .map(|slot| self.program.slots[slot].wires as usize)
+            .sum();
+        let mut output_lanes = vec![[0; Lanes::LEN]; output_wires];
+
+        for lane_row in 0..ROWS_PER_PASS as usize {
+            let (lane, bit) = (lane_row / 64, lane_row % 64);
+
+            inputs.fill(0);
+            let (mut byte, mut wire) = (0, 0);
+            for input in &program_op.inputs {
+                for i in 0..input.wires as usize {
+                    let value = self.input_scratch[wire + i].as_array()[lane] >> bit & 1;
+                    inputs[byte + i / 8] |= (value as u8) << (i % 8);
+                }
+                byte += cable_bytes(input.wires);
+                wire += input.wires as usize;
+            }
+
+            match self.logic[op] {
+                OpLogic::Builtin(logic) => logic(
+                    BlockInput::from(&inputs[..]),
+                    BlockOutputMut::from(&mut outputs[..]),
+                ),
+                OpLogic::Arithmetic(arithmetic) => evaluate_arithmetic(
+                    arithmetic,
+                    &inputs,
+                    program_op.inputs.iter().map(|input| input.wires),
+                    &mut outputs,
+                    (program_op.outputs.clone()).map(|slot| self.program.slots[slot].wires),
+                ),
+                OpLogic::Sequential { output, .. } => output(
+                    BlockState::from(&state[..]),
+                    BlockOutputMut::from(&mut outputs[..]),
+                ),
+                OpLogic::Wasm(wasm) => {
+                    let result = match program_op.state {
+                        Some(_) => self.wasm[wasm].output(&state, &mut outputs),
+                        None => self.wasm[wasm].evaluate(&inputs, &mut outputs),
+                    };
+                    // A block that fails outputs 0, like in the runner
+                    if result.is_err() {
+                        self.logic[op] = OpLogic::Faulted;
+                        outputs.fill(0);
+                    }
+                }
+                OpLogic::Faulted => outputs.fill(0),
+                OpLogic::Gate(_) => unreachable!(),
+            }
+
+            let (mut byte, mut wire) = (0, 0);
+            for slot in program_op.outputs.clone() {
+                let wires = self.program.slots[slot].wires;
+                let cable = &mut outputs[byte..][..cable_bytes(wires)];
+                mask_cable(cable, wires);
+                for i in 0..wires as usize {
+                    output_lanes[wire + i][lane] |= ((cable[i / 8] >> (i % 8) & 1) as u64) << bit;
+                }
+                byte += cable.len();
+                wire += wires as usize;
+            }
+        }
+
+        for (i, lanes) in output_lanes.into_iter().enumerate() {
+            self.wires[first_output + i] = Lanes::from_array(lanes);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Input vectors for the chapters with too many input wires to check all their combinations
+
+use super::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Edge cases of the inputs with more rows are only checked with all the inputs equal
+const MAX_EDGE_CASE_ROWS: usize = 1024;
+
+/// A seed that changes on each run
+pub(crate) fn new_seed() -> u64 {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    split_mix(time.as_nanos() as u64)
+}
+
+/// Values that usually break arithmetic circuits: zero, one, all ones,
+/// and the boundaries of the signed values.
+///
+/// Returns all the combinations of the edge values of each input,
+/// or only the rows where all the inputs are the same kind of edge value if there are too many.
+pub(crate) fn edge_cases(chapter: &Chapter) -> Vec<Vec<u64>> {
+    let port_edges = |wires: u8| -> Vec<u64> {
+        let max = port_mask(wires);
+        let sign = 1 << (wires.clamp(1, 64) - 1);
+        let mut values = vec![0, 1, max, sign, sign - 1];
+        values.sort();
+        values.dedup();
+        values
+    };
+    let edges: Vec<Vec<u64>> = chapter.inputs.iter().map(|p| port_edges(p.wires)).collect();
+
+    let combinations =
+        (edges.iter()).try_fold(1usize, |rows, values| rows.checked_mul(values.len()));
+    match combinations {
+        Some(rows) if rows <= MAX_EDGE_CASE_ROWS => {
+            let mut cases = vec![Vec::new()];
+            for values in &edges {
+                cases = (cases.iter())
+                    .flat_map(|case| {
+                        values.iter().map(move |value| {
+                            let mut case = case.clone();
+                            case.push(*value);
+                            case
+                        })
+                    })
+                    .collect();
+            }
+            cases
+        }
+        _ => {
+            let kinds = |wires: u8| {
+                let sign = 1 << (wires.clamp(1, 64) - 1);
+                [0, 1, port_mask(wires), sign, sign - 1]
+            };
+            let mut cases: Vec<Vec<u64>> = (0..5)
+                .map(|kind| {
+                    chapter
+                        .inputs
+                        .iter()
+                        .map(|p| kinds(p.wires)[kind])
+                        .collect()
+                })
+                .collect();
+            cases.dedup();
+            cases
+        }
+    }
+}
+
+/// The value of the inputs on a random row. The same seed and row always give the same inputs.
+pub(crate) fn random_inputs(chapter: &Chapter, seed: u64, row: u64) -> Vec<u64> {
+    let mut state = split_mix(seed ^ split_mix(row));
+    (chapter.inputs.iter())
+        .map(|port| {
+            state = split_mix(state);
+            state & port_mask(port.wires)
+        })
+        .collect()
+}
+
+/// A step of the SplitMix64 generator
+pub(crate) fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,54 @@
+//! Functions referenced by the truth tables of the chapters
+
+/// Computes the value of each chapter output from the value of each chapter input.
+/// The outputs are cut to the width of the ports.
+pub type ReferenceFn = fn(&[u64]) -> Vec<u64>;
+
+/// Finds a function by the name used in the chapter manifests
+pub fn reference_function(name: &str) -> Option<ReferenceFn> {
+    Some(match name {
+        "And" => and,
+        "Or" => or,
+        "Equal" => equal,
+        "HalfAdder" => half_adder,
+        "FullAdder" => full_adder,
+        "Adder" => adder,
+        "Subtractor" => subtractor,
+        _ => return None,
+    })
+}
+
+fn and(inputs: &[u64]) -> Vec<u64> {
+    vec![inputs[0] & inputs[1]]
+}
+
+fn or(inputs: &[u64]) -> Vec<u64> {
+    vec![inputs[0] | inputs[1]]
+}
+
+fn equal(inputs: &[u64]) -> Vec<u64> {
+    vec![(inputs[0] == inputs[1]) as u64]
+}
+
+/// Outputs `[sum, carry]`
+fn half_adder(inputs: &[u64]) -> Vec<u64> {
+    vec![inputs[0] ^ inputs[1], inputs[0] & inputs[1]]
+}
+
+/// Outputs `[sum, carry]`
+fn full_adder(inputs: &[u64]) -> Vec<u64> {
+    let sum = inputs[0] + inputs[1] + inputs[2];
+    vec![sum & 1, sum >> 1]
+}
+
+fn adder(inputs: &[u64]) -> Vec<u64> {
+    vec![inputs[0].wrapping_add(inputs[1])]
+}
+
+/// Outputs `[difference, sign]`
+fn subtractor(inputs: &[u64]) -> Vec<u64> {
+    vec![
+        inputs[0].wrapping_sub(inputs[1]),
+        (inputs[0] < inputs[1]) as u64,
+    ]
+}
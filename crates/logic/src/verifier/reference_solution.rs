@@ -0,0 +1,113 @@
+//! Checks of the reference solutions shipped with the modules
+
+use super::*;
+
+/// Why a reference solution does not prove that its chapter can be solved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceSolutionError {
+    /// The chapter has no truth table and no sequence test,
+    /// so the solution can not prove anything
+    NothingToCheck,
+    /// The wire refers to a pin that the block or the chapter does not have
+    DanglingWire(Wire),
+    /// `solution.blocks[block]` is not allowed in the chapter, or its shape does not follow
+    /// the description or the template values of the allowed blocks
+    BlockNotAllowed {
+        block: usize,
+        desc: BlockDescId,
+    },
+    /// `solution.blocks[block]` is not a block of the module, or it has no logic
+    MissingLogic {
+        block: usize,
+        desc: BlockDescId,
+    },
+    Verify(VerifyError),
+    /// The solution does not pass the truth table
+    Mismatch(Mismatch),
+    /// The solution does not pass the sequence test, on the given step
+    TestMismatch {
+        step: usize,
+        mismatch: Mismatch,
+    },
+}
+
+/// The result of checking the reference solution of a chapter
+pub struct ReferenceSolutionReport<'a> {
+    pub chapter: &'a Chapter,
+    pub result: Result<(), ReferenceSolutionError>,
+}
+
+/// Checks that the reference solution of a chapter only uses the allowed blocks
+/// and passes the truth table and the sequence test.
+/// A chapter without truth table and sequence test can not be checked.
+/// Returns `None` if the chapter has no reference solution.
+pub fn check_reference_solution(
+    module: &Module,
+    book: &Book,
+    chapter: &Chapter,
+) -> Option<Result<(), ReferenceSolutionError>> {
+    let solution = chapter.reference_solution.as_ref()?;
+    if chapter.truth_table.is_none() && chapter.test.is_none() {
+        return Some(Err(ReferenceSolutionError::NothingToCheck));
+    }
+
+    for (block, placed) in solution.blocks.iter().enumerate() {
+        let desc = &placed.shape.description;
+        if !chapter.allows_block(book, &placed.shape) {
+            let desc = desc.clone();
+            return Some(Err(ReferenceSolutionError::BlockNotAllowed { block, desc }));
+        }
+        if module
+            .blocks
+            .get(&desc.name)
+            .and_then(|d| d.logic.as_ref())
+            .is_none()
+        {
+            let desc = desc.clone();
+            return Some(Err(ReferenceSolutionError::MissingLogic { block, desc }));
+        }
+    }
+
+    if let Some(wire) = solution.dangling_wire(chapter) {
+        return Some(Err(ReferenceSolutionError::DanglingWire(wire)));
+    }
+
+    let verifier = ChapterVerifier::new(chapter, solution, &module.blocks);
+    let result = match verifier.verify_truth_table() {
+        Ok(TruthTableReport {
+            mismatch: Some(mismatch),
+            ..
+        }) => Err(ReferenceSolutionError::Mismatch(mismatch)),
+        // There is nothing to pass
+        Ok(_) | Err(VerifyError::NoTruthTable) => Ok(()),
+        Err(error) => Err(ReferenceSolutionError::Verify(error)),
+    };
+    if result.is_err() {
+        return Some(result);
+    }
+
+    Some(match verifier.verify_sequence_test() {
+        Ok(SequenceTestReport {
+            checked_steps,
+            mismatch: Some(mismatch),
+        }) => Err(ReferenceSolutionError::TestMismatch {
+            step: checked_steps - 1,
+            mismatch,
+        }),
+        Ok(_) | Err(VerifyError::NoSequenceTest) => Ok(()),
+        Err(error) => Err(ReferenceSolutionError::Verify(error)),
+    })
+}
+
+/// Checks the reference solution of every chapter of a module that has one
+pub fn check_reference_solutions(module: &Module) -> Vec<ReferenceSolutionReport<'_>> {
+    let mut reports = Vec::new();
+    for book in module.iter_books() {
+        for chapter in book.iter_chapters() {
+            if let Some(result) = check_reference_solution(module, book, chapter) {
+                reports.push(ReferenceSolutionReport { chapter, result });
+            }
+        }
+    }
+    reports
+}
@@ -0,0 +1,88 @@
+//! Checks of the behaviour of a solution over time
+
+use super::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceTestReport {
+    /// Number of steps that have been checked
+    pub checked_steps: usize,
+    /// First step with an unexpected output
+    pub mismatch: Option<Mismatch>,
+}
+
+impl SequenceTestReport {
+    pub fn passed(&self) -> bool {
+        self.mismatch.is_none()
+    }
+}
+
+impl ChapterVerifier<'_> {
+    /// Runs the steps of the sequence test in order from the initial state of the solution,
+    /// and stops on the first mismatch.
+    ///
+    /// Each step is a tick of the simulation. If the test has a clock, the clock input
+    /// is set to 0 and the signals settle before the tick, and it is set to 1 on the tick,
+    /// so the solution sees a rising edge.
+    pub fn verify_sequence_test(&self) -> Result<SequenceTestReport, VerifyError> {
+        let test = (self.chapter.test.as_ref()).ok_or(VerifyError::NoSequenceTest)?;
+        let mut runner = ChapterRunner::from_program(self.program.clone(), self.blocks);
+        let mut inputs = vec![0; self.chapter.inputs.len()];
+
+        for (step_index, step) in test.steps.iter().enumerate() {
+            for ((value, new), port) in inputs
+                .iter_mut()
+                .zip(&step.inputs)
+                .zip(&self.chapter.inputs)
+            {
+                if let Some(new) = new {
+                    *value = new & port_mask(port.wires);
+                }
+            }
+
+            let result = match test.clock {
+                Some(clock) => {
+                    inputs[clock] = 0;
+                    set_inputs(&mut runner, &inputs);
+                    runner.settle().and_then(|()| {
+                        inputs[clock] = 1;
+                        set_inputs(&mut runner, &inputs);
+                        runner.tick()
+                    })
+                }
+                None => {
+                    set_inputs(&mut runner, &inputs);
+                    runner.tick()
+                }
+            };
+            if let Err(unsettled) = result {
+                return Err(VerifyError::NotSettled { inputs, unsettled });
+            }
+
+            let outputs: Vec<u64> = (0..self.chapter.outputs.len())
+                .map(|port| port_value(runner.output(port)))
+                .collect();
+
+            if !outputs_match(&step.expected, &outputs) {
+                return Ok(SequenceTestReport {
+                    checked_steps: step_index + 1,
+                    mismatch: Some(Mismatch {
+                        inputs,
+                        expected: step.expected.clone(),
+                        outputs,
+                    }),
+                });
+            }
+        }
+
+        Ok(SequenceTestReport {
+            checked_steps: test.steps.len(),
+            mismatch: None,
+        })
+    }
+}
+
+fn set_inputs(runner: &mut ChapterRunner, inputs: &[u64]) {
+    for (port, value) in inputs.iter().enumerate() {
+        runner.set_input(port, &value.to_le_bytes());
+    }
+}
@@ -0,0 +1,404 @@
+//! Export of solutions to structural Verilog (IEEE 1364-2005)
+//!
+//! The chapter becomes the top module, with a port for each chapter port.
+//! The logic gates are written as expressions, each composite block as its own module,
+//! and the other blocks as modules with a table of their outputs for all their inputs.
+//! The sequential blocks keep their state in a register updated on the rising edge of `clk`,
+//! which is a tick of the simulation.
+
+mod table;
+
+use crate::*;
+use digolog_module_loader::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Blocks written as tables can not have more input wires, counting the wires of the state
+pub const MAX_TABLE_WIRES: u32 = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerilogError {
+    /// The block does not exist or it has no logic
+    NoLogic(BlockDescId),
+    /// The block has to be written as a table, and it has more than [`MAX_TABLE_WIRES`] input wires
+    TooManyWires { desc: BlockDescId, wires: u32 },
+    /// A block written in WebAssembly has failed while building its table
+    Wasm { desc: BlockDescId, error: WasmError },
+}
+
+/// Writes a solution as Verilog modules.
+/// The top module is named like the chapter, and it has a `clk` input if
+/// the solution has sequential blocks. The blocks are searched by name in `blocks`.
+pub fn export_verilog(
+    chapter: &Chapter,
+    solution: &ChapterSolution,
+    blocks: &ModuleBlocks,
+) -> Result<String, VerilogError> {
+    let mut exporter = Exporter {
+        blocks,
+        submodules: String::new(),
+        generated: HashMap::new(),
+        module_names: HashSet::new(),
+    };
+    let name = exporter.module_name(&chapter.id.title);
+    let netlist = Netlist {
+        inputs: &chapter.inputs,
+        outputs: &chapter.outputs,
+        blocks: &solution.blocks,
+        wires: &solution.wires,
+    };
+
+    let mut verilog = String::new();
+    writeln!(
+        verilog,
+        "// Generated by digolog from a solution of the chapter {:?}",
+        chapter.id.title
+    )
+    .unwrap();
+    let top = exporter.netlist_module(&name, netlist)?;
+    verilog.push_str(&top.code);
+    verilog.push_str(&exporter.submodules);
+    Ok(verilog)
+}
+
+/// Blocks and wires to write as a module: a chapter solution or a composite block
+#[derive(Copy, Clone)]
+struct Netlist<'a> {
+    inputs: &'a [BlockCable],
+    outputs: &'a [BlockCable],
+    blocks: &'a [Block],
+    wires: &'a [Wire],
+}
+
+/// A Verilog module written by the exporter
+#[derive(Clone)]
+struct VerilogModule {
+    name: String,
+    /// The module has a `clk` input
+    clocked: bool,
+    code: String,
+}
+
+struct Exporter<'a> {
+    blocks: &'a ModuleBlocks,
+    /// Code of the modules instantiated by the top module
+    submodules: String,
+    /// Modules written for each block description and shape
+    generated: HashMap<(String, Vec<u8>, Vec<u8>), VerilogModule>,
+    module_names: HashSet<String>,
+}
+
+impl Exporter<'_> {
+    /// Writes the module of a netlist. It is not added to the submodules.
+    fn netlist_module(
+        &mut self,
+        name: &str,
+        netlist: Netlist,
+    ) -> Result<VerilogModule, VerilogError> {
+        let (input_names, output_names) = port_names(netlist.inputs, netlist.outputs);
+        let mut body = String::new();
+        let mut clocked = false;
+
+        // A net for each block output
+        let net = |block: usize, pin: usize| {
+            let shape = &netlist.blocks[block].shape;
+            let pin_name = match shape.outputs[pin].lable.as_str() {
+                "" => format!("out{pin}"),
+                lable => lable.to_owned(),
+            };
+            format!(
+                "{}_{block}_{}",
+                identifier(&shape.lable),
+                identifier(&pin_name)
+            )
+        };
+        // The expression that drives a sink
+        let source =
+            |sink: PinRef, wires: u8| match netlist.wires.iter().find(|wire| wire.sink == sink) {
+                Some(wire) => match wire.source {
+                    PinRef::Chapter { port } => input_names[port].clone(),
+                    PinRef::Block { block, pin } => net(block, pin),
+                },
+                None => format!("{}'d0", wires.max(1)),
+            };
+
+        for (block, placed) in netlist.blocks.iter().enumerate() {
+            for (pin, cable) in placed.shape.outputs.iter().enumerate() {
+                writeln!(body, "    wire {}{};", range(cable.wires), net(block, pin)).unwrap();
+            }
+        }
+        if !netlist.blocks.is_empty() {
+            body.push('\n');
+        }
+
+        for (block, placed) in netlist.blocks.iter().enumerate() {
+            let shape = &placed.shape;
+            let desc = &shape.description;
+            let logic = (self.blocks.get(&desc.name)).and_then(|d| d.logic.as_ref());
+            let inputs: Vec<String> = (shape.inputs.iter().enumerate())
+                .map(|(pin, cable)| source(PinRef::Block { block, pin }, cable.wires))
+                .collect();
+
+            match logic {
+                None => return Err(VerilogError::NoLogic(desc.clone())),
+                Some(BlockLogic::Gate(gate)) => {
+                    // A gate only drives its first output, like in the runner
+                    for pin in 0..shape.outputs.len() {
+                        let expression = match pin {
+                            0 => gate_expression(*gate, &inputs, shape.outputs[0].wires),
+                            _ => format!("{}'d0", shape.outputs[pin].wires.max(1)),
+                        };
+                        writeln!(body, "    assign {} = {expression};", net(block, pin)).unwrap();
+                    }
+                }
+                Some(BlockLogic::Arithmetic(arithmetic)) => {
+                    for (pin, cable) in shape.outputs.iter().enumerate() {
+                        let expression =
+                            arithmetic_expression(*arithmetic, &inputs, pin, cable.wires);
+                        writeln!(body, "    assign {} = {expression};", net(block, pin)).unwrap();
+                    }
+                }
+                Some(logic) => {
+                    let module = match logic {
+                        BlockLogic::Composite(composite) => {
+                            self.composite_module(desc, composite)?
+                        }
+                        _ => self.table_module(desc, logic, shape)?,
+                    };
+                    clocked |= module.clocked;
+
+                    // The names of the ports of the module
+                    let (module_inputs, module_outputs) = match logic {
+                        BlockLogic::Composite(composite) => {
+                            port_names(&composite.inputs, &composite.outputs)
+                        }
+                        _ => port_names(&shape.inputs, &shape.outputs),
+                    };
+                    let mut connections = Vec::new();
+                    if module.clocked {
+                        connections.push(".clk(clk)".to_owned());
+                    }
+                    for (port, expression) in module_inputs.iter().zip(&inputs) {
+                        connections.push(format!(".{port}({expression})"));
+                    }
+                    // A composite block can be placed with fewer outputs than its module
+                    for (pin, port) in module_outputs.iter().enumerate().take(shape.outputs.len()) {
+                        connections.push(format!(".{port}({})", net(block, pin)));
+                    }
+                    let instance = format!("u_{}_{block}", identifier(&shape.lable));
+                    writeln!(
+                        body,
+                        "    {} {instance} ({});",
+                        module.name,
+                        connections.join(", ")
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        for (port, cable) in netlist.outputs.iter().enumerate() {
+            let expression = source(PinRef::Chapter { port }, cable.wires);
+            writeln!(body, "    assign {} = {expression};", output_names[port]).unwrap();
+        }
+
+        let mut ports = Vec::new();
+        if clocked {
+            ports.push("input wire clk".to_owned());
+        }
+        for (cable, name) in netlist.inputs.iter().zip(&input_names) {
+            ports.push(format!("input wire {}{name}", range(cable.wires)));
+        }
+        for (cable, name) in netlist.outputs.iter().zip(&output_names) {
+            ports.push(format!("output wire {}{name}", range(cable.wires)));
+        }
+
+        let mut code = String::new();
+        writeln!(code, "\nmodule {name} (").unwrap();
+        writeln!(code, "    {}", ports.join(",\n    ")).unwrap();
+        writeln!(code, ");").unwrap();
+        code.push_str(&body);
+        writeln!(code, "endmodule").unwrap();
+
+        Ok(VerilogModule {
+            name: name.to_owned(),
+            clocked,
+            code,
+        })
+    }
+
+    /// The module of a composite block, written the first time it is used
+    fn composite_module(
+        &mut self,
+        desc: &BlockDescId,
+        composite: &CompositeLogic,
+    ) -> Result<VerilogModule, VerilogError> {
+        let key = (desc.name.clone(), Vec::new(), Vec::new());
+        if let Some(module) = self.generated.get(&key) {
+            return Ok(module.clone());
+        }
+
+        let name = self.module_name(&desc.name);
+        let netlist = Netlist {
+            inputs: &composite.inputs,
+            outputs: &composite.outputs,
+            blocks: &composite.blocks,
+            wires: &composite.wires,
+        };
+        let module = self.netlist_module(&name, netlist)?;
+        self.submodules.push_str(&module.code);
+        self.generated.insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// A name for a new module, different from the previous ones
+    fn module_name(&mut self, name: &str) -> String {
+        let base = identifier(name);
+        let mut name = base.clone();
+        let mut count = 1;
+        while !self.module_names.insert(name.clone()) {
+            name = format!("{base}_{count}");
+            count += 1;
+        }
+        name
+    }
+}
+
+/// The expression of the output of a gate with `wires` wires
+fn gate_expression(gate: Gate, inputs: &[String], wires: u8) -> String {
+    let wires = wires.max(1);
+    match gate {
+        Gate::Not => match inputs.first() {
+            Some(input) => format!("~{input}"),
+            None => format!("~{wires}'d0"),
+        },
+        Gate::And | Gate::Or | Gate::XOr => {
+            let (operator, empty) = match gate {
+                Gate::And => (" & ", format!("~{wires}'d0")),
+                Gate::Or => (" | ", format!("{wires}'d0")),
+                _ => (" ^ ", format!("{wires}'d0")),
+            };
+            match inputs.is_empty() {
+                true => empty,
+                false => inputs.join(operator),
+            }
+        }
+        Gate::Equal => {
+            let comparisons: Vec<String> = (inputs.iter().skip(1))
+                .map(|input| format!("{} == {input}", inputs[0]))
+                .collect();
+            match comparisons.is_empty() {
+                true => "1'b1".into(),
+                false => format!("({})", comparisons.join(") && (")),
+            }
+        }
+    }
+}
+
+/// The expression of the output `pin` of an arithmetic block, with `wires` wires.
+/// The operands are extended to the width of the output, like in the runner.
+fn arithmetic_expression(
+    arithmetic: Arithmetic,
+    inputs: &[String],
+    pin: usize,
+    wires: u8,
+) -> String {
+    let wires = wires.max(1);
+    let zero = format!("{wires}'d0");
+    let input = |i: usize| inputs.get(i).cloned().unwrap_or_else(|| zero.clone());
+    match (arithmetic, pin) {
+        (Arithmetic::Add, 0) if !inputs.is_empty() => inputs.join(" + "),
+        (Arithmetic::Mul, 0) if !inputs.is_empty() => inputs.join(" * "),
+        (Arithmetic::Mul, 0) => format!("{wires}'d1"),
+        (Arithmetic::Sub, 0) => format!("{} - {}", input(0), input(1)),
+        (Arithmetic::Sub, 1) => format!("{} < {}", input(0), input(1)),
+        (Arithmetic::Div, 0) => {
+            format!("{} == 0 ? ~{zero} : {} / {}", input(1), input(0), input(1))
+        }
+        (Arithmetic::Mod, 0) => format!(
+            "{} == 0 ? {} : {} % {}",
+            input(1),
+            input(0),
+            input(0),
+            input(1)
+        ),
+        _ => zero,
+    }
+}
+
+/// The range of a net with `wires` wires, empty for a single wire
+fn range(wires: u8) -> String {
+    match wires {
+        0 | 1 => String::new(),
+        wires => format!("[{}:0] ", wires - 1),
+    }
+}
+
+/// Unique names for the input and output ports of a module, from the lables of the cables.
+/// A cable without lable is named `inN` or `outN` with its number.
+fn port_names(inputs: &[BlockCable], outputs: &[BlockCable]) -> (Vec<String>, Vec<String>) {
+    let mut used = HashSet::from(["clk".to_owned()]);
+    let mut names = |cables: &[BlockCable], prefix: &str| -> Vec<String> {
+        (cables.iter().enumerate())
+            .map(|(index, cable)| {
+                let mut name = match cable.lable.is_empty() {
+                    true => format!("{prefix}{index}"),
+                    false => identifier(&cable.lable),
+                };
+                while !used.insert(name.clone()) {
+                    name = format!("{name}_{index}");
+                }
+                name
+            })
+            .collect()
+    };
+    let inputs = names(inputs, "in");
+    (inputs, names(outputs, "out"))
+}
+
+/// Replaces the characters that can not be part of a Verilog identifier
+fn identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "always",
+        "and",
+        "assign",
+        "begin",
+        "buf",
+        "case",
+        "default",
+        "else",
+        "end",
+        "endcase",
+        "endmodule",
+        "for",
+        "if",
+        "initial",
+        "inout",
+        "input",
+        "module",
+        "nand",
+        "nor",
+        "not",
+        "or",
+        "output",
+        "posedge",
+        "reg",
+        "wire",
+        "xnor",
+        "xor",
+    ];
+
+    let mut identifier: String = (name.chars())
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    if !identifier.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        identifier.insert(0, '_');
+    }
+    if KEYWORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    }
+    identifier
+}
@@ -0,0 +1,347 @@
+//! Modules of the blocks whose logic is code, written as a table
+//! of their outputs for all the values of their inputs
+
+use super::*;
+use crate::runner::{cable_bytes, mask_cable, WasmBlock};
+
+/// A function of the logic of a block, from a buffer to another one
+type TableFn<'a> = Box<dyn FnMut(&[u8], &mut [u8]) -> Result<(), WasmError> + 'a>;
+
+/// Evaluates the logic of a block on byte buffers laid out as [`BlockInput`]
+enum TableLogic<'a> {
+    Combinational(TableFn<'a>),
+    Sequential {
+        state_bytes: usize,
+        output: TableFn<'a>,
+        update: TableFn<'a>,
+    },
+}
+
+impl Exporter<'_> {
+    /// The module of a block that is not a gate or a composite block,
+    /// written the first time that its description is used with the widths of `shape`
+    pub(super) fn table_module(
+        &mut self,
+        desc: &BlockDescId,
+        logic: &BlockLogic,
+        shape: &BlockShape,
+    ) -> Result<VerilogModule, VerilogError> {
+        let widths = |cables: &[BlockCable]| cables.iter().map(|c| c.wires).collect();
+        let key = (
+            desc.name.clone(),
+            widths(&shape.inputs),
+            widths(&shape.outputs),
+        );
+        if let Some(module) = self.generated.get(&key) {
+            return Ok(module.clone());
+        }
+
+        let wasm_error = |error| VerilogError::Wasm {
+            desc: desc.clone(),
+            error,
+        };
+        let logic = match logic {
+            BlockLogic::Builtin(logic) => TableLogic::Combinational(Box::new(|inputs, outputs| {
+                logic(BlockInput::from(inputs), BlockOutputMut::from(outputs));
+                Ok(())
+            })),
+            BlockLogic::Sequential {
+                state_bytes,
+                output,
+                update,
+            } => TableLogic::Sequential {
+                state_bytes: state_bytes(shape),
+                output: Box::new(|state, outputs| {
+                    output(BlockState::from(state), BlockOutputMut::from(outputs));
+                    Ok(())
+                }),
+                update: Box::new(|inputs, state| {
+                    update(BlockInput::from(inputs), BlockStateMut::from(state));
+                    Ok(())
+                }),
+            },
+            BlockLogic::Wasm(logic) => {
+                let wasm = std::rc::Rc::new(std::cell::RefCell::new(WasmBlock::new(logic)));
+                let state_bytes = wasm.borrow_mut().state_bytes(shape).map_err(wasm_error)?;
+                match state_bytes {
+                    None => TableLogic::Combinational(Box::new(move |inputs, outputs| {
+                        wasm.borrow_mut().evaluate(inputs, outputs)
+                    })),
+                    Some(state_bytes) => {
+                        let update_wasm = wasm.clone();
+                        TableLogic::Sequential {
+                            state_bytes,
+                            output: Box::new(move |state, outputs| {
+                                wasm.borrow_mut().output(state, outputs)
+                            }),
+                            update: Box::new(move |inputs, state| {
+                                update_wasm.borrow_mut().update(inputs, state)
+                            }),
+                        }
+                    }
+                }
+            }
+            BlockLogic::Gate(_) | BlockLogic::Arithmetic(_) | BlockLogic::Composite(_) => {
+                unreachable!()
+            }
+        };
+
+        let input_wires: u32 = shape.inputs.iter().map(|c| c.wires as u32).sum();
+        let state_wires = match &logic {
+            TableLogic::Combinational(_) => 0,
+            TableLogic::Sequential { state_bytes, .. } => *state_bytes as u32 * 8,
+        };
+        let wires = input_wires + state_wires;
+        if wires > MAX_TABLE_WIRES {
+            return Err(VerilogError::TooManyWires {
+                desc: desc.clone(),
+                wires,
+            });
+        }
+
+        let name = self.module_name(&desc.name);
+        let (input_names, output_names) = port_names(&shape.inputs, &shape.outputs);
+        let output_wires: u32 = shape.outputs.iter().map(|c| c.wires as u32).sum();
+        let mut code = String::new();
+
+        let mut ports = Vec::new();
+        if let TableLogic::Sequential { .. } = logic {
+            ports.push("input wire clk".to_owned());
+        }
+        for (cable, name) in shape.inputs.iter().zip(&input_names) {
+            ports.push(format!("input wire {}{name}", range(cable.wires)));
+        }
+        for (cable, name) in shape.outputs.iter().zip(&output_names) {
+            ports.push(format!("output wire {}{name}", range(cable.wires)));
+        }
+        writeln!(code, "\nmodule {name} (").unwrap();
+        writeln!(code, "    {}", ports.join(",\n    ")).unwrap();
+        writeln!(code, ");").unwrap();
+
+        // The first cable has the lowest wires
+        let concat = |names: &[String]| {
+            let names: Vec<&str> = names.iter().rev().map(String::as_str).collect();
+            format!("{{{}}}", names.join(", "))
+        };
+        let output_bytes: usize = shape.outputs.iter().map(|c| cable_bytes(c.wires)).sum();
+        let mut outputs = vec![0; output_bytes];
+        let clocked = matches!(logic, TableLogic::Sequential { .. });
+
+        match logic {
+            TableLogic::Combinational(mut evaluate) => {
+                let mut rows = Vec::new();
+                for row in 0..1u64 << input_wires {
+                    let inputs = unpack(row, &shape.inputs);
+                    outputs.fill(0);
+                    evaluate(&inputs, &mut outputs).map_err(wasm_error)?;
+                    rows.push(pack(&mut outputs, &shape.outputs));
+                }
+
+                if !shape.outputs.is_empty() {
+                    writeln!(code, "    reg {}table_out;", range_wide(output_wires)).unwrap();
+                }
+                write_table(
+                    &mut code,
+                    &concat(&input_names),
+                    input_wires,
+                    "table_out",
+                    output_wires,
+                    &rows,
+                    None,
+                );
+            }
+            TableLogic::Sequential {
+                state_bytes,
+                mut output,
+                mut update,
+            } => {
+                let state_cable = [BlockCable {
+                    lable: String::new(),
+                    wires: state_wires as u8,
+                }];
+                let mut next_rows = Vec::new();
+                for row in 0..1u64 << wires {
+                    // The state is in the lowest wires
+                    let mut state = unpack(row & mask(state_wires), &state_cable);
+                    state.resize(state_bytes, 0);
+                    let inputs = unpack(row >> state_wires, &shape.inputs);
+                    update(&inputs, &mut state).map_err(wasm_error)?;
+                    next_rows.push(pack(&mut state, &state_cable));
+                }
+                let mut output_rows = Vec::new();
+                for row in 0..1u64 << state_wires {
+                    let mut state = unpack(row, &state_cable);
+                    state.resize(state_bytes, 0);
+                    outputs.fill(0);
+                    output(&state, &mut outputs).map_err(wasm_error)?;
+                    output_rows.push(pack(&mut outputs, &shape.outputs));
+                }
+
+                let state_range = range_wide(state_wires);
+                writeln!(
+                    code,
+                    "    reg {state_range}state = {};",
+                    literal(&vec![false; state_wires as usize])
+                )
+                .unwrap();
+                writeln!(code, "    reg {state_range}next_state;").unwrap();
+                if !shape.outputs.is_empty() {
+                    writeln!(code, "    reg {}table_out;", range_wide(output_wires)).unwrap();
+                }
+                writeln!(code, "\n    always @(posedge clk) state <= next_state;").unwrap();
+
+                let mut update_inputs = input_names.clone();
+                update_inputs.insert(0, "state".into());
+                write_table(
+                    &mut code,
+                    &concat(&update_inputs),
+                    wires,
+                    "next_state",
+                    state_wires,
+                    &next_rows,
+                    Some("state"),
+                );
+                write_table(
+                    &mut code,
+                    "state",
+                    state_wires,
+                    "table_out",
+                    output_wires,
+                    &output_rows,
+                    None,
+                );
+            }
+        }
+        // A block without outputs, like a sink of the signals, has nothing to assign
+        if !shape.outputs.is_empty() {
+            writeln!(code, "    assign {} = table_out;", concat(&output_names)).unwrap();
+        }
+        writeln!(code, "endmodule").unwrap();
+
+        let module = VerilogModule {
+            name,
+            clocked,
+            code,
+        };
+        self.submodules.push_str(&module.code);
+        self.generated.insert(key, module.clone());
+        Ok(module)
+    }
+}
+
+/// Writes an `always` block that sets `target` to `rows[selector]`.
+/// The most common value is the default, so only the other rows are written.
+/// With `hold`, the net that is in the lowest wires of the selector, the rows
+/// where the target is equal to it can be left to the default too.
+fn write_table(
+    code: &mut String,
+    selector: &str,
+    selector_wires: u32,
+    target: &str,
+    target_wires: u32,
+    rows: &[Vec<bool>],
+    hold: Option<&str>,
+) {
+    if target_wires == 0 {
+        return;
+    }
+    if selector_wires == 0 {
+        writeln!(code, "\n    always @(*) {target} = {};", literal(&rows[0])).unwrap();
+        return;
+    }
+
+    let bits =
+        |row: usize, wires: u32| -> Vec<bool> { (0..wires).map(|i| row >> i & 1 == 1).collect() };
+    let holds = |row: usize, value: &[bool]| hold.is_some() && value == bits(row, target_wires);
+
+    let mut counts: HashMap<&[bool], usize> = HashMap::new();
+    for row in rows {
+        *counts.entry(row).or_default() += 1;
+    }
+    let (constant, constant_rows) = (counts.into_iter())
+        .max_by_key(|(value, count)| (*count, std::cmp::Reverse(*value)))
+        .unwrap();
+    let held_rows = (rows.iter().enumerate())
+        .filter(|(row, value)| holds(*row, value))
+        .count();
+    let hold = hold.filter(|_| held_rows > constant_rows);
+
+    writeln!(code, "\n    always @(*) begin").unwrap();
+    writeln!(code, "        case ({selector})").unwrap();
+    for (row, value) in rows.iter().enumerate() {
+        let default = match hold {
+            Some(_) => holds(row, value),
+            None => value[..] == *constant,
+        };
+        if !default {
+            let selector = literal(&bits(row, selector_wires));
+            writeln!(
+                code,
+                "            {selector}: {target} = {};",
+                literal(value)
+            )
+            .unwrap();
+        }
+    }
+    let default = match hold {
+        Some(hold) => hold.to_owned(),
+        None => literal(constant),
+    };
+    writeln!(code, "            default: {target} = {default};").unwrap();
+    writeln!(code, "        endcase").unwrap();
+    writeln!(code, "    end").unwrap();
+}
+
+/// The cables of a row of a table, laid out as [`BlockInput`]
+fn unpack(row: u64, cables: &[BlockCable]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut wire = 0;
+    for cable in cables {
+        let start = bytes.len();
+        bytes.resize(start + cable_bytes(cable.wires), 0);
+        for i in 0..cable.wires as usize {
+            bytes[start + i / 8] |= ((row >> wire & 1) as u8) << (i % 8);
+            wire += 1;
+        }
+    }
+    bytes
+}
+
+/// The wires of the cables, the first wire of the first cable first
+fn pack(bytes: &mut [u8], cables: &[BlockCable]) -> Vec<bool> {
+    let mut wires = Vec::new();
+    let mut start = 0;
+    for cable in cables {
+        let cable_bytes = &mut bytes[start..][..cable_bytes(cable.wires)];
+        mask_cable(cable_bytes, cable.wires);
+        wires.extend((0..cable.wires as usize).map(|i| cable_bytes[i / 8] >> (i % 8) & 1 == 1));
+        start += cable_bytes.len();
+    }
+    wires
+}
+
+/// A sized hexadecimal literal, with the first wire as the lowest bit
+fn literal(wires: &[bool]) -> String {
+    let digits: String = (0..wires.len().div_ceil(4).max(1))
+        .rev()
+        .map(|digit| {
+            let value = (0..4)
+                .filter(|bit| wires.get(digit * 4 + bit) == Some(&true))
+                .fold(0, |value, bit| value | 1 << bit);
+            char::from_digit(value, 16).unwrap()
+        })
+        .collect();
+    format!("{}'h{digits}", wires.len().max(1))
+}
+
+/// Like [`range`], for the concatenation of several cables
+fn range_wide(wires: u32) -> String {
+    match wires {
+        0 | 1 => String::new(),
+        wires => format!("[{}:0] ", wires - 1),
+    }
+}
+
+fn mask(wires: u32) -> u64 {
+    (1 << wires) - 1
+}
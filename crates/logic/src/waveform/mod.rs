@@ -0,0 +1,114 @@
+//! Recording of the signals of a simulation over time
+
+mod vcd;
+
+use crate::*;
+use digolog_module_loader::*;
+
+/// Records the value of some cables after each tick of a [`ChapterRunner`].
+///
+/// Only the changes are stored, so it can record long simulations.
+pub struct WaveformRecorder {
+    probes: Vec<Probe>,
+    /// Name of the top scope
+    chapter: String,
+    /// Scope and name of each probe. The probes of the chapter ports have no scope.
+    names: Vec<(Option<String>, String)>,
+    /// Wires of each probe
+    widths: Vec<u8>,
+    /// Last recorded value of each probe, `None` if it is not driven
+    last: Vec<Option<Vec<u8>>>,
+    changes: Vec<Change>,
+    /// Number of recorded samples
+    samples: u64,
+}
+
+struct Change {
+    /// Number of the sample
+    time: u64,
+    /// Index in `probes`
+    probe: usize,
+    value: Option<Vec<u8>>,
+}
+
+impl WaveformRecorder {
+    /// Prepares the recording of `probes`.
+    /// Use [`Probe::all`] to record everything.
+    ///
+    /// # Panic
+    /// If a probe refers to a missing port or pin.
+    pub fn new(chapter: &Chapter, solution: &ChapterSolution, probes: Vec<Probe>) -> Self {
+        let cable = |probe: &Probe| match *probe {
+            Probe::Input(port) => &chapter.inputs[port],
+            Probe::Output(port) => &chapter.outputs[port],
+            Probe::Block { block, pin } => &solution.blocks[block].shape.outputs[pin],
+        };
+
+        let names = (probes.iter())
+            .map(|probe| {
+                let cable = cable(probe);
+                match *probe {
+                    Probe::Input(_) | Probe::Output(_) => (None, cable.lable.clone()),
+                    Probe::Block { block, pin } => {
+                        let scope = format!("{}_{block}", solution.blocks[block].shape.lable);
+                        let name = match cable.lable.is_empty() {
+                            true => format!("out{pin}"),
+                            false => cable.lable.clone(),
+                        };
+                        (Some(scope), name)
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            chapter: chapter.id.title.clone(),
+            names,
+            widths: probes.iter().map(|probe| cable(probe).wires).collect(),
+            last: vec![None; probes.len()],
+            changes: Vec::new(),
+            samples: 0,
+            probes,
+        }
+    }
+
+    pub fn probes(&self) -> &[Probe] {
+        &self.probes
+    }
+
+    /// Number of recorded samples
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Records the current value of the probes. Call it after each tick.
+    pub fn sample(&mut self, runner: &ChapterRunner) {
+        for (probe_index, probe) in self.probes.iter().enumerate() {
+            let value = runner.probe(*probe);
+
+            let last = &mut self.last[probe_index];
+            if self.samples == 0 || last.as_deref() != value {
+                *last = value.map(|value| value.to_vec());
+                self.changes.push(Change {
+                    time: self.samples,
+                    probe: probe_index,
+                    value: last.clone(),
+                });
+            }
+        }
+        self.samples += 1;
+    }
+
+    /// Value of each probe on a sample, `None` if it was not driven
+    pub fn values_at(&self, sample: u64) -> Vec<Option<&[u8]>> {
+        let mut values = vec![None; self.probes.len()];
+        for change in self
+            .changes
+            .iter()
+            .take_while(|change| change.time <= sample)
+        {
+            values[change.probe] = change.value.as_deref();
+        }
+        values
+    }
+}
@@ -0,0 +1,147 @@
+//! Export of the recorded signals to the Value Change Dump format (IEEE 1364),
+//! which can be opened with waveform viewers like GTKWave.
+
+use super::*;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+impl WaveformRecorder {
+    /// Writes the recording as a VCD file.
+    ///
+    /// Each sample is one nanosecond. The chapter ports are in a scope named
+    /// like the chapter, with a nested scope for each block named `lable_index`.
+    pub fn write_vcd(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "$version digolog $end")?;
+        writeln!(out, "$timescale 1 ns $end")?;
+        writeln!(out, "$scope module {} $end", vcd_name(&self.chapter))?;
+
+        // Probes of each scope, in the order they first appear
+        let mut scopes: Vec<(Option<&str>, Vec<usize>)> = Vec::new();
+        for (probe, (scope, _)) in self.names.iter().enumerate() {
+            let scope = scope.as_deref();
+            match scopes.iter_mut().find(|(s, _)| *s == scope) {
+                Some((_, probes)) => probes.push(probe),
+                None => scopes.push((scope, vec![probe])),
+            }
+        }
+        // The ports go before the nested scopes
+        scopes.sort_by_key(|(scope, _)| scope.is_some());
+
+        for (scope, probes) in &scopes {
+            if let Some(scope) = scope {
+                writeln!(out, "$scope module {} $end", vcd_name(scope))?;
+            }
+
+            let mut used_names = HashMap::new();
+            for &probe in probes {
+                let name = vcd_name(&self.names[probe].1);
+                // Two cables of a scope can have the same lable
+                let repeated: &mut usize = used_names.entry(name.clone()).or_default();
+                let name = match *repeated {
+                    0 => name,
+                    n => format!("{name}_{n}"),
+                };
+                *repeated += 1;
+
+                let width = self.widths[probe].max(1);
+                writeln!(out, "$var wire {width} {} {name} $end", identifier(probe))?;
+            }
+
+            if scope.is_some() {
+                writeln!(out, "$upscope $end")?;
+            }
+        }
+
+        writeln!(out, "$upscope $end")?;
+        writeln!(out, "$enddefinitions $end")?;
+
+        let mut time = None;
+        for change in &self.changes {
+            if time != Some(change.time) {
+                time = Some(change.time);
+                writeln!(out, "#{}", change.time)?;
+            }
+            write_value(&mut out, self.widths[change.probe], change.value.as_deref())?;
+            writeln!(out, "{}", identifier(change.probe))?;
+        }
+        if self.samples > 0 {
+            writeln!(out, "#{}", self.samples)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a value without the identifier. `None` is written as unknown.
+/// A cable without wires has no bytes, and is written as a 0 on one wire.
+fn write_value(out: &mut impl Write, wires: u8, value: Option<&[u8]>) -> io::Result<()> {
+    let bit = |wire: usize| match value {
+        Some(value) => match value.get(wire / 8).map_or(0, |byte| byte >> (wire % 8) & 1) {
+            0 => '0',
+            _ => '1',
+        },
+        None => 'x',
+    };
+
+    if wires <= 1 {
+        return write!(out, "{}", bit(0));
+    }
+    write!(out, "b")?;
+    for wire in (0..wires as usize).rev() {
+        write!(out, "{}", bit(wire))?;
+    }
+    write!(out, " ")
+}
+
+/// Short code of a probe, made of the printable ASCII characters
+fn identifier(mut probe: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - b'!' + 1) as usize;
+
+    let mut code = String::new();
+    loop {
+        code.push((FIRST + (probe % COUNT) as u8) as char);
+        probe /= COUNT;
+        if probe == 0 {
+            return code;
+        }
+        probe -= 1;
+    }
+}
+
+/// Removes the characters that can not be part of a VCD name
+fn vcd_name(name: &str) -> String {
+    let name: String = (name.chars())
+        .map(|c| match c.is_ascii_graphic() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    match name.is_empty() {
+        true => "_".into(),
+        false => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(wires: u8, value: Option<&[u8]>) -> String {
+        let mut out = Vec::new();
+        write_value(&mut out, wires, value).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn values_are_written_from_the_highest_wire() {
+        assert_eq!(value(1, Some(&[1])), "1");
+        assert_eq!(value(10, Some(&[0b1000_0001, 0b10])), "b1010000001 ");
+        assert_eq!(value(3, None), "bxxx ");
+    }
+
+    #[test]
+    fn a_cable_without_bytes_is_zero() {
+        assert_eq!(value(0, Some(&[])), "0");
+        assert_eq!(value(0, None), "x");
+    }
+}
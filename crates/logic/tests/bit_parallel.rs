@@ -0,0 +1,133 @@
+//! Truth tables verified on many rows at once, compared with the scalar runner
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// Verifies the truth table with and without bit-parallel simulation,
+/// and checks that both find the same rows
+fn verify(
+    chapter: &Chapter,
+    solution: &ChapterSolution,
+    blocks: &ModuleBlocks,
+) -> TruthTableReport {
+    let mut verifier = ChapterVerifier::new(chapter, solution, blocks);
+    let packed = verifier.verify_truth_table().unwrap();
+    verifier.bit_parallel = false;
+    assert_eq!(verifier.verify_truth_table().unwrap(), packed);
+    packed
+}
+
+/// A single block, with a pin wired to each port of the chapter
+fn single_block(chapter: &Chapter, name: &str) -> ChapterSolution {
+    let mut solution = chapter.new_solution();
+    let (inputs, outputs) = (chapter.inputs.clone(), chapter.outputs.clone());
+    solution.blocks.push(block(name, inputs, outputs));
+    for i in 0..chapter.inputs.len() {
+        solution.wires.push(wire(port(i), pin(0, i)));
+    }
+    for i in 0..chapter.outputs.len() {
+        solution.wires.push(wire(pin(0, i), port(i)));
+    }
+    solution
+}
+
+/// The outputs of a single block, with the given value and width of each input
+fn evaluate(name: &str, inputs: &[(u8, u64)], outputs: &[u8]) -> Vec<Vec<u8>> {
+    let cables = |wires: &mut dyn Iterator<Item = u8>| wires.map(|w| cable("", w)).collect();
+    let chapter = chapter(
+        name,
+        cables(&mut inputs.iter().map(|&(wires, _)| wires)),
+        cables(&mut outputs.iter().copied()),
+    );
+    let blocks = arithmetic_blocks();
+    let mut runner = ChapterRunner::new(&chapter, &single_block(&chapter, name), &blocks);
+    for (port, (_, value)) in inputs.iter().enumerate() {
+        runner.set_input(port, &value.to_le_bytes());
+    }
+    runner.settle().unwrap();
+    (0..outputs.len())
+        .map(|port| runner.output(port).to_vec())
+        .collect()
+}
+
+#[test]
+fn wide_ports_are_verified_bit_parallel() {
+    let mut chapter = chapter(
+        "Wide and",
+        vec![cable("a", 100), cable("b", 100)],
+        vec![cable("r", 100)],
+    );
+    let pattern = 0x00ff_00ff_00ff_00ff;
+    chapter.truth_table = Some(table(&[
+        &[("a", !0), ("b", pattern), ("r", pattern)],
+        &[("a", 1 << 63), ("b", 1 << 63), ("r", 1 << 63)],
+        &[("a", 3), ("b", 5), ("r", 7)],
+    ]));
+    let blocks = gate_blocks();
+    let solution = single_block(&chapter, "And");
+    assert!(ChapterProgram::compile(&chapter, &solution, &blocks).is_levelized());
+
+    let report = verify(&chapter, &solution, &blocks);
+    assert_eq!(report.checked_rows, 3);
+    let mismatch = report.mismatch.unwrap();
+    assert_eq!(mismatch.inputs, [3, 5]);
+    assert_eq!(mismatch.outputs, [1]);
+}
+
+#[test]
+fn arithmetic_blocks_are_verified_row_by_row() {
+    let blocks = arithmetic_blocks();
+
+    let mut addition = chapter(
+        "Addition",
+        vec![cable("a", 4), cable("b", 4)],
+        vec![cable("sum", 5)],
+    );
+    addition.truth_table = Some(function_table("Adder"));
+    let report = verify(&addition, &single_block(&addition, "Add"), &blocks);
+    assert!(report.passed(), "{report:?}");
+    assert_eq!(report.checked_rows, 256);
+
+    let mut subtraction = chapter(
+        "Subtraction",
+        vec![cable("a", 4), cable("b", 4)],
+        vec![cable("difference", 4), cable("sign", 1)],
+    );
+    subtraction.truth_table = Some(function_table("Subtractor"));
+    let solution = single_block(&subtraction, "Sub");
+    let report = verify(&subtraction, &solution, &blocks);
+    assert!(report.passed(), "{report:?}");
+
+    // b - a instead of a - b
+    let mut swapped = single_block(&subtraction, "Sub");
+    swapped.wires[0].sink = pin(0, 1);
+    swapped.wires[1].sink = pin(0, 0);
+    let mismatch = verify(&subtraction, &swapped, &blocks).mismatch.unwrap();
+    assert_eq!(mismatch.inputs, [1, 0]);
+    assert_eq!(mismatch.outputs, [15, 1]);
+}
+
+#[test]
+fn arithmetic_blocks_use_the_width_of_their_cables() {
+    assert_eq!(evaluate("Add", &[(4, 9), (4, 8), (4, 7)], &[5]), [[24]]);
+    assert_eq!(evaluate("Add", &[(4, 9), (4, 8)], &[4]), [[1]]);
+    assert_eq!(evaluate("Sub", &[(4, 3), (4, 5)], &[8, 1]), [[0xfe], [1]]);
+    assert_eq!(evaluate("Sub", &[(4, 5), (4, 3)], &[4, 1]), [[2], [0]]);
+    assert_eq!(
+        evaluate("Mul", &[(8, 200), (8, 100)], &[16]),
+        [[0x20, 0x4e]]
+    );
+    assert_eq!(evaluate("Div", &[(8, 200), (4, 7)], &[8]), [[28]]);
+    assert_eq!(evaluate("Mod", &[(8, 200), (4, 7)], &[4]), [[4]]);
+
+    // Dividing by 0
+    assert_eq!(evaluate("Div", &[(8, 200), (4, 0)], &[6]), [[63]]);
+    assert_eq!(evaluate("Mod", &[(8, 200), (4, 0)], &[8]), [[200]]);
+
+    // Cables wider than the values of the chapter ports
+    let carry = evaluate("Add", &[(100, u64::MAX), (100, 1)], &[100]);
+    assert_eq!(carry[0][..9], [0, 0, 0, 0, 0, 0, 0, 0, 1]);
+}
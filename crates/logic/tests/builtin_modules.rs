@@ -0,0 +1,116 @@
+//! The modules shipped with the game, verified with their own blocks
+
+mod common;
+
+use common::{block, cable, pin, port, wire};
+use digolog_logic::*;
+use digolog_module_loader::*;
+use std::path::Path;
+
+fn fundamentals() -> Module {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../builtin_modules/Fundamentals");
+    Module::from_path(path, "builtin".into()).unwrap()
+}
+
+#[test]
+fn fundamental_gates_have_logic() {
+    let module = fundamentals();
+    for (name, gate) in [
+        ("Not", Gate::Not),
+        ("And", Gate::And),
+        ("Or", Gate::Or),
+        ("XOr", Gate::XOr),
+        ("Equal", Gate::Equal),
+    ] {
+        let logic = module.blocks[name].logic.as_ref();
+        assert!(
+            matches!(logic, Some(BlockLogic::Gate(g)) if *g == gate),
+            "{name} is not a {gate:?} gate"
+        );
+    }
+}
+
+#[test]
+fn fundamental_arithmetic_blocks_have_logic() {
+    let module = fundamentals();
+    for (name, arithmetic) in [
+        ("Add", Arithmetic::Add),
+        ("Sub", Arithmetic::Sub),
+        ("Mul", Arithmetic::Mul),
+        ("Div", Arithmetic::Div),
+        ("Mod", Arithmetic::Mod),
+    ] {
+        let logic = module.blocks[name].logic.as_ref();
+        assert!(
+            matches!(logic, Some(BlockLogic::Arithmetic(a)) if *a == arithmetic),
+            "{name} is not a {arithmetic:?} block"
+        );
+    }
+}
+
+#[test]
+fn subtraction_is_simulated_with_the_sub_block() {
+    let module = fundamentals();
+    let chapter = module.find_chapter("Arithmetic/Subtraction").unwrap();
+    let mut solution = chapter.new_solution();
+    solution.blocks.push(block(
+        "Sub",
+        vec![cable("", 4), cable("sub", 4)],
+        vec![cable("diff", 4), cable("sign", 1)],
+    ));
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(pin(0, 0), port(0)),
+        wire(pin(0, 1), port(1)),
+    ];
+
+    let report = (ChapterVerifier::new(chapter, &solution, &module.blocks))
+        .verify_truth_table()
+        .unwrap();
+    assert!(report.passed(), "{report:?}");
+    assert_eq!(report.checked_rows, 256);
+}
+
+#[test]
+fn half_adder_is_verified_bit_parallel() {
+    let module = fundamentals();
+    let chapter = module.find_chapter("Arithmetic/Half Adder").unwrap();
+    let solution = chapter.reference_solution.as_ref().unwrap();
+    let program = ChapterProgram::compile(chapter, solution, &module.blocks);
+    assert!(program.is_levelized(), "the packed runner is not used");
+
+    let verifier = ChapterVerifier::new(chapter, solution, &module.blocks);
+    let report = verifier.verify_truth_table().unwrap();
+    assert!(report.passed(), "{report:?}");
+    assert_eq!(report.checked_rows, 4);
+
+    // The sum and the carry swapped
+    let swapped = ChapterSolution {
+        completion_status: ChapterCompletionStatus::NotStarted,
+        blocks: solution.blocks.clone(),
+        wires: (solution.wires.iter())
+            .map(|wire| match wire.sink {
+                PinRef::Chapter { port } => Wire {
+                    source: wire.source,
+                    sink: PinRef::Chapter { port: 1 - port },
+                },
+                _ => *wire,
+            })
+            .collect(),
+    };
+    let verifier = ChapterVerifier::new(chapter, &swapped, &module.blocks);
+    let report = verifier.verify_truth_table().unwrap();
+    let mismatch = report.mismatch.expect("the swapped outputs are found");
+    assert_eq!(mismatch.inputs, [1, 0]);
+}
+
+#[test]
+fn fundamental_reference_solutions_pass() {
+    let module = fundamentals();
+    let reports = check_reference_solutions(&module);
+    assert!(!reports.is_empty());
+    for report in reports {
+        assert_eq!(report.result, Ok(()), "{}", report.chapter.id.title);
+    }
+}
@@ -0,0 +1,205 @@
+//! Chapters, blocks and solutions built in memory for the tests
+
+#![allow(dead_code)]
+
+use digolog_math::*;
+use digolog_module_loader::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub fn block_desc(name: &str, logic: BlockLogic) -> (String, Arc<BlockDesc>) {
+    let desc = BlockDesc {
+        id: BlockDescId { name: name.into() },
+        lable: name.into(),
+        group: "Test".into(),
+        color: "#00f".into(),
+        inputs: vec![],
+        outputs: vec![],
+        logic: Some(logic),
+        cost: 1,
+        constants: Default::default(),
+    };
+    (name.into(), Arc::new(desc))
+}
+
+pub fn cable(lable: &str, wires: u8) -> BlockCable {
+    BlockCable {
+        lable: lable.into(),
+        wires,
+    }
+}
+
+pub fn block(name: &str, inputs: Vec<BlockCable>, outputs: Vec<BlockCable>) -> Block {
+    Block {
+        shape: BlockShape {
+            description: BlockDescId { name: name.into() },
+            lable: name.into(),
+            inputs,
+            outputs,
+            layout: PinLayout::default(),
+        },
+        pos: Vec2::new(0, 0),
+        orientation: Orientation::default(),
+    }
+}
+
+pub fn chapter(title: &str, inputs: Vec<BlockCable>, outputs: Vec<BlockCable>) -> Chapter {
+    Chapter {
+        id: ChapterId {
+            book_id: BookId {
+                module_id: ModuleId {
+                    name: "Test".into(),
+                    namespace: "test".into(),
+                },
+                title: "Test".into(),
+            },
+            title: title.into(),
+        },
+        allowed_blocks: vec![],
+        inputs,
+        outputs,
+        truth_table: None,
+        test: None,
+        reference_solution: None,
+        completion_status: ChapterCompletionStatus::NotStarted,
+        best_metrics: None,
+    }
+}
+
+/// A truth table computed by the reference function `name`
+pub fn function_table(name: &str) -> TruthTable {
+    TruthTable {
+        format: String::new(),
+        rows: TruthTableRows::Function(name.into()),
+        max_exhaustive_wires: None,
+        random_rows: None,
+    }
+}
+
+/// A truth table with the values of the ports on each row
+pub fn table(rows: &[&[(&str, u64)]]) -> TruthTable {
+    let rows = (rows.iter())
+        .map(|row| {
+            row.iter()
+                .map(|&(port, value)| (port.into(), value))
+                .collect()
+        })
+        .collect();
+    TruthTable {
+        format: String::new(),
+        rows: TruthTableRows::Table(rows),
+        max_exhaustive_wires: None,
+        random_rows: None,
+    }
+}
+
+pub fn wire(source: PinRef, sink: PinRef) -> Wire {
+    Wire { source, sink }
+}
+
+pub fn pin(block: usize, pin: usize) -> PinRef {
+    PinRef::Block { block, pin }
+}
+
+pub fn port(port: usize) -> PinRef {
+    PinRef::Chapter { port }
+}
+
+/// A xorshift generator, so the generated circuits are the same on every run
+pub struct Random(pub u32);
+
+impl Random {
+    pub fn below(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as usize % max
+    }
+}
+
+fn register_state(_: &BlockShape) -> usize {
+    1
+}
+
+fn register_output(state: BlockState, mut output: BlockOutputMut) {
+    output[0] = state[0];
+}
+
+/// Stores the `data` input when the `write` input is 1
+fn register_update(input: BlockInput, mut state: BlockStateMut) {
+    if input[1] & 1 == 1 {
+        state[0] = input[0];
+    }
+}
+
+/// The arithmetic blocks, named like their operation
+pub fn arithmetic_blocks() -> ModuleBlocks {
+    [
+        block_desc("Add", BlockLogic::Arithmetic(Arithmetic::Add)),
+        block_desc("Sub", BlockLogic::Arithmetic(Arithmetic::Sub)),
+        block_desc("Mul", BlockLogic::Arithmetic(Arithmetic::Mul)),
+        block_desc("Div", BlockLogic::Arithmetic(Arithmetic::Div)),
+        block_desc("Mod", BlockLogic::Arithmetic(Arithmetic::Mod)),
+    ]
+    .into()
+}
+
+/// The logic gates and a one wire register
+pub fn gate_blocks() -> ModuleBlocks {
+    [
+        block_desc("Not", BlockLogic::Gate(Gate::Not)),
+        block_desc("And", BlockLogic::Gate(Gate::And)),
+        block_desc("Or", BlockLogic::Gate(Gate::Or)),
+        block_desc("XOr", BlockLogic::Gate(Gate::XOr)),
+        block_desc(
+            "Register",
+            BlockLogic::Sequential {
+                state_bytes: register_state,
+                output: register_output,
+                update: register_update,
+            },
+        ),
+    ]
+    .into()
+}
+
+/// A register that adds the `step` input to its value on each tick
+pub fn counter() -> (Chapter, ChapterSolution, ModuleBlocks) {
+    let chapter = chapter("Counter", vec![cable("step", 8)], vec![cable("count", 8)]);
+    let mut solution = chapter.new_solution();
+    solution.blocks = vec![
+        block(
+            "Register",
+            vec![cable("data", 8), cable("write", 1)],
+            vec![cable("", 8)],
+        ),
+        block("Add", vec![cable("", 8); 2], vec![cable("", 8)]),
+        block("Not", vec![cable("", 1)], vec![cable("", 1)]),
+    ];
+    solution.wires = vec![
+        wire(pin(0, 0), pin(1, 0)),
+        wire(port(0), pin(1, 1)),
+        wire(pin(1, 0), pin(0, 0)),
+        // The register is always written: the Not of an unconnected input is 1
+        wire(pin(2, 0), pin(0, 1)),
+        wire(pin(0, 0), port(0)),
+    ];
+    let mut blocks = gate_blocks();
+    blocks.extend(arithmetic_blocks());
+    (chapter, solution, blocks)
+}
+
+/// Writes a module with one block group and one book in a new folder, with an empty
+/// `solutions` folder. `name` is the folder, in the temporary folder of the tests.
+pub fn write_module(name: &str, blocks: &str, book: &str) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&path);
+    for folder in ["blocks", "books", "solutions"] {
+        std::fs::create_dir_all(path.join(folder)).unwrap();
+    }
+    let module = "name = \"Test\"\nrequirements = []\n";
+    std::fs::write(path.join("module.toml"), module).unwrap();
+    std::fs::write(path.join("blocks/Test.toml"), blocks).unwrap();
+    std::fs::write(path.join("books/Book.toml"), book).unwrap();
+    path
+}
@@ -0,0 +1,130 @@
+//! Chapters completed by their solutions, and their progress saved with the solutions
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+use std::path::PathBuf;
+
+fn half_adder() -> Chapter {
+    let mut chapter = chapter(
+        "Half adder",
+        vec![cable("a", 1), cable("b", 1)],
+        vec![cable("sum", 1), cable("carry", 1)],
+    );
+    chapter.truth_table = Some(TruthTable {
+        format: String::new(),
+        rows: TruthTableRows::Function("HalfAdder".into()),
+        max_exhaustive_wires: None,
+        random_rows: None,
+    });
+    chapter
+}
+
+/// A half adder with the given gate for the sum, and optionally a double negation of the carry
+fn solution(chapter: &Chapter, sum_gate: &str, double_not: bool) -> ChapterSolution {
+    let gate = || vec![cable("", 1), cable("", 1)];
+    let mut solution = chapter.new_solution();
+    solution.blocks = vec![
+        block(sum_gate, gate(), vec![cable("", 1)]),
+        block("And", gate(), vec![cable("", 1)]),
+    ];
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(port(0), pin(1, 0)),
+        wire(port(1), pin(1, 1)),
+        wire(pin(0, 0), port(0)),
+    ];
+    if double_not {
+        for _ in 0..2 {
+            (solution.blocks).push(block("Not", vec![cable("", 1)], vec![cable("", 1)]));
+        }
+        solution.wires.push(wire(pin(1, 0), pin(2, 0)));
+        solution.wires.push(wire(pin(2, 0), pin(3, 0)));
+        solution.wires.push(wire(pin(3, 0), port(1)));
+    } else {
+        solution.wires.push(wire(pin(1, 0), port(1)));
+    }
+    solution
+}
+
+#[test]
+fn completed_solutions_record_the_best_metrics() {
+    let blocks = gate_blocks();
+    let mut chapter = half_adder();
+
+    let mut wrong = solution(&chapter, "Or", false);
+    let completion = complete_chapter(&mut chapter, &mut wrong, &blocks).unwrap();
+    assert_eq!(completion, Completion::Failed);
+    assert_eq!(wrong.completion_status, ChapterCompletionStatus::InProgress);
+    assert_eq!(
+        chapter.completion_status,
+        ChapterCompletionStatus::NotStarted
+    );
+    assert_eq!(chapter.best_metrics, None);
+
+    let mut slow = solution(&chapter, "XOr", true);
+    let Completion::Completed { metrics, best } =
+        complete_chapter(&mut chapter, &mut slow, &blocks).unwrap()
+    else {
+        panic!("the solution is correct");
+    };
+    assert!(best);
+    assert_eq!(metrics.blocks(), 4);
+    assert_eq!(slow.completion_status, ChapterCompletionStatus::Completed);
+    assert_eq!(
+        chapter.completion_status,
+        ChapterCompletionStatus::Completed
+    );
+
+    let mut fast = solution(&chapter, "XOr", false);
+    let completion = complete_chapter(&mut chapter, &mut fast, &blocks).unwrap();
+    assert!(matches!(
+        completion,
+        Completion::Completed { best: true, .. }
+    ));
+    let best_metrics = chapter.best_metrics.clone().unwrap();
+    assert_eq!(best_metrics.blocks(), 2);
+
+    let completion = complete_chapter(&mut chapter, &mut slow, &blocks).unwrap();
+    assert!(matches!(
+        completion,
+        Completion::Completed { best: false, .. }
+    ));
+    assert_eq!(chapter.best_metrics, Some(best_metrics));
+
+    // A wrong solution does not undo the completion of the chapter
+    complete_chapter(&mut chapter, &mut wrong, &blocks).unwrap();
+    assert_eq!(
+        chapter.completion_status,
+        ChapterCompletionStatus::Completed
+    );
+}
+
+#[test]
+fn progress_is_saved_with_the_solution() {
+    let blocks = gate_blocks();
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("half_adder.solution.toml");
+    let mut chapter = half_adder();
+    let mut solved = solution(&chapter, "XOr", false);
+    complete_chapter(&mut chapter, &mut solved, &blocks).unwrap();
+    chapter.save_solution(&solved, &path).unwrap();
+
+    let mut loaded_chapter = half_adder();
+    let loaded = loaded_chapter.load_solution(&path).unwrap();
+    assert_eq!(loaded.completion_status, ChapterCompletionStatus::Completed);
+    assert_eq!(loaded.wires, solved.wires);
+    assert_eq!(
+        loaded_chapter.completion_status,
+        ChapterCompletionStatus::Completed
+    );
+    assert_eq!(loaded_chapter.best_metrics, chapter.best_metrics);
+
+    // Solutions saved without their chapter still load
+    solved.save(&path).unwrap();
+    let mut loaded_chapter = half_adder();
+    assert!(loaded_chapter.load_solution(&path).is_some());
+    assert_eq!(loaded_chapter.best_metrics, None);
+}
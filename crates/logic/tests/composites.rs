@@ -0,0 +1,110 @@
+//! Composite blocks made from chapter solutions, saved and loaded with their module
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+use std::path::PathBuf;
+
+fn test_module() -> Module {
+    let id = ModuleId {
+        name: "Test".into(),
+        namespace: "test".into(),
+    };
+    Module::new(id, gate_blocks())
+}
+
+fn half_adder() -> (Chapter, ChapterSolution) {
+    let mut chapter = chapter(
+        "Half Adder",
+        vec![cable("a", 1), cable("b", 1)],
+        vec![cable("sum", 1), cable("carry", 1)],
+    );
+    chapter.truth_table = Some(function_table("HalfAdder"));
+    let gate = |name| block(name, vec![cable("", 1), cable("", 1)], vec![cable("", 1)]);
+    let mut solution = chapter.new_solution();
+    solution.blocks = vec![gate("XOr"), gate("And")];
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(port(0), pin(1, 0)),
+        wire(port(1), pin(1, 1)),
+        wire(pin(0, 0), port(0)),
+        wire(pin(1, 0), port(1)),
+    ];
+    (chapter, solution)
+}
+
+/// A full adder made of two half adders, whose carries are joined by an Or
+fn full_adder(chapter: &Chapter, half_adder: &str) -> ChapterSolution {
+    let half = || {
+        block(
+            half_adder,
+            vec![cable("a", 1), cable("b", 1)],
+            vec![cable("sum", 1), cable("carry", 1)],
+        )
+    };
+    let mut solution = chapter.new_solution();
+    solution.blocks = vec![
+        half(),
+        half(),
+        block("Or", vec![cable("", 1), cable("", 1)], vec![cable("", 1)]),
+    ];
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(pin(0, 0), pin(1, 0)),
+        wire(port(2), pin(1, 1)),
+        wire(pin(0, 1), pin(2, 0)),
+        wire(pin(1, 1), pin(2, 1)),
+        wire(pin(1, 0), port(0)),
+        wire(pin(2, 0), port(1)),
+    ];
+    solution
+}
+
+#[test]
+fn composites_are_loaded_as_saved() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("composites.toml");
+    let mut module = test_module();
+    let (half_chapter, half_solution) = half_adder();
+    let half_adder = BlockDesc::from_solution(
+        BlockDescId {
+            name: "Player Half Adder".into(),
+        },
+        "Player".into(),
+        "#0ff".into(),
+        &half_chapter,
+        &half_solution,
+    );
+    module.add_composite(half_adder);
+    module.save_composites(&path).unwrap();
+
+    let mut loaded = test_module();
+    assert!(!loaded.blocks.contains_key("Player Half Adder"));
+    loaded.load_composites(&path).unwrap();
+    let desc = &loaded.blocks["Player Half Adder"];
+    assert_eq!(desc.lable, "Half Adder");
+    assert_eq!(desc.group, "Player");
+    assert_eq!(desc.color, module.blocks["Player Half Adder"].color);
+    assert_eq!(desc.inputs.len(), 2);
+    assert_eq!(desc.outputs.len(), 2);
+
+    let mut chapter = chapter(
+        "Full Adder",
+        vec![cable("a", 1), cable("b", 1), cable("c", 1)],
+        vec![cable("sum", 1), cable("carry", 1)],
+    );
+    chapter.truth_table = Some(function_table("FullAdder"));
+    let solution = full_adder(&chapter, "Player Half Adder");
+    let verifier = ChapterVerifier::new(&chapter, &solution, &loaded.blocks);
+    let report = verifier.verify_truth_table().unwrap();
+    assert!(report.passed(), "{report:?}");
+    assert_eq!(report.checked_rows, 8);
+
+    // Saving the loaded composites gives the same file
+    let saved = std::fs::read_to_string(&path).unwrap();
+    loaded.save_composites(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), saved);
+}
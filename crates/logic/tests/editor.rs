@@ -0,0 +1,236 @@
+//! Edits of a solution, checked against the palette of the chapter
+
+mod common;
+
+use common::write_module;
+use digolog_logic::*;
+use digolog_math::*;
+use digolog_module_loader::*;
+
+const BLOCKS: &str = r##"color = "#fff"
+
+[blocks.And]
+lable = "&"
+inputs = ["bundle<N, W>"]
+outputs = ["cable<W>"]
+gate = "and"
+
+[blocks.Or]
+lable = "|"
+inputs = ["bundle"]
+outputs = ["cable"]
+gate = "or"
+
+[blocks.Decoder]
+lable = "Dec"
+inputs = ["cable<C>"]
+outputs = ["bundle<B, 1>"]
+constant.C = "B:bits()"
+"##;
+
+const BOOK: &str = r#"allowed_blocks = ["Or", "Decoder"]
+
+[[chapters]]
+title = "Both"
+allowed_blocks = ["And<N=3, W=2>"]
+inputs = ["a[2]", "b[2]", "c[2]"]
+outputs = ["r[2]"]
+"#;
+
+fn module(name: &str) -> Module {
+    Module::from_path(write_module(name, BLOCKS, BOOK), "test".into()).unwrap()
+}
+
+fn editor(module: &Module) -> SolutionEditor<'_> {
+    let book = module.iter_books().next().unwrap();
+    let chapter = book.iter_chapters().next().unwrap();
+    SolutionEditor::new(book, chapter, chapter.new_solution())
+}
+
+fn subset<'a>(editor: &SolutionEditor<'a>, name: &str) -> &'a BlockDescSubset {
+    (editor.chapter().allowed_blocks.iter())
+        .find(|subset| subset.block_desc.id.name == name)
+        .unwrap()
+}
+
+fn cables(wires: &[u8]) -> Vec<BlockCable> {
+    (wires.iter())
+        .map(|&wires| BlockCable {
+            lable: String::new(),
+            wires,
+        })
+        .collect()
+}
+
+fn shape(name: &str, inputs: &[u8], outputs: &[u8]) -> BlockShape {
+    BlockShape {
+        description: BlockDescId { name: name.into() },
+        lable: name.into(),
+        inputs: cables(inputs),
+        outputs: cables(outputs),
+        layout: PinLayout::default(),
+    }
+}
+
+#[test]
+fn placed_blocks_have_the_template_values_of_the_palette() {
+    let module = module("editor_place");
+    let mut editor = editor(&module);
+    let and = subset(&editor, "And");
+    let index = editor.place(and, Vec2::new(0, 0)).unwrap();
+
+    let shape = &editor.solution().blocks[index].shape;
+    let wires = |cables: &[BlockCable]| cables.iter().map(|c| c.wires).collect::<Vec<_>>();
+    assert_eq!(wires(&shape.inputs), [2, 2, 2]);
+    assert_eq!(wires(&shape.outputs), [2]);
+}
+
+#[test]
+fn placed_shapes_are_validated() {
+    let module = module("editor_place_shape");
+    let mut editor = editor(&module);
+    let pos = Vec2::new(0, 0);
+
+    assert_eq!(
+        editor.place_shape(shape("And", &[2, 2, 2], &[2]), pos),
+        Ok(0)
+    );
+    assert_eq!(
+        editor.place_shape(shape("Or", &[1, 1, 1, 1], &[1]), pos),
+        Ok(1)
+    );
+    // Decoder<B=4, C=2>
+    assert_eq!(
+        editor.place_shape(shape("Decoder", &[2], &[1; 4]), pos),
+        Ok(2)
+    );
+
+    let and = BlockDescId { name: "And".into() };
+    assert_eq!(
+        editor.place_shape(shape("And", &[2, 2], &[2]), pos),
+        Err(EditError::BlockNotAllowed(and.clone())),
+        "the palette only has And<N=3, W=2>"
+    );
+    assert_eq!(
+        editor.place_shape(shape("And", &[2, 2, 2], &[1]), pos),
+        Err(EditError::InvalidShape(and)),
+        "the inputs and the output have a different width"
+    );
+    assert_eq!(
+        editor.place_shape(shape("Not", &[1], &[1]), pos),
+        Err(EditError::BlockNotAllowed(BlockDescId {
+            name: "Not".into()
+        }))
+    );
+    let Err(EditError::TemplateConflict { block: 3, conflict }) =
+        editor.place_shape(shape("Decoder", &[3], &[1; 4]), pos)
+    else {
+        panic!("4 cables need 2 wires to be selected, not 3");
+    };
+    assert_eq!(conflict.name, "C");
+    assert_eq!(editor.solution().blocks.len(), 3);
+}
+
+/// The position of every block of the solution
+fn positions(editor: &SolutionEditor) -> Vec<Vec2<i32>> {
+    (editor.solution().blocks.iter())
+        .map(|block| block.pos)
+        .collect()
+}
+
+#[test]
+fn edits_are_undone_and_redone() {
+    let module = module("editor_undo");
+    let mut editor = editor(&module);
+    let and = subset(&editor, "And");
+    editor.place(and, Vec2::new(0, 0)).unwrap();
+    editor.move_block(0, Vec2::new(5, 1)).unwrap();
+    let a = PinRef::Chapter { port: 0 };
+    let and_in = PinRef::Block { block: 0, pin: 0 };
+    editor.connect(a, and_in).unwrap();
+    editor.delete_block(0).unwrap();
+    assert_eq!(editor.solution().wires, []);
+
+    // The deleted block comes back with its wire
+    assert!(editor.undo());
+    assert_eq!(positions(&editor), [Vec2::new(5, 1)]);
+    assert_eq!(editor.solution().wires.len(), 1);
+    assert!(editor.undo());
+    assert_eq!(editor.solution().wires, []);
+    assert!(editor.undo());
+    assert_eq!(positions(&editor), [Vec2::new(0, 0)]);
+    assert!(editor.undo());
+    assert!(editor.solution().blocks.is_empty());
+    assert!(!editor.undo());
+    assert!(!editor.can_undo());
+
+    assert!(editor.redo());
+    assert!(editor.redo());
+    assert_eq!(positions(&editor), [Vec2::new(5, 1)]);
+    // A new edit forgets the undone edits
+    editor.move_block(0, Vec2::new(2, 2)).unwrap();
+    assert!(!editor.can_redo());
+    assert!(!editor.redo());
+    assert!(editor.undo());
+    assert_eq!(positions(&editor), [Vec2::new(5, 1)]);
+}
+
+#[test]
+fn the_history_forgets_the_oldest_edits() {
+    let module = module("editor_capacity");
+    let book = module.iter_books().next().unwrap();
+    let chapter = book.iter_chapters().next().unwrap();
+    let mut editor =
+        SolutionEditor::with_history_capacity(book, chapter, chapter.new_solution(), 2);
+    let and = subset(&editor, "And");
+    editor.place(and, Vec2::new(0, 0)).unwrap();
+    for x in 1..=3 {
+        editor.move_block(0, Vec2::new(x, 0)).unwrap();
+    }
+
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert!(!editor.undo(), "only the last 2 moves are kept");
+    assert_eq!(positions(&editor), [Vec2::new(1, 0)]);
+    assert!(editor.redo());
+    assert!(editor.redo());
+    assert_eq!(positions(&editor), [Vec2::new(3, 0)]);
+}
+
+#[test]
+fn the_moves_of_a_drag_are_undone_together() {
+    let module = module("editor_drag");
+    let mut editor = editor(&module);
+    let and = subset(&editor, "And");
+    editor.place(and, Vec2::new(0, 0)).unwrap();
+    editor.place(and, Vec2::new(0, 4)).unwrap();
+
+    for x in 1..=3 {
+        assert_eq!(
+            editor.drag_block(0, Vec2::new(x, 0)),
+            Ok(Vec2::new(x - 1, 0))
+        );
+    }
+    editor.end_drag();
+    editor.drag_block(0, Vec2::new(4, 0)).unwrap();
+    // Dragging another block is another step
+    editor.drag_block(1, Vec2::new(1, 4)).unwrap();
+    editor.drag_block(1, Vec2::new(2, 4)).unwrap();
+
+    assert!(editor.undo());
+    assert_eq!(positions(&editor), [Vec2::new(4, 0), Vec2::new(0, 4)]);
+    assert!(editor.undo());
+    assert_eq!(positions(&editor), [Vec2::new(3, 0), Vec2::new(0, 4)]);
+    assert!(editor.undo());
+    assert_eq!(positions(&editor), [Vec2::new(0, 0), Vec2::new(0, 4)]);
+
+    // The drag does not continue a move that was redone
+    assert!(editor.redo());
+    editor.drag_block(0, Vec2::new(7, 0)).unwrap();
+    assert!(editor.undo());
+    assert_eq!(positions(&editor), [Vec2::new(3, 0), Vec2::new(0, 4)]);
+    assert_eq!(
+        editor.drag_block(5, Vec2::new(0, 0)),
+        Err(EditError::NoSuchBlock(5))
+    );
+}
@@ -0,0 +1,92 @@
+//! Solutions proven equal to the reference function of their chapter
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// A half adder made of a `gate` for the sum and an `And` for the carry
+fn half_adder(gate: &str) -> (Chapter, ChapterSolution) {
+    let mut chapter = chapter(
+        "Half Adder",
+        vec![cable("a", 1), cable("b", 1)],
+        vec![cable("sum", 1), cable("carry", 1)],
+    );
+    chapter.truth_table = Some(function_table("HalfAdder"));
+    let mut solution = chapter.new_solution();
+    let inputs = || vec![cable("", 1), cable("", 1)];
+    solution
+        .blocks
+        .push(block(gate, inputs(), vec![cable("", 1)]));
+    solution
+        .blocks
+        .push(block("And", inputs(), vec![cable("", 1)]));
+    for block in 0..2 {
+        solution.wires.push(wire(port(0), pin(block, 0)));
+        solution.wires.push(wire(port(1), pin(block, 1)));
+        solution.wires.push(wire(pin(block, 0), port(block)));
+    }
+    (chapter, solution)
+}
+
+#[test]
+fn a_correct_solution_is_proven() {
+    let (chapter, solution) = half_adder("XOr");
+    let blocks = gate_blocks();
+    let verifier = ChapterVerifier::new(&chapter, &solution, &blocks);
+    assert_eq!(verifier.prove_equivalence(), Ok(Equivalence::Proven));
+}
+
+#[test]
+fn a_wide_adder_is_proven() {
+    let mut chapter = chapter(
+        "Addition",
+        vec![cable("a", 32), cable("b", 32)],
+        vec![cable("sum", 33)],
+    );
+    chapter.truth_table = Some(function_table("Adder"));
+    let mut solution = chapter.new_solution();
+    solution.blocks.push(block(
+        "Add",
+        vec![cable("", 32), cable("", 32)],
+        vec![cable("", 33)],
+    ));
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(pin(0, 0), port(0)),
+    ];
+    let blocks = arithmetic_blocks();
+    let verifier = ChapterVerifier::new(&chapter, &solution, &blocks);
+    assert_eq!(verifier.prove_equivalence(), Ok(Equivalence::Proven));
+}
+
+#[test]
+fn a_wrong_solution_has_a_counterexample() {
+    let (chapter, solution) = half_adder("Or");
+    let blocks = gate_blocks();
+    let verifier = ChapterVerifier::new(&chapter, &solution, &blocks);
+    let Ok(Equivalence::Counterexample(mismatch)) = verifier.prove_equivalence() else {
+        panic!("an Or is not a XOr");
+    };
+    // Only 1 + 1 tells an Or from a XOr
+    assert_eq!(mismatch.inputs, [1, 1]);
+    assert_eq!(mismatch.outputs, [1, 1]);
+    assert_eq!(mismatch.expected, [Some(0), Some(1)]);
+}
+
+#[test]
+fn the_ports_must_match_the_function() {
+    let (mut chapter, solution) = half_adder("XOr");
+    chapter.truth_table = Some(function_table("FullAdder"));
+    let blocks = gate_blocks();
+    let verifier = ChapterVerifier::new(&chapter, &solution, &blocks);
+    assert_eq!(
+        verifier.prove_equivalence(),
+        Err(FormalError::WrongPorts {
+            inputs: 3,
+            outputs: 2
+        })
+    );
+}
@@ -0,0 +1,102 @@
+//! Circuits imported from a Logisim 2.7 file, with the blocks of the Fundamentals module
+
+use digolog_logic::*;
+use digolog_module_loader::*;
+use std::path::Path;
+
+const CIRC: &str = include_str!("fixtures/half_adder.circ");
+
+fn fundamentals() -> Module {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../builtin_modules/Fundamentals");
+    Module::from_path(path, "builtin".into()).unwrap()
+}
+
+fn names(solution: &ChapterSolution) -> Vec<&str> {
+    (solution.blocks.iter())
+        .map(|block| block.shape.description.name.as_str())
+        .collect()
+}
+
+/// Every block has the cables of its description
+fn assert_valid_shapes(solution: &ChapterSolution, blocks: &ModuleBlocks) {
+    for block in &solution.blocks {
+        let desc = &blocks[&block.shape.description.name];
+        assert!(
+            desc.template_values(&block.shape).is_some(),
+            "{:?} is not a valid {}",
+            block.shape,
+            desc.id.name
+        );
+    }
+}
+
+#[test]
+fn gates_pins_and_tunnels_are_imported() {
+    let module = fundamentals();
+    let chapter = module.find_chapter("Arithmetic/Half Adder").unwrap();
+    let import = import_logisim(CIRC, None, chapter, &module.blocks).unwrap();
+    assert_eq!(import.untranslated, []);
+
+    let solution = &import.solution;
+    assert_eq!(names(solution), ["And", "XOr"]);
+    assert_valid_shapes(solution, &module.blocks);
+    // The inputs a and b are connected with wires and with tunnels
+    assert_eq!(solution.wires.len(), 6);
+
+    let report = (ChapterVerifier::new(chapter, solution, &module.blocks))
+        .verify_truth_table()
+        .unwrap();
+    assert!(report.passed(), "{report:?}");
+}
+
+#[test]
+fn constants_and_unconnected_enables_are_gates_with_their_inputs() {
+    let module = fundamentals();
+    let chapter = module.find_chapter("Arithmetic/Half Adder").unwrap();
+    let import = import_logisim(CIRC, Some("extras"), chapter, &module.blocks).unwrap();
+
+    let solution = &import.solution;
+    assert_eq!(
+        names(solution),
+        ["Or", "Not", "Or", "Register", "Or", "Not"]
+    );
+    assert_valid_shapes(solution, &module.blocks);
+    assert_eq!(solution.blocks[0].shape.inputs.len(), 0);
+    assert_eq!(solution.blocks[2].shape.outputs[0].wires, 4);
+
+    let wire = |source: usize, sink: usize, pin: usize| Wire {
+        source: PinRef::Block {
+            block: source,
+            pin: 0,
+        },
+        sink: PinRef::Block { block: sink, pin },
+    };
+    // The enable of the register is 1, like in Logisim
+    assert_eq!(
+        solution.wires,
+        [wire(0, 1, 0), wire(4, 5, 0), wire(5, 3, 1)]
+    );
+}
+
+#[test]
+fn left_out_components_are_reported() {
+    let module = fundamentals();
+    let chapter = module.find_chapter("Arithmetic/Half Adder").unwrap();
+    let import = import_logisim(CIRC, Some("extras"), chapter, &module.blocks).unwrap();
+
+    let untranslated = |component: &str, x, y, reason| Untranslated {
+        component: component.into(),
+        loc: [x, y].into(),
+        reason,
+    };
+    assert_eq!(
+        import.untranslated,
+        [
+            untranslated("Decoder", 300, 300, UntranslatedReason::UnknownComponent),
+            untranslated("main", 500, 300, UntranslatedReason::Subcircuit),
+        ]
+    );
+
+    let error = import_logisim(CIRC, Some("missing"), chapter, &module.blocks).err();
+    assert_eq!(error, Some(LogisimError::NoCircuit("missing".into())));
+}
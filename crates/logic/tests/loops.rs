@@ -0,0 +1,119 @@
+//! Combinational loops found in the wires of a solution
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+fn solution(blocks: Vec<Block>, wires: Vec<Wire>) -> ChapterSolution {
+    let mut solution = chapter("Loops", vec![], vec![]).new_solution();
+    solution.blocks = blocks;
+    solution.wires = wires;
+    solution
+}
+
+fn not() -> Block {
+    block("Not", vec![cable("", 1)], vec![cable("", 1)])
+}
+
+fn register() -> Block {
+    block(
+        "Register",
+        vec![cable("data", 1), cable("write", 1)],
+        vec![cable("", 1)],
+    )
+}
+
+/// A composite whose first output is the negation of its first input,
+/// and whose second output is a register that stores its second input
+fn split_blocks() -> ModuleBlocks {
+    let two = || vec![cable("", 1), cable("", 1)];
+    let composite = CompositeLogic {
+        inputs: two(),
+        outputs: two(),
+        blocks: vec![not(), register()],
+        wires: vec![
+            wire(port(0), pin(0, 0)),
+            wire(pin(0, 0), port(0)),
+            wire(port(1), pin(1, 0)),
+            wire(port(0), pin(1, 1)),
+            wire(pin(1, 0), port(1)),
+        ],
+    };
+    let mut blocks = gate_blocks();
+    let (name, split) = block_desc("Split", BlockLogic::Composite(composite));
+    blocks.insert(name, split);
+    blocks
+}
+
+fn split() -> Block {
+    let two = || vec![cable("", 1), cable("", 1)];
+    block("Split", two(), two())
+}
+
+#[test]
+fn a_gate_wired_to_itself_is_a_loop() {
+    let solution = solution(vec![not()], vec![wire(pin(0, 0), pin(0, 0))]);
+    assert_eq!(
+        find_combinational_loops(&solution, &gate_blocks()),
+        [CombinationalLoop {
+            blocks: vec![0],
+            wires: vec![0],
+        }]
+    );
+}
+
+#[test]
+fn a_register_breaks_a_loop() {
+    let solution = solution(
+        vec![not(), register()],
+        vec![wire(pin(0, 0), pin(1, 0)), wire(pin(1, 0), pin(0, 0))],
+    );
+    assert_eq!(find_combinational_loops(&solution, &gate_blocks()), []);
+}
+
+#[test]
+fn loops_that_share_a_block_are_one_loop() {
+    let and = block("And", vec![cable("", 1), cable("", 1)], vec![cable("", 1)]);
+    let solution = solution(
+        vec![and, not(), not()],
+        vec![
+            wire(pin(0, 0), pin(1, 0)),
+            wire(pin(1, 0), pin(0, 0)),
+            wire(pin(0, 0), pin(2, 0)),
+            wire(pin(2, 0), pin(0, 1)),
+        ],
+    );
+    assert_eq!(
+        find_combinational_loops(&solution, &gate_blocks()),
+        [CombinationalLoop {
+            blocks: vec![0, 1, 2],
+            wires: vec![0, 1, 2, 3],
+        }]
+    );
+}
+
+#[test]
+fn composites_only_loop_through_the_pins_that_depend_on_each_other() {
+    let blocks = split_blocks();
+    // The stored output feeds the stored input: the register breaks the loop
+    let stored = solution(vec![split()], vec![wire(pin(0, 1), pin(0, 1))]);
+    assert_eq!(find_combinational_loops(&stored, &blocks), []);
+
+    // The negated output feeds the stored input, and the stored output the negated input
+    let crossed = solution(
+        vec![split()],
+        vec![wire(pin(0, 0), pin(0, 1)), wire(pin(0, 1), pin(0, 0))],
+    );
+    assert_eq!(find_combinational_loops(&crossed, &blocks), []);
+
+    let negated = solution(vec![split()], vec![wire(pin(0, 0), pin(0, 0))]);
+    assert_eq!(
+        find_combinational_loops(&negated, &blocks),
+        [CombinationalLoop {
+            blocks: vec![0],
+            wires: vec![0],
+        }]
+    );
+}
@@ -0,0 +1,112 @@
+//! Compiled programs saved next to the solutions and loaded again
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+use std::path::PathBuf;
+
+fn cache_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.program.toml"))
+}
+
+/// A chain of `length` Not gates
+fn not_chain(length: usize) -> (Chapter, ChapterSolution) {
+    let chapter = chapter("Chain", vec![cable("a", 1)], vec![cable("r", 1)]);
+    let mut solution = chapter.new_solution();
+    for gate in 0..length {
+        solution
+            .blocks
+            .push(block("Not", vec![cable("", 1)], vec![cable("", 1)]));
+        let source = match gate {
+            0 => port(0),
+            _ => pin(gate - 1, 0),
+        };
+        solution.wires.push(wire(source, pin(gate, 0)));
+    }
+    solution.wires.push(wire(pin(length - 1, 0), port(0)));
+    (chapter, solution)
+}
+
+/// Blocks with a composite half adder whose second output is not driven
+fn composite_blocks() -> ModuleBlocks {
+    let mut blocks = gate_blocks();
+    let half = CompositeLogic {
+        inputs: vec![cable("a", 1), cable("b", 1)],
+        outputs: vec![cable("sum", 1), cable("carry", 1)],
+        blocks: vec![block(
+            "XOr",
+            vec![cable("", 1), cable("", 1)],
+            vec![cable("", 1)],
+        )],
+        wires: vec![
+            wire(port(0), pin(0, 0)),
+            wire(port(1), pin(0, 1)),
+            wire(pin(0, 0), port(0)),
+        ],
+    };
+    let (name, desc) = block_desc("Half", BlockLogic::Composite(half));
+    blocks.insert(name, desc);
+    blocks
+}
+
+#[test]
+fn programs_are_loaded_as_saved() {
+    let blocks = gate_blocks();
+    // Many solutions, so some fingerprints use the highest bit
+    for length in 1..=32 {
+        let (chapter, solution) = not_chain(length);
+        let program = ChapterProgram::compile(&chapter, &solution, &blocks);
+        let path = cache_path(&format!("chain{length}"));
+        program.save(&path).unwrap();
+
+        let loaded = ChapterProgram::load(&path).expect("the program can be loaded");
+        assert!(loaded.is_compiled_from(&chapter, &solution, &blocks));
+
+        let mut runner = ChapterRunner::from_program(loaded, &blocks);
+        runner.set_input(0, &[1]);
+        runner.settle().unwrap();
+        assert_eq!(runner.output(0), [(length % 2 == 0) as u8]);
+    }
+}
+
+#[test]
+fn outputs_that_are_not_driven_are_saved() {
+    let blocks = composite_blocks();
+    let chapter = chapter(
+        "Half adder",
+        vec![cable("a", 1), cable("b", 1)],
+        vec![cable("sum", 1), cable("carry", 1)],
+    );
+    let mut solution = chapter.new_solution();
+    solution.blocks.push(block(
+        "Half",
+        vec![cable("a", 1), cable("b", 1)],
+        vec![cable("sum", 1), cable("carry", 1)],
+    ));
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(pin(0, 0), port(0)),
+        wire(pin(0, 1), port(1)),
+    ];
+
+    let path = cache_path("half");
+    ChapterProgram::compile(&chapter, &solution, &blocks)
+        .save(&path)
+        .unwrap();
+    let loaded = ChapterProgram::load(&path).expect("the program can be loaded");
+    assert!(loaded.is_compiled_from(&chapter, &solution, &blocks));
+
+    let mut runner = ChapterRunner::from_program(loaded, &blocks);
+    runner.set_input(0, &[1]);
+    runner.settle().unwrap();
+    assert_eq!(runner.block_output(0, 0), Some(&[1][..]));
+    assert_eq!(runner.block_output(0, 1), None);
+
+    // The input b is not connected anymore
+    solution.wires.remove(1);
+    let loaded = ChapterProgram::load(&path).unwrap();
+    assert!(!loaded.is_compiled_from(&chapter, &solution, &blocks));
+}
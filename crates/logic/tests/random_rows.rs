@@ -0,0 +1,120 @@
+//! Chapters with too many input wires, checked with edge cases and seeded random rows
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// Adds two 32 wire inputs, and forgets the carry
+fn add_without_carry(input: BlockInput, mut output: BlockOutputMut) {
+    let a = u32::from_le_bytes(input[0..4].try_into().unwrap());
+    let b = u32::from_le_bytes(input[4..8].try_into().unwrap());
+    output[..4].copy_from_slice(&a.wrapping_add(b).to_le_bytes());
+    output[4] = 0;
+}
+
+/// Adds two 32 wire inputs, and adds one more if the wire 20 of `a` is 1 and its wire 0 is 0.
+/// No edge case has these wires.
+fn add_with_hidden_bug(input: BlockInput, mut output: BlockOutputMut) {
+    let a = u32::from_le_bytes(input[0..4].try_into().unwrap()) as u64;
+    let b = u32::from_le_bytes(input[4..8].try_into().unwrap()) as u64;
+    let bug = (a & 0x10_0001 == 0x10_0000) as u64;
+    output[..5].copy_from_slice(&(a + b + bug).to_le_bytes()[..5]);
+}
+
+/// An adder chapter of `wires` wire inputs, solved by one block `name`
+fn adder(wires: u8, name: &str, random_rows: u64) -> (Chapter, ChapterSolution) {
+    let mut chapter = chapter(
+        "Adder",
+        vec![cable("a", wires), cable("b", wires)],
+        vec![cable("sum", wires + 1)],
+    );
+    let mut table = function_table("Adder");
+    table.random_rows = Some(random_rows);
+    chapter.truth_table = Some(table);
+
+    let mut solution = chapter.new_solution();
+    solution.blocks.push(block(
+        name,
+        vec![cable("", wires); 2],
+        vec![cable("", wires + 1)],
+    ));
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(pin(0, 0), port(0)),
+    ];
+    (chapter, solution)
+}
+
+fn blocks() -> ModuleBlocks {
+    let mut blocks = arithmetic_blocks();
+    blocks.extend([
+        block_desc("NoCarry", BlockLogic::Builtin(add_without_carry)),
+        block_desc("Buggy", BlockLogic::Builtin(add_with_hidden_bug)),
+    ]);
+    blocks
+}
+
+fn verify(chapter: &Chapter, solution: &ChapterSolution, seed: Option<u64>) -> TruthTableReport {
+    let blocks = blocks();
+    let mut verifier = ChapterVerifier::new(chapter, solution, &blocks);
+    verifier.seed = seed;
+    verifier.verify_truth_table().unwrap()
+}
+
+#[test]
+fn wide_inputs_check_edge_cases_and_random_rows() {
+    let (chapter, solution) = adder(32, "Add", 100);
+    let report = verify(&chapter, &solution, Some(42));
+    // 0, 1, the sign boundaries and all ones on each input
+    let edge_cases = 5 * 5;
+    assert_eq!(
+        report,
+        TruthTableReport {
+            checked_rows: edge_cases + 100,
+            mismatch: None,
+            seed: Some(42),
+        }
+    );
+}
+
+#[test]
+fn edge_cases_are_checked_first() {
+    let (chapter, solution) = adder(32, "NoCarry", 100);
+    let report = verify(&chapter, &solution, Some(42));
+    let mismatch = report.mismatch.unwrap();
+    // The first carry is 1 + all ones
+    assert_eq!(mismatch.inputs, [1, u32::MAX as u64]);
+    assert_eq!(mismatch.expected, [Some(1 << 32)]);
+    assert_eq!(mismatch.outputs, [0]);
+    assert_eq!(report.checked_rows, 10);
+}
+
+#[test]
+fn random_rows_are_repeated_with_their_seed() {
+    let (chapter, solution) = adder(32, "Buggy", 10_000);
+    let report = verify(&chapter, &solution, Some(7));
+    assert!(report.checked_rows > 25, "the edge cases pass");
+    let mismatch = report.mismatch.as_ref().unwrap();
+    assert_eq!(mismatch.inputs[0] & 0x10_0001, 0x10_0000);
+    assert_eq!(report.seed, Some(7));
+    assert_eq!(verify(&chapter, &solution, report.seed), report);
+
+    let report = verify(&chapter, &solution, None);
+    assert!(report.seed.is_some(), "a new seed is recorded");
+}
+
+#[test]
+fn the_truth_table_sets_the_limit_of_exhaustive_checks() {
+    let (mut chapter, solution) = adder(4, "Add", 10);
+    let report = verify(&chapter, &solution, Some(3));
+    assert_eq!((report.checked_rows, report.seed), (256, None));
+
+    (chapter.truth_table.as_mut().unwrap()).max_exhaustive_wires = Some(4);
+    let report = verify(&chapter, &solution, Some(3));
+    // 0, 1, 7, 8 and 15 on each input
+    assert_eq!((report.checked_rows, report.seed), (25 + 10, Some(3)));
+    assert!(report.passed());
+}
@@ -0,0 +1,108 @@
+//! Reference solutions of a module, checked against the rules of their chapters
+
+mod common;
+
+use common::write_module;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+const BLOCKS: &str = r##"color = "#fff"
+
+[blocks.And]
+lable = "&"
+inputs = ["bundle<N, W>"]
+outputs = ["cable<W>"]
+gate = "and"
+"##;
+
+/// Two inputs, a template value that the reference solution can break
+const CHAPTER: &str = r#"allowed_blocks = ["And<N=2>"]
+
+[[chapters]]
+title = "Both"
+inputs = ["a", "b"]
+outputs = ["r"]
+reference_solution = "solutions/Both.txt"
+"#;
+
+const TRUTH_TABLE: &str = r#"
+[chapters.truth_table]
+table = [{a = 0, b = 0, r = 0}, {a = 0, b = 1, r = 0}, {a = 1, b = 0, r = 0}, {a = 1, b = 1, r = 1}]
+"#;
+
+const SOLUTION: &str = "input a, b
+output r
+
+block and: And(_, _) -> (_) at 2 0
+
+and.0 = a
+and.1 = b
+r = and.0
+";
+
+fn check(name: &str, book: &str, solution: &str) -> Result<(), ReferenceSolutionError> {
+    let path = write_module(name, BLOCKS, book);
+    std::fs::write(path.join("solutions/Both.txt"), solution).unwrap();
+    let module = Module::from_path(path, "test".into()).unwrap();
+    let mut reports = check_reference_solutions(&module);
+    assert_eq!(reports.len(), 1);
+    reports.remove(0).result
+}
+
+#[test]
+fn a_reference_solution_passes() {
+    let book = format!("{CHAPTER}{TRUTH_TABLE}");
+    assert_eq!(check("reference_passes", &book, SOLUTION), Ok(()));
+}
+
+#[test]
+fn a_wrong_reference_solution_fails() {
+    let book = format!("{CHAPTER}{TRUTH_TABLE}");
+    let solution = SOLUTION.replace("and.1 = b", "and.1 = a");
+    let error = check("reference_mismatch", &book, &solution).unwrap_err();
+    let ReferenceSolutionError::Mismatch(mismatch) = error else {
+        panic!("{error:?}");
+    };
+    assert_eq!(mismatch.inputs, [1, 0]);
+}
+
+#[test]
+fn the_template_values_of_the_allowed_blocks_are_enforced() {
+    let book = format!("{CHAPTER}{TRUTH_TABLE}");
+    let solution = SOLUTION
+        .replace("And(_, _)", "And(_, _, _)")
+        .replace("and.1 = b", "and.1, and.2 = b");
+    let error = check("reference_template", &book, &solution).unwrap_err();
+    assert!(
+        matches!(&error, ReferenceSolutionError::BlockNotAllowed { block: 0, desc } if desc.name == "And"),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn a_chapter_without_checks_is_not_verified() {
+    let error = check("reference_nothing", CHAPTER, SOLUTION).unwrap_err();
+    assert_eq!(error, ReferenceSolutionError::NothingToCheck);
+}
+
+#[test]
+fn dangling_wires_are_reported() {
+    let book =
+        format!("{CHAPTER}{TRUTH_TABLE}").replace("solutions/Both.txt", "solutions/Both.toml");
+    let path = write_module("reference_dangling", BLOCKS, &book);
+
+    let mut solution = SolutionText::parse(SOLUTION).unwrap().solution;
+    let dangling = Wire {
+        source: PinRef::Block { block: 0, pin: 0 },
+        sink: PinRef::Block { block: 0, pin: 5 },
+    };
+    solution.wires.push(dangling);
+    solution.save(path.join("solutions/Both.toml")).unwrap();
+
+    let module = Module::from_path(path, "test".into()).unwrap();
+    let reports = check_reference_solutions(&module);
+    assert_eq!(
+        reports[0].result,
+        Err(ReferenceSolutionError::DanglingWire(dangling))
+    );
+}
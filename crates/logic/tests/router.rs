@@ -0,0 +1,155 @@
+//! Orthogonal paths of the wires around the blocks of a solution
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_math::*;
+use digolog_module_loader::*;
+
+/// A block with one input and one output at a point of the grid
+fn buffer(x: i32, y: i32) -> Block {
+    let mut block = block("Not", vec![cable("", 1)], vec![cable("", 1)]);
+    block.pos = Vec2::new(x, y);
+    block
+}
+
+fn solution(blocks: Vec<Block>, wires: Vec<Wire>) -> ChapterSolution {
+    let mut solution = chapter("Route", vec![], vec![]).new_solution();
+    solution.blocks = blocks;
+    solution.wires = wires;
+    solution
+}
+
+fn route(solution: &ChapterSolution) -> Router {
+    Router::route(solution, ChapterPorts::default())
+}
+
+fn points(path: &WirePath) -> Vec<[i32; 2]> {
+    path.points.iter().map(|point| (*point).into()).collect()
+}
+
+/// Every point of a path, from its source to its sink
+fn steps(path: &WirePath) -> Vec<[i32; 2]> {
+    let points = points(path);
+    let mut steps = vec![points[0]];
+    for segment in points.windows(2) {
+        let [a, b] = [segment[0], segment[1]];
+        assert!(
+            a[0] == b[0] || a[1] == b[1],
+            "{a:?} to {b:?} is not orthogonal"
+        );
+        let direction = [(b[0] - a[0]).signum(), (b[1] - a[1]).signum()];
+        let mut point = a;
+        while point != b {
+            point = [point[0] + direction[0], point[1] + direction[1]];
+            steps.push(point);
+        }
+    }
+    steps
+}
+
+/// Whether a point is on a block, or inside it
+fn on_block(solution: &ChapterSolution, [x, y]: [i32; 2]) -> bool {
+    (solution.blocks.iter()).any(|block| {
+        let ([x0, y0], [x1, y1]) = (block.rect().min.into(), block.rect().max.into());
+        (x0..=x1).contains(&x) && (y0..=y1).contains(&y)
+    })
+}
+
+#[test]
+fn aligned_pins_have_a_straight_wire() {
+    let solution = solution(
+        vec![buffer(0, 0), buffer(10, 0)],
+        vec![wire(pin(0, 0), pin(1, 0))],
+    );
+    let router = route(&solution);
+    let path = router.path(&solution.wires[0]).unwrap();
+    assert_eq!(points(path), [[4, 1], [10, 1]]);
+    assert_eq!(path.bends(), 0);
+}
+
+#[test]
+fn wires_go_around_the_blocks() {
+    // The block in the middle covers the row of the pins
+    let solution = solution(
+        vec![buffer(0, 0), buffer(20, 0), buffer(10, -1)],
+        vec![wire(pin(0, 0), pin(1, 0))],
+    );
+    let router = route(&solution);
+    let path = router.path(&solution.wires[0]).unwrap();
+    let steps = steps(path);
+    assert_eq!(steps.first(), Some(&[4, 1]));
+    assert_eq!(steps.last(), Some(&[20, 1]));
+    for step in &steps[1..steps.len() - 1] {
+        assert!(!on_block(&solution, *step), "{step:?} is on a block");
+    }
+    // Around the block and back to the row of the pins
+    assert_eq!(path.bends(), 4);
+}
+
+#[test]
+fn wires_of_different_sources_do_not_cross_when_a_path_as_short_does_not() {
+    // The second wire goes up with two bends, across the first one or left of its source
+    let solution = solution(
+        vec![buffer(20, 0), buffer(40, 0), buffer(0, 4), buffer(30, -4)],
+        vec![wire(pin(0, 0), pin(1, 0)), wire(pin(2, 0), pin(3, 0))],
+    );
+    let router = route(&solution);
+    let first = steps(router.path(&solution.wires[0]).unwrap());
+    let second = router.path(&solution.wires[1]).unwrap();
+    assert_eq!(second.bends(), 2);
+    for step in steps(second) {
+        assert!(!first.contains(&step), "the wires meet at {step:?}");
+    }
+}
+
+#[test]
+fn walled_in_pins_still_have_a_wire() {
+    // The wall covers the left of the input of the sink
+    let solution = solution(
+        vec![buffer(0, 5), buffer(10, 0), buffer(6, 0)],
+        vec![wire(pin(0, 0), pin(1, 0))],
+    );
+    let router = route(&solution);
+    let path = router.path(&solution.wires[0]).unwrap();
+    assert_eq!(points(path), [[4, 6], [7, 6], [7, 1], [10, 1]]);
+}
+
+#[test]
+fn only_the_wires_of_a_moved_block_are_routed_again() {
+    let mut solution = solution(
+        vec![
+            buffer(0, 0),
+            buffer(20, 0),
+            buffer(0, 10),
+            buffer(20, 10),
+            buffer(40, 40),
+        ],
+        vec![wire(pin(0, 0), pin(1, 0)), wire(pin(2, 0), pin(3, 0))],
+    );
+    let [top, bottom] = [solution.wires[0], solution.wires[1]];
+    let mut router = route(&solution);
+    let kept = router.path(&bottom).unwrap().clone();
+
+    // The unconnected block moves onto the top wire
+    solution.blocks[4].pos = Vec2::new(10, -1);
+    assert_eq!(router.block_moved(&solution, 4), [top]);
+    let steps = steps(router.path(&top).unwrap());
+    assert!(steps[1..steps.len() - 1]
+        .iter()
+        .all(|step| !on_block(&solution, *step)));
+    assert_eq!(router.path(&bottom), Some(&kept));
+
+    // The sink of the top wire moves, and the wire follows its pin
+    solution.blocks[1].pos = Vec2::new(20, 4);
+    assert_eq!(router.block_moved(&solution, 1), [top]);
+    let path = router.path(&top).unwrap();
+    assert_eq!(path.points.last(), Some(&Vec2::new(20, 5)));
+    assert_eq!(router.path(&bottom), Some(&kept));
+
+    // A removed wire is forgotten
+    solution.wires.remove(0);
+    assert_eq!(router.update(&solution), []);
+    assert_eq!(router.path(&top), None);
+}
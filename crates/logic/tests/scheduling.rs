@@ -0,0 +1,148 @@
+//! The scheduling modes of the runner give the same results as the full sweep
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// Wires per layer of the generated circuits
+const WIDTH: usize = 6;
+/// Layers of blocks of the generated circuits
+const DEPTH: usize = 5;
+const TICKS: usize = 40;
+
+/// Layers of gates and registers, where each block reads blocks of the previous layer.
+/// With `loops`, some gates also read blocks of their own layer or of later layers.
+fn generate_circuit(random: &mut Random, loops: bool) -> (Chapter, ChapterSolution) {
+    let chapter = chapter(
+        "Generated",
+        (0..WIDTH).map(|_| cable("in", 1)).collect(),
+        (0..WIDTH).map(|_| cable("out", 1)).collect(),
+    );
+    let mut solution = chapter.new_solution();
+
+    for _ in 0..DEPTH * WIDTH {
+        let (name, inputs) = match random.below(5) {
+            0 => ("Not", 1),
+            1 => ("And", 2),
+            2 => ("Or", 2),
+            3 => ("XOr", 2),
+            _ => ("Register", 2),
+        };
+        let inputs = (0..inputs).map(|_| cable("", 1)).collect();
+        solution
+            .blocks
+            .push(block(name, inputs, vec![cable("", 1)]));
+    }
+
+    for (index, placed) in solution.blocks.iter().enumerate() {
+        let layer = index / WIDTH;
+        for input in 0..placed.shape.inputs.len() {
+            let source = match layer {
+                _ if loops
+                    && placed.shape.description.name != "Register"
+                    && random.below(4) == 0 =>
+                {
+                    pin(layer * WIDTH + random.below((DEPTH - layer) * WIDTH), 0)
+                }
+                0 => port(random.below(WIDTH)),
+                _ => pin((layer - 1) * WIDTH + random.below(WIDTH), 0),
+            };
+            solution.wires.push(wire(source, pin(index, input)));
+        }
+    }
+    for output in 0..WIDTH {
+        solution
+            .wires
+            .push(wire(pin((DEPTH - 1) * WIDTH + output, 0), port(output)));
+    }
+    (chapter, solution)
+}
+
+/// Ticks a runner of each mode with the same random inputs,
+/// and checks that they give the same results as the first one after every tick
+fn assert_same_ticks(
+    chapter: &Chapter,
+    solution: &ChapterSolution,
+    blocks: &ModuleBlocks,
+    modes: &[Scheduling],
+    random: &mut Random,
+) {
+    let mut runners: Vec<ChapterRunner> = (modes.iter())
+        .map(|mode| {
+            let mut runner = ChapterRunner::new(chapter, solution, blocks);
+            runner.settle_limit = 32;
+            runner.set_scheduling(*mode);
+            runner
+        })
+        .collect();
+
+    for tick in 0..TICKS {
+        for port in 0..WIDTH {
+            let value = [random.below(2) as u8];
+            for runner in &mut runners {
+                runner.set_input(port, &value);
+            }
+        }
+        let results: Vec<_> = runners.iter_mut().map(ChapterRunner::tick).collect();
+
+        let (first, others) = runners.split_first().unwrap();
+        for ((mode, runner), result) in modes[1..].iter().zip(others).zip(&results[1..]) {
+            assert_eq!(&results[0], result, "tick {tick} with {mode:?}");
+            for port in 0..WIDTH {
+                assert_eq!(
+                    first.output(port),
+                    runner.output(port),
+                    "output {port} on tick {tick} with {mode:?}"
+                );
+            }
+            for block in 0..solution.blocks.len() {
+                assert_eq!(
+                    first.block_output(block, 0),
+                    runner.block_output(block, 0),
+                    "block {block} on tick {tick} with {mode:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn levelized_circuits() {
+    let blocks = gate_blocks();
+    let mut random = Random(0x2545_f491);
+    for _ in 0..20 {
+        let (chapter, solution) = generate_circuit(&mut random, false);
+        let modes = [
+            Scheduling::FullSweep,
+            Scheduling::EventDriven,
+            Scheduling::Levelized,
+        ];
+        assert_same_ticks(&chapter, &solution, &blocks, &modes, &mut random);
+    }
+}
+
+#[test]
+fn circuits_with_loops() {
+    let blocks = gate_blocks();
+    let mut random = Random(0x9e37_79b9);
+    let mut looping = 0;
+    for _ in 0..20 {
+        let (chapter, solution) = generate_circuit(&mut random, true);
+        let program = ChapterProgram::compile(&chapter, &solution, &blocks);
+        let modes: &[Scheduling] = match program.is_levelized() {
+            true => &[
+                Scheduling::FullSweep,
+                Scheduling::EventDriven,
+                Scheduling::Levelized,
+            ],
+            false => {
+                looping += 1;
+                &[Scheduling::FullSweep, Scheduling::EventDriven]
+            }
+        };
+        assert_same_ticks(&chapter, &solution, &blocks, modes, &mut random);
+    }
+    assert!(looping > 10, "only {looping} circuits have a loop");
+}
@@ -0,0 +1,70 @@
+//! Graphviz and SVG drawings of a solution, with lables that have special characters
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// A `Not` of 4 wires between the chapter ports, with lables that need escaping
+fn solution() -> (Chapter, ChapterSolution) {
+    let chapter = chapter(
+        "A \"quoted\" \\ title",
+        vec![cable("a<b & c", 4)],
+        vec![cable("\"r\"", 4)],
+    );
+    let mut solution = chapter.new_solution();
+    let mut not = block("Not", vec![cable("<in>", 4)], vec![cable("", 4)]);
+    not.shape.lable = "{x|y}".into();
+    solution.blocks.push(not);
+    solution.wires = vec![wire(port(0), pin(0, 0)), wire(pin(0, 0), port(0))];
+    (chapter, solution)
+}
+
+#[test]
+fn dot_strings_and_records_are_escaped() {
+    let (chapter, solution) = solution();
+    let dot = export_dot(&chapter, &solution, &gate_blocks());
+    let lines: Vec<&str> = dot.lines().collect();
+
+    assert_eq!(lines[0], r#"digraph "A \"quoted\" \\ title" {"#);
+    assert!(lines.contains(&r#"    in0 [shape=rarrow, label="a<b & c"];"#));
+    assert!(lines.contains(&r#"    out0 [shape=rarrow, label="\"r\""];"#));
+    // The input pin has its lable, the output pin its number
+    assert!(
+        lines.contains(&r##"    b0 [shape=record, style=filled, fillcolor="#0000ff", fontcolor="#ffffff", label="{ <i0> \<in\> } | \{x\|y\} | { <o0> 0 }"];"##),
+        "{dot}"
+    );
+    assert!(lines.contains(&r#"    in0 -> b0:i0:w [label="4", penwidth=2.5];"#));
+    assert!(lines.contains(&r#"    b0:o0:e -> out0 [label="4", penwidth=2.5];"#));
+    assert_eq!(lines.last(), Some(&"}"));
+}
+
+#[test]
+fn svg_text_is_escaped() {
+    let (chapter, solution) = solution();
+    let svg = export_svg(&chapter, &solution, &gate_blocks());
+    let document = roxmltree::Document::parse(&svg).unwrap();
+
+    let texts: Vec<&str> = (document.descendants())
+        .filter(|node| node.has_tag_name("title") || node.has_tag_name("text"))
+        .filter_map(|node| node.text())
+        .collect();
+    assert_eq!(texts[0], "A \"quoted\" \\ title");
+    for text in ["{x|y}", "<in>", "a<b & c[4]", "\"r\"[4]"] {
+        assert!(texts.contains(&text), "{text:?} is not in {texts:?}");
+    }
+
+    let fills: Vec<&str> = (document.descendants())
+        .filter(|node| node.has_tag_name("rect"))
+        .filter_map(|node| node.attribute("fill"))
+        .collect();
+    assert_eq!(
+        fills,
+        ["#ffffff", "#0000ff"],
+        "the background and the block"
+    );
+    // One path for each wire
+    let paths = document.descendants().filter(|n| n.has_tag_name("path"));
+    assert_eq!(paths.count(), 2);
+}
@@ -0,0 +1,120 @@
+//! Sequence tests of clocked chapters, run step by step against a solution
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// A chapter with a `d` input, a `clk` input and a `q` output
+fn latch_chapter(clock: Option<usize>, steps: Vec<TestStep>) -> Chapter {
+    let mut chapter = chapter(
+        "Latch",
+        vec![cable("d", 8), cable("clk", 1)],
+        vec![cable("q", 8)],
+    );
+    chapter.test = Some(SequenceTest { clock, steps });
+    chapter
+}
+
+fn step(inputs: [Option<u64>; 2], q: Option<u64>) -> TestStep {
+    TestStep {
+        inputs: inputs.to_vec(),
+        expected: vec![q],
+    }
+}
+
+/// A register that stores `d` when `clk` is 1
+fn latch(chapter: &Chapter) -> ChapterSolution {
+    let mut solution = chapter.new_solution();
+    solution.blocks.push(block(
+        "Register",
+        vec![cable("data", 8), cable("write", 1)],
+        vec![cable("", 8)],
+    ));
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(1), pin(0, 1)),
+        wire(pin(0, 0), port(0)),
+    ];
+    solution
+}
+
+fn verify(chapter: &Chapter, solution: &ChapterSolution) -> SequenceTestReport {
+    let blocks = gate_blocks();
+    let verifier = ChapterVerifier::new(chapter, solution, &blocks);
+    verifier.verify_sequence_test().unwrap()
+}
+
+#[test]
+fn each_step_is_a_rising_edge_of_the_clock() {
+    // The clock is not set by the steps, the verifier toggles it
+    let steps = vec![
+        step([Some(5), None], Some(5)),
+        step([None, None], Some(5)),
+        step([Some(7), None], None),
+        step([None, None], Some(7)),
+    ];
+    let chapter = latch_chapter(Some(1), steps);
+    let report = verify(&chapter, &latch(&chapter));
+    assert_eq!(
+        report,
+        SequenceTestReport {
+            checked_steps: 4,
+            mismatch: None,
+        }
+    );
+}
+
+#[test]
+fn without_a_clock_each_step_is_a_tick() {
+    // The register only stores when the steps set `clk`
+    let steps = vec![
+        step([Some(5), Some(0)], Some(0)),
+        step([None, Some(1)], Some(5)),
+        step([Some(9), Some(0)], Some(5)),
+    ];
+    let chapter = latch_chapter(None, steps);
+    assert!(verify(&chapter, &latch(&chapter)).passed());
+}
+
+#[test]
+fn the_first_mismatch_is_reported() {
+    let steps = vec![
+        step([Some(5), None], Some(5)),
+        // Not checked, so the wrong value is ignored
+        step([Some(6), None], None),
+        step([Some(7), None], Some(9)),
+        step([Some(8), None], Some(1)),
+    ];
+    let chapter = latch_chapter(Some(1), steps);
+    let report = verify(&chapter, &latch(&chapter));
+    assert_eq!(report.checked_steps, 3);
+    assert_eq!(
+        report.mismatch,
+        Some(Mismatch {
+            inputs: vec![7, 1],
+            expected: vec![Some(9)],
+            outputs: vec![7],
+        })
+    );
+}
+
+#[test]
+fn the_inputs_are_cut_to_the_width_of_their_port() {
+    let chapter = latch_chapter(Some(1), vec![step([Some(0x1FF), None], Some(0xFF))]);
+    assert!(verify(&chapter, &latch(&chapter)).passed());
+}
+
+#[test]
+fn a_chapter_without_a_sequence_test_can_not_be_verified() {
+    let mut chapter = latch_chapter(None, vec![]);
+    chapter.test = None;
+    let solution = latch(&chapter);
+    let blocks = gate_blocks();
+    let verifier = ChapterVerifier::new(&chapter, &solution, &blocks);
+    assert_eq!(
+        verifier.verify_sequence_test(),
+        Err(VerifyError::NoSequenceTest)
+    );
+}
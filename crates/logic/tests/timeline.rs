@@ -0,0 +1,102 @@
+//! Snapshots of a simulation, and a timeline to step back and forth between ticks
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+
+fn tick(runner: &mut ChapterRunner, step: u8) -> u8 {
+    runner.set_input(0, &[step]);
+    runner.tick().unwrap();
+    runner.output(0)[0]
+}
+
+#[test]
+fn a_snapshot_restores_the_signals_and_the_states() {
+    let (chapter, solution, blocks) = counter();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    for _ in 0..3 {
+        tick(&mut runner, 1);
+    }
+    assert_eq!(runner.output(0), [3]);
+    let snapshot = runner.snapshot();
+    assert_eq!(snapshot.tick(), 3);
+
+    tick(&mut runner, 10);
+    tick(&mut runner, 10);
+    assert_eq!((runner.ticks(), runner.output(0)), (5, &[23][..]));
+
+    runner.restore(&snapshot);
+    assert_eq!((runner.ticks(), runner.output(0)), (3, &[3][..]));
+    assert_eq!(tick(&mut runner, 1), 4, "the register has its old value");
+}
+
+#[test]
+fn the_timeline_steps_back_and_forth() {
+    let (chapter, solution, blocks) = counter();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    // Key frames on ticks 1, 4 and 7, deltas in between
+    let mut timeline = Timeline::with_keyframe_interval(100, 3);
+    for _ in 0..8 {
+        tick(&mut runner, 2);
+        timeline.record(&runner);
+    }
+    assert_eq!(timeline.ticks(), Some(1..=8));
+
+    for count in (1..8).rev() {
+        assert!(timeline.step_back(&mut runner));
+        assert_eq!(
+            (runner.ticks(), runner.output(0)[0]),
+            (count, 2 * count as u8)
+        );
+    }
+    assert!(!timeline.step_back(&mut runner), "tick 1 is the first one");
+
+    assert!(timeline.step_forward(&mut runner));
+    assert_eq!(runner.output(0), [4]);
+    assert!(timeline.jump(&mut runner, 6));
+    assert_eq!(runner.output(0), [12]);
+    assert!(!timeline.jump(&mut runner, 9));
+    assert_eq!(runner.ticks(), 6);
+}
+
+#[test]
+fn recording_after_going_back_discards_the_future() {
+    let (chapter, solution, blocks) = counter();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    let mut timeline = Timeline::with_keyframe_interval(100, 4);
+    for _ in 0..6 {
+        tick(&mut runner, 1);
+        timeline.record(&runner);
+    }
+
+    assert!(timeline.jump(&mut runner, 3));
+    assert_eq!(tick(&mut runner, 10), 13);
+    timeline.record(&runner);
+    assert_eq!(timeline.ticks(), Some(1..=4));
+    assert_eq!(timeline.snapshot_at(4), Some(runner.snapshot()));
+    assert!(!timeline.step_forward(&mut runner));
+}
+
+#[test]
+fn the_timeline_keeps_the_last_ticks() {
+    let (chapter, solution, blocks) = counter();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    let mut timeline = Timeline::with_keyframe_interval(5, 3);
+    let mut snapshots = Vec::new();
+    for _ in 0..12 {
+        tick(&mut runner, 3);
+        timeline.record(&runner);
+        snapshots.push(runner.snapshot());
+    }
+
+    // The oldest kept frame was a delta, it is rebuilt as a key frame
+    assert_eq!(timeline.ticks(), Some(8..=12));
+    assert_eq!(timeline.snapshot_at(7), None);
+    for snapshot in &snapshots[7..] {
+        assert_eq!(
+            timeline.snapshot_at(snapshot.tick()).as_ref(),
+            Some(snapshot)
+        );
+    }
+}
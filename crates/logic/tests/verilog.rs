@@ -0,0 +1,34 @@
+//! Solutions exported to structural Verilog
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// A block that reads its input and has no outputs
+fn probe(_: BlockInput, _: BlockOutputMut) {}
+
+#[test]
+fn blocks_without_outputs_assign_nothing() {
+    let mut blocks = gate_blocks();
+    let (name, desc) = block_desc("Probe", BlockLogic::Builtin(probe));
+    blocks.insert(name, desc);
+
+    let chapter = chapter("Probed", vec![cable("a", 1)], vec![cable("r", 1)]);
+    let mut solution = chapter.new_solution();
+    solution.blocks = vec![
+        block("Probe", vec![cable("", 1)], vec![]),
+        block("Not", vec![cable("", 1)], vec![cable("", 1)]),
+    ];
+    solution.wires = vec![
+        wire(port(0), pin(0, 0)),
+        wire(port(0), pin(1, 0)),
+        wire(pin(1, 0), port(0)),
+    ];
+
+    let verilog = export_verilog(&chapter, &solution, &blocks).unwrap();
+    assert!(verilog.contains("module Probe"), "{verilog}");
+    assert!(!verilog.contains("table_out"), "{verilog}");
+    assert!(!verilog.contains("assign {}"), "{verilog}");
+}
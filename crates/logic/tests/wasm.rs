@@ -0,0 +1,148 @@
+//! Blocks written in WebAssembly, run by the runner through the ABI of [`WasmLogic`]
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+/// Adds its two input bytes
+const ADDER: &str = r#"(module
+    (memory (export "memory") 2)
+    (func (export "buffer") (param i32) (result i32) (i32.const 1024))
+    (func (export "evaluate") (param $inputs i32) (param i32) (param $outputs i32) (param i32)
+        (i32.store8 (local.get $outputs)
+            (i32.add
+                (i32.load8_u (local.get $inputs))
+                (i32.load8_u offset=1 (local.get $inputs))))))"#;
+
+/// Adds its input to a byte of state on each tick
+const COUNTER: &str = r#"(module
+    (memory (export "memory") 2)
+    (func (export "buffer") (param i32) (result i32) (i32.const 1024))
+    (func (export "state_bytes") (param i32 i32) (result i32) (i32.const 1))
+    (func (export "output") (param $state i32) (param i32) (param $outputs i32) (param i32)
+        (i32.store8 (local.get $outputs) (i32.load8_u (local.get $state))))
+    (func (export "update") (param $inputs i32) (param i32) (param $state i32) (param i32)
+        (i32.store8 (local.get $state)
+            (i32.add
+                (i32.load8_u (local.get $state))
+                (i32.load8_u (local.get $inputs))))))"#;
+
+/// Never returns
+const LOOP: &str = r#"(module
+    (memory (export "memory") 2)
+    (func (export "buffer") (param i32) (result i32) (i32.const 1024))
+    (func (export "evaluate") (param i32 i32 i32 i32) (loop $forever (br $forever))))"#;
+
+fn wasm_blocks(wat: &str) -> ModuleBlocks {
+    let code = wat::parse_str(wat).unwrap();
+    let logic = BlockLogic::Wasm(WasmLogic { code: code.into() });
+    [block_desc("Wasm", logic)].into()
+}
+
+/// A chapter with `inputs` bytes in and one byte out, solved by one `Wasm` block
+fn single_block(inputs: usize) -> (Chapter, ChapterSolution) {
+    let chapter = chapter("Wasm", vec![cable("", 8); inputs], vec![cable("", 8)]);
+    let mut solution = chapter.new_solution();
+    (solution.blocks).push(block(
+        "Wasm",
+        vec![cable("", 8); inputs],
+        vec![cable("", 8)],
+    ));
+    for input in 0..inputs {
+        solution.wires.push(wire(port(input), pin(0, input)));
+    }
+    solution.wires.push(wire(pin(0, 0), port(0)));
+    (chapter, solution)
+}
+
+#[test]
+fn a_block_reads_its_inputs_and_writes_its_outputs() {
+    let (chapter, solution) = single_block(2);
+    let blocks = wasm_blocks(ADDER);
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    for (a, b) in [(0, 0), (3, 4), (200, 100)] {
+        runner.set_input(0, &[a]);
+        runner.set_input(1, &[b]);
+        runner.settle().unwrap();
+        assert_eq!(runner.output(0), [a.wrapping_add(b)]);
+    }
+    assert_eq!(runner.faults(), []);
+}
+
+#[test]
+fn a_block_keeps_its_state_between_ticks() {
+    let (chapter, solution) = single_block(1);
+    let blocks = wasm_blocks(COUNTER);
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    runner.set_input(0, &[5]);
+    runner.settle().unwrap();
+    assert_eq!(runner.output(0), [0], "the state starts at 0");
+    for count in 1..=3 {
+        runner.tick().unwrap();
+        assert_eq!(runner.output(0), [5 * count]);
+    }
+}
+
+#[test]
+fn a_block_that_runs_out_of_fuel_is_stopped() {
+    let (chapter, solution) = single_block(1);
+    let blocks = wasm_blocks(LOOP);
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    runner.set_input(0, &[1]);
+    runner.settle().unwrap();
+    assert_eq!(runner.output(0), [0]);
+    assert_eq!(
+        runner.faults(),
+        [BlockFault {
+            block: 0,
+            error: WasmError::OutOfFuel,
+        }]
+    );
+
+    // The block is not called again
+    runner.set_input(0, &[2]);
+    runner.tick().unwrap();
+    assert_eq!(runner.faults().len(), 1);
+}
+
+#[test]
+fn a_module_without_the_exports_of_the_abi_is_a_fault() {
+    let (chapter, solution) = single_block(1);
+    let blocks = wasm_blocks(r#"(module (func (export "evaluate") (param i32 i32 i32 i32)))"#);
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    runner.settle().unwrap();
+    let [BlockFault {
+        block: 0,
+        error: WasmError::InvalidModule(error),
+    }] = runner.faults()
+    else {
+        panic!("{:?}", runner.faults());
+    };
+    assert!(error.contains("memory"), "{error}");
+}
+
+#[test]
+fn the_wasm_file_is_loaded_from_the_module_folder() {
+    let blocks = r##"color = "#fff"
+
+[blocks.Add]
+lable = "+"
+inputs = ["cable<8>", "cable<8>"]
+outputs = ["cable<8>"]
+wasm = "add.wasm"
+"##;
+    let book = "allowed_blocks = [\"Add\"]\nchapters = []\n";
+    let path = write_module("wasm_module", blocks, book);
+    std::fs::write(path.join("add.wasm"), wat::parse_str(ADDER).unwrap()).unwrap();
+    let module = Module::from_path(path, "test".into()).unwrap();
+
+    let (chapter, mut solution) = single_block(2);
+    solution.blocks[0].shape.description.name = "Add".into();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &module.blocks);
+    runner.set_input(0, &[20]);
+    runner.set_input(1, &[22]);
+    runner.settle().unwrap();
+    assert_eq!(runner.output(0), [42]);
+}
@@ -0,0 +1,107 @@
+//! Watch conditions that pause a clocked simulation, on cables found by name
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+use digolog_module_loader::*;
+
+fn runner_with_step(step: u8) -> (Chapter, ChapterSolution, ChapterRunner) {
+    let (chapter, solution, blocks) = counter();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    runner.set_input(0, &[step]);
+    (chapter, solution, runner)
+}
+
+#[test]
+fn cables_are_found_by_name() {
+    let (chapter, solution, _) = runner_with_step(0);
+    let find = |name| Probe::find(&chapter, &solution, name);
+    assert_eq!(find("count"), Some(Probe::Output(0)));
+    assert_eq!(find("step"), Some(Probe::Input(0)));
+    assert_eq!(find("Add.out0"), Some(Probe::Block { block: 1, pin: 0 }));
+    assert_eq!(
+        find("Register_0.out0"),
+        Some(Probe::Block { block: 0, pin: 0 })
+    );
+    assert_eq!(find("Add.out1"), None);
+    assert_eq!(find("missing"), None);
+}
+
+#[test]
+fn the_run_stops_on_the_tick_where_a_condition_holds() {
+    let (chapter, solution, mut runner) = runner_with_step(1);
+    let count = Probe::find(&chapter, &solution, "count").unwrap();
+    runner.watches.push(Watch {
+        probe: count,
+        condition: Condition::Equals(0x1F),
+    });
+
+    let hits = runner.run_until(100).unwrap();
+    let hit = WatchHit {
+        watch: 0,
+        tick: 31,
+        value: 0x1F,
+    };
+    assert_eq!(hits, [hit]);
+    assert_eq!(runner.ticks(), 31);
+    assert_eq!(runner.probe(count), Some(&[0x1F][..]));
+    // The next value of the register is on the output of the adder
+    assert_eq!(
+        runner.probe(Probe::Block { block: 1, pin: 0 }),
+        Some(&[0x20][..])
+    );
+}
+
+#[test]
+fn the_run_stops_after_the_maximum_ticks() {
+    let (_, _, mut runner) = runner_with_step(1);
+    runner.watches.push(Watch {
+        probe: Probe::Output(0),
+        condition: Condition::Equals(200),
+    });
+    assert_eq!(runner.run_until(50).unwrap(), []);
+    assert_eq!(runner.ticks(), 50);
+}
+
+#[test]
+fn conditions_compare_with_the_previous_tick() {
+    // The count stays 0
+    let (_, _, mut runner) = runner_with_step(0);
+    runner.watches = vec![
+        Watch {
+            probe: Probe::Output(0),
+            condition: Condition::Becomes(0),
+        },
+        Watch {
+            probe: Probe::Output(0),
+            condition: Condition::Changes,
+        },
+    ];
+    assert_eq!(runner.run_until(10).unwrap(), []);
+
+    // The count is 2 on the first tick
+    runner.set_input(0, &[2]);
+    runner.watches = vec![
+        Watch {
+            probe: Probe::Output(0),
+            condition: Condition::Becomes(4),
+        },
+        Watch {
+            probe: Probe::Output(0),
+            condition: Condition::NotEquals(0),
+        },
+        Watch {
+            probe: Probe::Output(0),
+            condition: Condition::Changes,
+        },
+    ];
+    let hits = runner.run_until(10).unwrap();
+    let watches: Vec<usize> = hits.iter().map(|hit| hit.watch).collect();
+    assert_eq!(watches, [1, 2], "the count is 2 on tick 11");
+    assert_eq!((hits[0].tick, hits[0].value), (11, 2));
+
+    let hits = runner.run_until(10).unwrap();
+    assert_eq!(hits.len(), 3, "the count becomes 4 on tick 12");
+    assert!(hits.iter().all(|hit| hit.tick == 12 && hit.value == 4));
+}
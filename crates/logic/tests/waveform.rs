@@ -0,0 +1,65 @@
+//! Signals of a simulation recorded and exported to VCD
+
+mod common;
+
+use common::*;
+use digolog_logic::*;
+
+#[test]
+fn the_recording_is_written_as_vcd() {
+    // A cable without wires is declared with one wire
+    let chapter = chapter(
+        "Not gate",
+        vec![cable("a", 1), cable("b", 4), cable("none", 0)],
+        vec![cable("r", 1)],
+    );
+    let mut solution = chapter.new_solution();
+    solution
+        .blocks
+        .push(block("Not", vec![cable("", 1)], vec![cable("", 1)]));
+    solution.wires = vec![wire(port(0), pin(0, 0)), wire(pin(0, 0), port(0))];
+
+    let blocks = gate_blocks();
+    let mut runner = ChapterRunner::new(&chapter, &solution, &blocks);
+    let mut recorder = WaveformRecorder::new(&chapter, &solution, Probe::all(&chapter, &solution));
+    for (a, b) in [(0, 5), (1, 10), (1, 10), (0, 10)] {
+        runner.set_input(0, &[a]);
+        runner.set_input(1, &[b]);
+        runner.tick().unwrap();
+        recorder.sample(&runner);
+    }
+
+    let mut vcd = Vec::new();
+    recorder.write_vcd(&mut vcd).unwrap();
+    let expected = "\
+$version digolog $end
+$timescale 1 ns $end
+$scope module Not_gate $end
+$var wire 1 ! a $end
+$var wire 4 \" b $end
+$var wire 1 # none $end
+$var wire 1 $ r $end
+$scope module Not_0 $end
+$var wire 1 % out0 $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+b0101 \"
+0#
+1$
+1%
+#1
+1!
+b1010 \"
+0$
+0%
+#3
+0!
+1$
+1%
+#4
+";
+    assert_eq!(String::from_utf8(vcd).unwrap(), expected);
+}
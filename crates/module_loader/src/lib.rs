@@ -19,7 +19,7 @@
 //! }
 //!
 //! // Load a chapter solution
-//! let solution = chapter.load_solution();
+//! let solution = chapter.load_solution(save_file_path);
 //! ```
 //!
 #![feature(portable_simd)]
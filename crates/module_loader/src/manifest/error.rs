@@ -0,0 +1,83 @@
+//! Errors found while loading a module
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Why a module can not be loaded
+#[derive(Debug)]
+pub enum ModuleError {
+    /// A file or a folder of the module can not be read
+    Io { path: PathBuf, error: io::Error },
+    /// A manifest is not valid TOML, or it does not have the expected fields
+    Manifest {
+        path: PathBuf,
+        error: toml::de::Error,
+    },
+    /// A manifest folder has something that is not a `.toml` file
+    UnexpectedItem(PathBuf),
+    /// A block refers to a block of another module, which is not supported yet
+    BlockRef { block: String, reference: String },
+    /// A block pin does not follow the format `type name`
+    InvalidPin { block: String, pin: String },
+    /// A chapter port does not follow the format `name` or `name[wires]`
+    InvalidPort { chapter: String, port: String },
+    /// A book or a chapter allows a block or a block group that does not exist.
+    /// `allowed_in` is the title of the book or the chapter.
+    UnknownBlock { allowed_in: String, block: String },
+    /// The template values of an allowed block, like `In=1` in `Add<In=1>`,
+    /// are not `name=number` for templates of the block
+    InvalidTemplateValues { allowed_in: String, block: String },
+    /// The sequence test of a chapter uses a port that the chapter does not have
+    UnknownTestPort { chapter: String, port: String },
+    /// The sequence test of a chapter has a value that is not a number, `"x"` or `"-"`
+    InvalidTestValue { chapter: String, value: String },
+    /// The reference solution of a chapter does not exist or it is invalid
+    InvalidReferenceSolution { chapter: String, path: PathBuf },
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "can not read {}: {error}", path.display()),
+            Self::Manifest { path, error } => {
+                write!(f, "invalid manifest {}: {error}", path.display())
+            }
+            Self::UnexpectedItem(path) => {
+                write!(f, "{} is not a .toml manifest", path.display())
+            }
+            Self::BlockRef { block, reference } => write!(
+                f,
+                "the block {block:?} refers to {reference:?}, blocks of other modules are not supported"
+            ),
+            Self::InvalidPin { block, pin } => {
+                write!(f, "the block {block:?} has the invalid pin {pin:?}")
+            }
+            Self::InvalidPort { chapter, port } => {
+                write!(f, "chapter {chapter:?} has the invalid port {port:?}")
+            }
+            Self::UnknownBlock { allowed_in, block } => {
+                write!(f, "{allowed_in:?} allows the unknown block or block group {block:?}")
+            }
+            Self::InvalidTemplateValues { allowed_in, block } => write!(
+                f,
+                "{allowed_in:?} allows {block:?}, whose template values are not templates of the block"
+            ),
+            Self::UnknownTestPort { chapter, port } => write!(
+                f,
+                "the sequence test of chapter {chapter:?} uses the unknown port {port:?}"
+            ),
+            Self::InvalidTestValue { chapter, value } => write!(
+                f,
+                "the sequence test of chapter {chapter:?} has the value {value:?}, expected a number, \"x\" or \"-\""
+            ),
+            Self::InvalidReferenceSolution { chapter, path } => write!(
+                f,
+                "the reference solution of chapter {chapter:?} can not be read from {}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {}
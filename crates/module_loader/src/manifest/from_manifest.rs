@@ -1,45 +1,230 @@
 //! functions that construct modules from manifests
 
-use std::sync::Arc;
+use std::{collections::HashMap, path::Path, sync::Arc};
 
 use crate::*;
 
-use super::BlockManifestRef;
+use super::{BlockManifestRef, TestValueManifest};
 
 impl BlockDescSubset {
-    fn from_manifest(blocks: &ModuleBlocks, subset: &str) -> Self {
-        if let Some(block_desc) = blocks.get(subset).cloned() {
-            return Self { block_desc };
+    /// The block with the given name, or all the blocks of the group with the given name.
+    /// A block can have template values, like `Add<In=1>`.
+    fn from_manifest(
+        blocks: &ModuleBlocks,
+        allowed_in: &str,
+        subset: &str,
+    ) -> Result<Vec<Self>, ModuleError> {
+        let invalid_values = || ModuleError::InvalidTemplateValues {
+            allowed_in: allowed_in.into(),
+            block: subset.into(),
+        };
+        let (name, values) = match subset.trim().strip_suffix('>') {
+            Some(subset) => subset.split_once('<').ok_or_else(invalid_values)?,
+            None if subset.contains('<') => return Err(invalid_values()),
+            None => (subset, ""),
+        };
+        let name = name.trim();
+
+        if let Some(block_desc) = blocks.get(name).cloned() {
+            let mut template_values = TemplateValues::new();
+            for value in values.split(',').filter(|value| !value.trim().is_empty()) {
+                let (template, value) = value.split_once('=').ok_or_else(invalid_values)?;
+                let template = template.trim();
+                let value = value.trim().parse().map_err(|_| invalid_values())?;
+                if !block_desc.has_template(template) {
+                    return Err(invalid_values());
+                }
+                template_values.insert(template.into(), value);
+            }
+            return Ok(vec![Self {
+                block_desc,
+                values: template_values,
+            }]);
+        }
+        if !values.trim().is_empty() {
+            return Err(invalid_values());
+        }
+        let group: Vec<Self> = (blocks.values())
+            .filter(|block_desc| block_desc.group == name)
+            .map(|block_desc| Self {
+                block_desc: block_desc.clone(),
+                values: TemplateValues::new(),
+            })
+            .collect();
+        if group.is_empty() {
+            return Err(ModuleError::UnknownBlock {
+                allowed_in: allowed_in.into(),
+                block: subset.into(),
+            });
         }
-        todo!()
+        Ok(group)
     }
 }
 
+/// The blocks allowed by the `allowed_blocks` of a book or a chapter
+fn allowed_blocks_from_manifest(
+    blocks: &ModuleBlocks,
+    allowed_in: &str,
+    allowed_blocks: &[String],
+) -> Result<Vec<BlockDescSubset>, ModuleError> {
+    let mut subsets = Vec::new();
+    for subset in allowed_blocks {
+        subsets.extend(BlockDescSubset::from_manifest(blocks, allowed_in, subset)?);
+    }
+    Ok(subsets)
+}
+
 impl Chapter {
-    fn from_manifest(blocks: &ModuleBlocks, book_id: BookId, manifest: ChapterManifest) -> Self {
-        (Chapter {
-            id: ChapterId {
-                book_id,
-                title: manifest.title,
+    fn from_manifest(
+        blocks: &ModuleBlocks,
+        book_id: BookId,
+        manifest: ChapterManifest,
+    ) -> Result<Self, ModuleError> {
+        let title = manifest.title;
+        let ports = |ports: &[String]| -> Result<Vec<BlockCable>, ModuleError> {
+            (ports.iter())
+                .map(|port| port_from_manifest(&title, port))
+                .collect()
+        };
+        let inputs = ports(&manifest.inputs)?;
+        let outputs = ports(&manifest.outputs)?;
+        let test = match manifest.test {
+            Some(test) => Some(SequenceTest::from_manifest(
+                &title, &inputs, &outputs, test,
+            )?),
+            None => None,
+        };
+        let reference_solution = match manifest.reference_solution {
+            Some(path) => match ChapterSolution::load(&path, ChapterCompletionStatus::Completed) {
+                Some(solution) => Some(solution),
+                None => {
+                    return Err(ModuleError::InvalidReferenceSolution {
+                        chapter: title,
+                        path,
+                    })
+                }
             },
-            allowed_blocks: manifest
-                .allowed_blocks
-                .iter()
-                .map(|desc_subset| BlockDescSubset::from_manifest(blocks, desc_subset))
-                .collect(),
+            None => None,
+        };
+
+        Ok(Chapter {
+            allowed_blocks: allowed_blocks_from_manifest(blocks, &title, &manifest.allowed_blocks)?,
+            id: ChapterId { book_id, title },
+            truth_table: manifest.truth_table.map(TruthTable::from_manifest),
+            test,
+            inputs,
+            outputs,
+            reference_solution,
             completion_status: ChapterCompletionStatus::NotStarted,
+            best_metrics: None,
+        })
+    }
+}
+
+impl TruthTable {
+    fn from_manifest(manifest: TruthTableManifest) -> Self {
+        TruthTable {
+            format: manifest.format,
+            rows: match manifest.function {
+                Some(function) => TruthTableRows::Function(function),
+                None => TruthTableRows::Table(manifest.table),
+            },
+            max_exhaustive_wires: manifest.max_exhaustive_wires,
+            random_rows: manifest.random_rows,
+        }
+    }
+}
+
+impl SequenceTest {
+    fn from_manifest(
+        chapter: &str,
+        inputs: &[BlockCable],
+        outputs: &[BlockCable],
+        manifest: SequenceTestManifest,
+    ) -> Result<Self, ModuleError> {
+        let unknown_port = |port: &str| ModuleError::UnknownTestPort {
+            chapter: chapter.into(),
+            port: port.into(),
+        };
+        let clock = match manifest.clock {
+            Some(clock) => Some(
+                (inputs.iter().position(|port| port.lable == clock))
+                    .ok_or_else(|| unknown_port(&clock))?,
+            ),
+            None => None,
+        };
+
+        let mut steps = Vec::new();
+        for step in &manifest.steps {
+            if let Some(name) = (step.keys())
+                .find(|name| !(inputs.iter().chain(outputs)).any(|port| port.lable == **name))
+            {
+                return Err(unknown_port(name));
+            }
+            let values = |ports: &[BlockCable]| -> Result<Vec<Option<u64>>, ModuleError> {
+                (ports.iter())
+                    .map(|port| match step.get(&port.lable) {
+                        None => Ok(None),
+                        Some(TestValueManifest::Value(value)) => Ok(Some(*value)),
+                        Some(TestValueManifest::DontCare(symbol))
+                            if symbol == "x" || symbol == "-" =>
+                        {
+                            Ok(None)
+                        }
+                        Some(TestValueManifest::DontCare(symbol)) => {
+                            Err(ModuleError::InvalidTestValue {
+                                chapter: chapter.into(),
+                                value: symbol.clone(),
+                            })
+                        }
+                    })
+                    .collect()
+            };
+            steps.push(TestStep {
+                inputs: values(inputs)?,
+                expected: values(outputs)?,
+            });
+        }
+
+        Ok(SequenceTest { clock, steps })
+    }
+}
+
+/// Parses a chapter port with the format `name` or `name[wires]`
+fn port_from_manifest(chapter: &str, port: &str) -> Result<BlockCable, ModuleError> {
+    if let Some((lable, wires)) = port.strip_suffix(']').and_then(|p| p.split_once('[')) {
+        Ok(BlockCable {
+            lable: lable.trim().into(),
+            wires: wires.trim().parse().map_err(|_| ModuleError::InvalidPort {
+                chapter: chapter.into(),
+                port: port.into(),
+            })?,
+        })
+    } else {
+        Ok(BlockCable {
+            lable: port.trim().into(),
+            wires: 1,
         })
     }
 }
 
 impl Book {
-    fn from_manifest(blocks: &ModuleBlocks, id: BookId, manifest: BookManifest) -> Self {
-        (Book {
+    fn from_manifest(
+        blocks: &ModuleBlocks,
+        id: BookId,
+        manifest: BookManifest,
+    ) -> Result<Self, ModuleError> {
+        Ok(Book {
+            allowed_blocks: allowed_blocks_from_manifest(
+                blocks,
+                &id.title,
+                &manifest.allowed_blocks,
+            )?,
             chapters: manifest
                 .chapters
                 .into_iter()
                 .map(|chapter_man| Chapter::from_manifest(blocks, id.clone(), chapter_man))
-                .collect(),
+                .collect::<Result<_, _>>()?,
             id,
         })
     }
@@ -51,70 +236,154 @@ impl BlockDesc {
         group_color: &str,
         block_name: String,
         block: BlockManifest,
-    ) -> Self {
-        (BlockDesc {
+    ) -> Result<Self, ModuleError> {
+        let pins = |pins: &[String]| -> Result<Vec<BlockPinDesc>, ModuleError> {
+            (pins.iter())
+                .map(|pin| pin_from_manifest(&block_name, pin, &block.sides))
+                .collect()
+        };
+        Ok(BlockDesc {
+            inputs: pins(&block.inputs)?,
+            outputs: pins(&block.outputs)?,
             id: BlockDescId { name: block_name },
             color: group_color.into(),
             group: group_name,
             lable: block.lable,
-            inputs: vec![],  // block.inputs,
-            outputs: vec![], // block.outputs,
-            logic: None,
+            logic: (block.gate.map(BlockLogic::Gate))
+                .or_else(|| block.arithmetic.map(BlockLogic::Arithmetic))
+                .or_else(|| (block.wasm_code).map(|code| BlockLogic::Wasm(WasmLogic { code }))),
+            cost: block.cost.unwrap_or(1),
+            constants: (block.constant.iter())
+                .map(|(name, expr)| (name.clone(), template_expr_from_manifest(expr)))
+                .collect(),
         })
     }
 }
 
+/// Parses a block pin with the format `type name`, like `cable<C> sum` or `bundle`
+fn pin_from_manifest(
+    block: &str,
+    pin: &str,
+    sides: &HashMap<String, Side>,
+) -> Result<BlockPinDesc, ModuleError> {
+    let pin = pin.trim();
+    let invalid_pin = || ModuleError::InvalidPin {
+        block: block.into(),
+        pin: pin.into(),
+    };
+    let (pin_type, lable) = match pin.split_once('>') {
+        Some((pin_type, lable)) => (format!("{pin_type}>"), lable),
+        None => match pin.split_once(char::is_whitespace) {
+            Some((pin_type, lable)) => (pin_type.into(), lable),
+            None => (pin.into(), ""),
+        },
+    };
+    let (kind, params) = match pin_type.strip_suffix('>') {
+        Some(pin_type) => {
+            let (kind, params) = pin_type.split_once('<').ok_or_else(invalid_pin)?;
+            (kind.trim(), params.split(',').map(str::trim).collect())
+        }
+        None => (pin_type.as_str(), vec![]),
+    };
+
+    let number = |param: &str| match param.parse() {
+        Ok(value) => TemplateNumber::Num(value),
+        Err(_) => TemplateNumber::Const(param.into()),
+    };
+    let pin_type = match (kind, params.as_slice()) {
+        ("wire", []) => PinTypeTemplate::Cable {
+            wires: TemplateNumber::Num(1),
+        },
+        ("cable", []) => PinTypeTemplate::Cable { wires: number("_") },
+        ("cable", [wires]) => PinTypeTemplate::Cable {
+            wires: number(wires),
+        },
+        ("bundle", []) => PinTypeTemplate::Bundle {
+            cables: number("_N"),
+            wires_per_cable: number("_"),
+        },
+        ("bundle", [cables, wires]) => PinTypeTemplate::Bundle {
+            cables: number(cables),
+            wires_per_cable: number(wires),
+        },
+        _ => return Err(invalid_pin()),
+    };
+    let lable = lable.trim();
+    Ok(BlockPinDesc {
+        pin_type,
+        side: sides.get(lable).copied(),
+        lable: lable.into(),
+    })
+}
+
+/// Parses a template constant, a number, a template name or `name:bits()`
+fn template_expr_from_manifest(expr: &str) -> TemplateExpr {
+    let expr = expr.trim();
+    match expr.strip_suffix(":bits()") {
+        Some(name) => TemplateExpr::Bits(name.trim().into()),
+        None => match expr.parse() {
+            Ok(value) => TemplateExpr::Number(TemplateNumber::Num(value)),
+            Err(_) => TemplateExpr::Number(TemplateNumber::Const(expr.into())),
+        },
+    }
+}
+
 impl BlockManifestRef {
-    /// Returns the block manifest loading it if necesary
-    fn manifest(self) -> BlockManifest {
+    /// Returns the block manifest loading it if necesary.
+    /// Blocks of other modules can not be loaded yet.
+    fn manifest(self, block: &str) -> Result<BlockManifest, ModuleError> {
         match self {
-            Self::Ref(id) => todo!(),
-            Self::Defined(manifest) => manifest,
+            Self::Ref(reference) => Err(ModuleError::BlockRef {
+                block: block.into(),
+                reference,
+            }),
+            Self::Defined(manifest) => Ok(manifest),
         }
     }
 }
 
 impl Module {
-    fn from_manifest(manifest: ModuleManifestBundle, namespace: String) -> Self {
+    /// Loads the module in the folder `mod_path`
+    pub fn from_path(mod_path: impl AsRef<Path>, namespace: String) -> Result<Self, ModuleError> {
+        Self::from_manifest(ModuleManifestBundle::from_mod_path(mod_path)?, namespace)
+    }
+
+    fn from_manifest(
+        manifest: ModuleManifestBundle,
+        namespace: String,
+    ) -> Result<Self, ModuleError> {
         let module_id = ModuleId {
             name: manifest.module.name,
             namespace,
         };
-        let blocks = manifest
-            .blocks
-            .into_iter()
-            .flat_map(|(group_name, group)| {
-                group.blocks.into_iter().map(move |(block_name, block)| {
-                    let manifest = block.manifest();
-                    let block_desc = BlockDesc::from_manifest(
-                        group_name.clone(),
-                        &group.color,
-                        block_name.clone(),
-                        manifest,
-                    );
-                    (block_name, Arc::new(block_desc))
-                })
-            })
-            .collect();
-        let books = manifest
-            .books
-            .into_iter()
-            .map(|(title, book_man)| {
-                let book_id = BookId {
-                    module_id: module_id.clone(),
-                    title: title.clone(),
-                };
-                let book = Book::from_manifest(&blocks, book_id, book_man);
-                (title, book)
-            })
-            .collect();
+        let mut blocks = ModuleBlocks::new();
+        for (group_name, group) in manifest.blocks {
+            for (block_name, block) in group.blocks {
+                let manifest = block.manifest(&block_name)?;
+                let block_desc = BlockDesc::from_manifest(
+                    group_name.clone(),
+                    &group.color,
+                    block_name.clone(),
+                    manifest,
+                )?;
+                blocks.insert(block_name, Arc::new(block_desc));
+            }
+        }
+        let mut books = HashMap::new();
+        for (title, book_man) in manifest.books {
+            let book_id = BookId {
+                module_id: module_id.clone(),
+                title: title.clone(),
+            };
+            books.insert(title, Book::from_manifest(&blocks, book_id, book_man)?);
+        }
 
-        Module {
+        Ok(Module {
             id: module_id,
             description: manifest.module.description,
             author: manifest.module.author,
             books,
             blocks,
-        }
+        })
     }
 }
@@ -4,59 +4,101 @@ use super::*;
 use std::path::Path;
 
 impl ModuleManifestBundle {
-    pub fn from_mod_path(mod_path: impl AsRef<Path>) -> Self {
-        Self {
-            module: ModuleManifest::from_mod_path(&mod_path),
-            blocks: BlockGroupManifest::from_mod_path(&mod_path).collect(),
-            books: BookManifest::from_mod_path(&mod_path).collect(),
-            scenes: ScenesManifest::from_mod_path(&mod_path),
-        }
+    pub fn from_mod_path(mod_path: impl AsRef<Path>) -> Result<Self, ModuleError> {
+        Ok(Self {
+            module: ModuleManifest::from_mod_path(&mod_path)?,
+            blocks: BlockGroupManifest::from_mod_path(&mod_path)?,
+            books: BookManifest::from_mod_path(&mod_path)?,
+            scenes: ScenesManifest::from_mod_path(&mod_path)?,
+        })
     }
 }
 
 impl ModuleManifest {
-    fn from_mod_path(mod_path: impl AsRef<Path>) -> Self {
-        let manifest_path = mod_path.as_ref().join("module.toml");
-        let manifest = read_to_string(manifest_path).unwrap();
-        toml::from_str(&manifest).unwrap()
+    fn from_mod_path(mod_path: impl AsRef<Path>) -> Result<Self, ModuleError> {
+        read_manifest(mod_path.as_ref().join("module.toml"))
     }
 }
 
 impl ScenesManifest {
-    fn from_mod_path(mod_path: impl AsRef<Path>) -> Self {
+    /// The scenes are optional, a module without `scenes.toml` has none
+    fn from_mod_path(mod_path: impl AsRef<Path>) -> Result<Self, ModuleError> {
         let manifest_path = mod_path.as_ref().join("scenes.toml");
-        let manifest = read_to_string(manifest_path).unwrap();
-        toml::from_str(&manifest).unwrap()
+        if !manifest_path.exists() {
+            return Ok(Self::default());
+        }
+        read_manifest(manifest_path)
     }
 }
 
 impl BookManifest {
-    fn from_mod_path(mod_path: impl AsRef<Path>) -> impl Iterator<Item = (String, Self)> {
-        iter_manifests_from_folder(mod_path.as_ref().join("books"))
+    fn from_mod_path(mod_path: impl AsRef<Path>) -> Result<HashMap<String, Self>, ModuleError> {
+        let mod_path = mod_path.as_ref();
+        let mut books: HashMap<String, Self> = read_manifest_folder(mod_path.join("books"))?;
+        for book in books.values_mut() {
+            for chapter in &mut book.chapters {
+                if let Some(solution) = &mut chapter.reference_solution {
+                    *solution = mod_path.join(&*solution);
+                }
+            }
+        }
+        Ok(books)
     }
 }
 
 impl BlockGroupManifest {
-    fn from_mod_path(mod_path: impl AsRef<Path>) -> impl Iterator<Item = (String, Self)> {
-        iter_manifests_from_folder(mod_path.as_ref().join("blocks"))
+    fn from_mod_path(mod_path: impl AsRef<Path>) -> Result<HashMap<String, Self>, ModuleError> {
+        let mod_path = mod_path.as_ref();
+        let mut groups: HashMap<String, Self> = read_manifest_folder(mod_path.join("blocks"))?;
+        for group in groups.values_mut() {
+            for block in group.blocks.values_mut() {
+                if let BlockManifestRef::Defined(block) = block {
+                    block.load_wasm(mod_path)?;
+                }
+            }
+        }
+        Ok(groups)
     }
 }
 
-fn iter_manifests_from_folder<M: DeserializeOwned>(
-    folder_path: impl AsRef<Path>,
-) -> impl Iterator<Item = (String, M)> {
-    let iter = read_dir(folder_path).unwrap().map(|entry| {
-        let path = entry.unwrap().path();
+impl BlockManifest {
+    /// Reads the WebAssembly module of the block, if it has one
+    fn load_wasm(&mut self, mod_path: &Path) -> Result<(), ModuleError> {
+        if let Some(wasm) = &self.wasm {
+            let path = mod_path.join(wasm);
+            let code = read(&path).map_err(|error| ModuleError::Io { path, error })?;
+            self.wasm_code = Some(code.into());
+        }
+        Ok(())
+    }
+}
+
+fn read_manifest<M: DeserializeOwned>(path: PathBuf) -> Result<M, ModuleError> {
+    match read_to_string(&path) {
+        Ok(src) => toml::from_str(&src).map_err(|error| ModuleError::Manifest { path, error }),
+        Err(error) => Err(ModuleError::Io { path, error }),
+    }
+}
 
-        if path.is_file() && path.extension() == Some(OsStr::new(".toml")) {
-            if let Some(Some(name)) = path.file_stem().map(|s| s.to_str()) {
-                let src = read_to_string(&path).unwrap();
-                let manifest = toml::from_str(&src).unwrap();
-                return (name.into(), manifest);
+/// Reads the `.toml` manifests of a folder, by the name of their file
+fn read_manifest_folder<M: DeserializeOwned>(
+    folder_path: PathBuf,
+) -> Result<HashMap<String, M>, ModuleError> {
+    let io_error = |path: &Path| {
+        let path = path.to_owned();
+        move |error| ModuleError::Io { path, error }
+    };
+    let mut manifests = HashMap::new();
+    for entry in read_dir(&folder_path).map_err(io_error(&folder_path))? {
+        let path = entry.map_err(io_error(&folder_path))?.path();
+
+        let name = path.file_stem().and_then(|s| s.to_str());
+        match name {
+            Some(name) if path.is_file() && path.extension() == Some(OsStr::new("toml")) => {
+                manifests.insert(name.into(), read_manifest(path.clone())?);
             }
+            _ => return Err(ModuleError::UnexpectedItem(path)),
         }
-
-        panic!("Unexpected item {}", path.display());
-    });
-    (iter)
+    }
+    Ok(manifests)
 }
@@ -1,3 +1,4 @@
+mod error;
 mod from_manifest;
 mod from_path;
 
@@ -6,9 +7,12 @@ use serde::{de::DeserializeOwned, *};
 use std::{
     collections::HashMap,
     ffi::OsStr,
-    fs::{read_dir, read_to_string},
+    fs::{read, read_dir, read_to_string},
+    path::PathBuf,
+    sync::Arc,
 };
 
+pub use error::*;
 pub use from_manifest::*;
 pub use from_path::*;
 
@@ -38,8 +42,10 @@ pub struct ModuleManifest {
 #[derive(Debug, Clone, Deserialize)]
 pub struct BookManifest {
     /// Books from the same module that need to be completed to start this book.
+    #[serde(default)]
     pub requirements: Vec<String>,
     /// Blocks or Block Groups that are allowed to use in any chapter of the book.
+    #[serde(default)]
     pub allowed_blocks: Vec<String>,
     pub chapters: Vec<ChapterManifest>,
 }
@@ -48,7 +54,56 @@ pub struct BookManifest {
 pub struct ChapterManifest {
     pub title: String,
     /// Blocks or Block Groups that are allowed to use only in this chapter.
+    #[serde(default)]
     pub allowed_blocks: Vec<String>,
+    /// Input ports of the chapter, with the format `name` or `name[wires]`
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// Output ports of the chapter, with the same format as `inputs`
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    pub truth_table: Option<TruthTableManifest>,
+    pub test: Option<SequenceTestManifest>,
+    /// Path of a solution of the chapter in the save file format, or in the text format
+    /// if it ends with `.txt`, relative to the module folder.
+    /// It proves that the chapter can be solved with the allowed blocks.
+    pub reference_solution: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TruthTableManifest {
+    /// How to display a row. `$port` is replaced by the value of the port.
+    #[serde(default)]
+    pub format: String,
+    /// Name of the function that computes the outputs from the inputs.
+    pub function: Option<String>,
+    /// Values of the ports on each row. Only used if there is no `function`.
+    #[serde(default)]
+    pub table: Vec<HashMap<String, u64>>,
+    /// If the inputs have more wires, the function is checked with edge cases
+    /// and random rows instead of all the combinations of the inputs.
+    pub max_exhaustive_wires: Option<u32>,
+    /// Number of random rows to check when the inputs have too many wires
+    pub random_rows: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SequenceTestManifest {
+    /// Name of the clock input. If there is a clock, each step is a rising edge.
+    /// Otherwise each step is a tick.
+    pub clock: Option<String>,
+    /// Values of the ports on each step.
+    /// The missing inputs keep their value, and the missing outputs are not checked.
+    pub steps: Vec<HashMap<String, TestValueManifest>>,
+}
+
+/// A value of a port on a test step
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TestValueManifest {
+    Value(u64),
+    /// `"x"` or `"-"`: the output is not checked, or the input keeps its value
+    DontCare(String),
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -68,11 +123,36 @@ pub enum BlockManifestRef {
 #[derive(Debug, Clone, Deserialize)]
 pub struct BlockManifest {
     lable: String,
+    /// Pins with the format `type name`, where the type is `wire`, `cable`, `cable<C>`,
+    /// `bundle` or `bundle<B, C>`. `B` and `C` are numbers or template names.
+    ///
+    /// A `cable` without width is a `cable<_>`, so all of them have the same width in a block.
+    /// A `bundle` without size is a `bundle<_N, _>`.
     inputs: Vec<String>,
     outputs: Vec<String>,
+    /// Template values computed from the others, as a number, a name, or `name:bits()`
+    #[serde(default)]
+    constant: HashMap<String, String>,
+    /// Side of the pins with the given lables: `left`, `top`, `right` or `bottom`.
+    /// The other inputs are on the left, and the other outputs on the right.
+    #[serde(default)]
+    sides: HashMap<String, Side>,
+    /// Logic gate of the block: `not`, `and`, `or`, `xor` or `equal`.
+    /// The builtin modules use it instead of a `wasm` file.
+    gate: Option<Gate>,
+    /// Arithmetic operation of the block: `add`, `sub`, `mul`, `div` or `mod`.
+    /// The builtin modules use it instead of a `wasm` file.
+    arithmetic: Option<Arithmetic>,
+    /// Path of a WebAssembly module with the logic of the block, relative to the module folder
+    wasm: Option<String>,
+    /// Cost of the block in the solution metrics, 1 by default
+    cost: Option<u32>,
+    /// Contents of the `wasm` file, loaded with the manifest
+    #[serde(skip)]
+    wasm_code: Option<Arc<[u8]>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct ScenesManifest {
     scenes: HashMap<String, SceneManifest>,
 }
@@ -1,4 +1,5 @@
 use crate::*;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Represents all the shapes that a block can have
@@ -10,19 +11,30 @@ pub struct BlockDesc {
     pub inputs: Vec<BlockPinDesc>,
     pub outputs: Vec<BlockPinDesc>,
     pub logic: Option<BlockLogic>,
+    /// Cost of the block in the solution metrics.
+    /// A composite block costs as much as the blocks it is made of, so it is not used.
+    pub cost: u32,
+    /// Template values computed from the others, like `C = B:bits()`
+    pub constants: HashMap<String, TemplateExpr>,
 }
 
 /// A subset of all the block shapes that a BlockDesc represents
 pub struct BlockDescSubset {
     pub block_desc: Arc<BlockDesc>,
-    // TODO: Represent the subset
+    /// Template values that the blocks of the subset must have, like `In=1` in `Add<In=1>`.
+    /// The other templates can have any value.
+    pub values: TemplateValues,
 }
 
 pub struct BlockPinDesc {
     pub pin_type: PinTypeTemplate,
     pub lable: String,
+    /// Inputs are on the left and outputs on the right by default
+    pub side: Option<Side>,
 }
+/// The type of a pin, with template names for the values chosen by the player
 pub enum PinTypeTemplate {
+    /// Many cables of the same width, like the inputs of an And
     Bundle {
         cables: TemplateNumber,
         wires_per_cable: TemplateNumber,
@@ -33,6 +45,64 @@ pub enum PinTypeTemplate {
 }
 
 pub enum TemplateNumber {
+    /// A template name, with a value for each placed block
     Const(String),
     Num(i64),
 }
+
+/// How a constant is computed from the other template values
+pub enum TemplateExpr {
+    Number(TemplateNumber),
+    /// Wires needed to count up to the value, like `B:bits()`
+    Bits(String),
+}
+
+impl BlockDesc {
+    /// Creates a composite block from the solution of a chapter.
+    /// The pins of the block are the ports of the chapter.
+    pub fn from_solution(
+        id: BlockDescId,
+        group: String,
+        color: Color,
+        chapter: &Chapter,
+        solution: &ChapterSolution,
+    ) -> Self {
+        let composite = CompositeLogic {
+            inputs: chapter.inputs.clone(),
+            outputs: chapter.outputs.clone(),
+            blocks: solution.blocks.clone(),
+            wires: solution.wires.clone(),
+        };
+        Self::from_composite(id, chapter.id.title.clone(), group, color, composite)
+    }
+
+    /// Creates a composite block from its netlist.
+    /// The pins of the block are the ports of the netlist.
+    pub fn from_composite(
+        id: BlockDescId,
+        lable: String,
+        group: String,
+        color: Color,
+        composite: CompositeLogic,
+    ) -> Self {
+        let pin = |port: &BlockCable| BlockPinDesc {
+            pin_type: PinTypeTemplate::Cable {
+                wires: TemplateNumber::Num(port.wires as i64),
+            },
+            lable: port.lable.clone(),
+            side: None,
+        };
+
+        BlockDesc {
+            id,
+            lable,
+            group,
+            color,
+            inputs: composite.inputs.iter().map(pin).collect(),
+            outputs: composite.outputs.iter().map(pin).collect(),
+            logic: Some(BlockLogic::Composite(composite)),
+            cost: 0,
+            constants: HashMap::new(),
+        }
+    }
+}
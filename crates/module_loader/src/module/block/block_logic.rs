@@ -1,14 +1,122 @@
 use crate::*;
 use derive_more::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 pub enum BlockLogic {
-    /// A rust function. (example: Logic Gates)
+    /// A rust function. (example: Comparators)
     Builtin(fn(BlockInput, BlockOutputMut)),
+    /// A logic gate applied to each wire of the input cables.
+    ///
+    /// Unlike `Builtin`, the runner knows what it does,
+    /// so it can evaluate it for many inputs at once.
+    Gate(Gate),
+    /// An arithmetic operation on the input cables, read as unsigned numbers.
+    ///
+    /// Like `Gate`, the runner knows what it does, so it can evaluate it
+    /// for cables of any width.
+    Arithmetic(Arithmetic),
+    /// A block with memory. (example: Register)
+    ///
+    /// The outputs only depend on the state,
+    /// and the state is only updated from the inputs when the clock ticks.
+    Sequential {
+        /// Bytes of state needed by a shape of the block
+        state_bytes: fn(&BlockShape) -> usize,
+        output: fn(BlockState, BlockOutputMut),
+        update: fn(BlockInput, BlockStateMut),
+    },
+    /// A block made of other blocks, from the solution of a chapter. (example: Full Adder)
+    Composite(CompositeLogic),
+    /// A WebAssembly module shipped with a third party module.
+    Wasm(WasmLogic),
 }
 
-#[derive(Deref, DerefMut)]
+/// The netlist of a composite block.
+///
+/// Its pins are the ports of the chapter: the wires connected to
+/// `PinRef::Chapter` are connected to the pins of the block.
+/// The blocks are searched by name in the same [`ModuleBlocks`] as the block itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeLogic {
+    pub inputs: Vec<BlockCable>,
+    pub outputs: Vec<BlockCable>,
+    pub blocks: Vec<Block>,
+    pub wires: Vec<Wire>,
+}
+
+/// The logic of a block written in WebAssembly.
+///
+/// The module has to export:
+/// - `memory`: the linear memory where the host writes the cables.
+/// - `buffer(size: i32) -> i32`: the address of `size` bytes of memory that the host can use.
+///   It is only called once, after the instantiation.
+///
+/// A block without memory exports:
+/// - `evaluate(inputs: i32, input_len: i32, outputs: i32, output_len: i32)`:
+///   writes the outputs from the inputs.
+///
+/// A block with memory exports:
+/// - `state_bytes(input_len: i32, output_len: i32) -> i32`: the bytes of state of a shape.
+/// - `output(state: i32, state_len: i32, outputs: i32, output_len: i32)`:
+///   writes the outputs from the state.
+/// - `update(inputs: i32, input_len: i32, state: i32, state_len: i32)`:
+///   updates the state from the inputs when the clock ticks.
+///
+/// The arguments are addresses and lengths in bytes of cables laid out as [`BlockInput`].
+/// The state is kept by the host, so the module must not keep anything else between calls.
+#[derive(Debug, Clone)]
+pub struct WasmLogic {
+    /// Contents of the `.wasm` file
+    pub code: Arc<[u8]>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gate {
+    /// Negates each wire of the first cable
+    Not,
+    /// Each output wire is the `and` of the same wire of all the input cables
+    And,
+    /// Each output wire is the `or` of the same wire of all the input cables
+    Or,
+    /// Each output wire is the `xor` of the same wire of all the input cables
+    XOr,
+    /// The output wire is 1 if all the input cables are equal
+    Equal,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arithmetic {
+    /// The output is the sum of all the input cables
+    Add,
+    /// The first output is the first input minus the second,
+    /// and the second output is 1 if the second input is greater than the first
+    Sub,
+    /// The output is the product of all the input cables
+    Mul,
+    /// The output is the first input divided by the second, rounded down.
+    /// All its wires are 1 if the second input is 0.
+    Div,
+    /// The output is the remainder of the first input divided by the second.
+    /// It is the first input if the second is 0.
+    Mod,
+}
+
+/// The output cables of a block, one after the other.
+///
+/// Each cable uses `ceil(wires / 8)` bytes (at least one), with the first wire
+/// in the lowest bit of the first byte.
+#[derive(Deref, DerefMut, From)]
 pub struct BlockOutputMut<'a>(&'a mut [u8]);
 
-#[derive(Deref)]
+/// The input cables of a block, with the same layout as [`BlockOutputMut`].
+#[derive(Deref, From)]
 pub struct BlockInput<'a>(&'a [u8]);
 
+#[derive(Deref, DerefMut, From)]
+pub struct BlockStateMut<'a>(&'a mut [u8]);
+
+#[derive(Deref, From)]
+pub struct BlockState<'a>(&'a [u8]);
@@ -1,27 +1,113 @@
 use crate::*;
+use serde::{Deserialize, Serialize};
+use std::hash::{DefaultHasher, Hash, Hasher};
 
 /// Instance of a BlockDesc
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct BlockShape {
     pub description: BlockDescId,
     pub lable: String,
     pub inputs: Vec<BlockCable>,
     pub outputs: Vec<BlockCable>,
+    #[serde(default, skip_serializing_if = "PinLayout::is_default")]
+    pub layout: PinLayout,
 }
 
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 pub struct BlockShapeId(u64);
 
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BlockCable {
     pub lable: String,
     pub wires: u8,
 }
 
+/// Creates the shape of a block from its description and the values of its templates.
+///
+/// Unlike [`BlockDesc::shape`], every cable gets a known width.
+pub struct BlockShapeBuilder<'a> {
+    pub desc: &'a BlockDesc,
+    /// The chosen values, the constants of the description are computed from them
+    pub values: TemplateValues,
+}
+
+/// Why a shape can not be created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockShapeError {
+    /// A template used by a pin has no value
+    MissingValue(String),
+    /// A template has a value that is not a number of cables or wires
+    InvalidValue {
+        name: String,
+        value: i64,
+    },
+    Conflict(TemplateConflict),
+}
+
+/// Width in grid units of a character of the lables
+const CHAR_WIDTH: f32 = 0.35;
+/// Minimum width of a block in grid units
+const MIN_WIDTH: f32 = 3.;
+
+impl BlockShapeBuilder<'_> {
+    pub fn build(self) -> Result<BlockShape, BlockShapeError> {
+        let Self { desc, mut values } = self;
+        desc.resolve(&mut values)
+            .map_err(BlockShapeError::Conflict)?;
+
+        for pin in desc.inputs.iter().chain(&desc.outputs) {
+            let (cables, wires) = match &pin.pin_type {
+                PinTypeTemplate::Cable { wires } => (None, wires),
+                PinTypeTemplate::Bundle {
+                    cables,
+                    wires_per_cable,
+                } => (Some(cables), wires_per_cable),
+            };
+            for (number, min) in [(cables, 0), (Some(wires), 1)] {
+                let Some(number) = number else {
+                    continue;
+                };
+                let name = match number {
+                    TemplateNumber::Const(name) => name.clone(),
+                    TemplateNumber::Num(value) => value.to_string(),
+                };
+                match number.value(&values) {
+                    None => return Err(BlockShapeError::MissingValue(name)),
+                    Some(value) if !(min..=u8::MAX as i64).contains(&value) => {
+                        return Err(BlockShapeError::InvalidValue { name, value })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        desc.shape(&values).map_err(BlockShapeError::Conflict)
+    }
+}
+
 impl BlockShape {
+    /// A hash of everything that is drawn, the same for equal shapes
     pub fn id(&self) -> BlockShapeId {
-        todo!()
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        BlockShapeId(hasher.finish())
     }
 
+    /// Size in grid units.
+    ///
+    /// There is a row or a column for each pin on a side,
+    /// and it is wide enough for the lable of the block between the lables of the pins.
     pub fn size(&self) -> Vec2<f32> {
-        todo!()
+        let widest = |cables: &[BlockCable]| {
+            (cables.iter())
+                .map(|cable| cable.lable.chars().count())
+                .max()
+                .unwrap_or(0)
+        };
+        let chars = self.lable.chars().count() + widest(&self.inputs) + widest(&self.outputs);
+        let width = (chars as f32 * CHAR_WIDTH).ceil() + 2.;
+        let [left, top, right, bottom] = self.side_pins();
+        let columns = top.max(bottom) as f32 + 1.;
+        let rows = left.max(right).max(1) as f32 + 1.;
+        Vec2::new(width.max(columns).max(MIN_WIDTH), rows)
     }
 }
@@ -0,0 +1,260 @@
+//! Where the pins of a block are, and how a placed block is turned.
+//!
+//! The pins of a shape are in rows and columns one grid unit apart, starting one unit
+//! from the corner, in the order of the inputs and then the outputs.
+//! A placed block is first mirrored left to right, then turned clockwise.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Grid units around a pin that hit it
+pub const PIN_RADIUS: f32 = 0.5;
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+/// The side of each cable of a shape
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinLayout {
+    /// Empty if all the inputs are on the left
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<Side>,
+    /// Empty if all the outputs are on the right
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<Side>,
+}
+
+/// Clockwise rotation of a placed block
+#[derive(Debug, Copy, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum Rotation {
+    #[default]
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+#[derive(Debug, Copy, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Orientation {
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// Flipped left to right, before the rotation
+    #[serde(default)]
+    pub mirrored: bool,
+}
+
+/// What is under a point of a placed block
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BlockHit {
+    Input(usize),
+    Output(usize),
+    Body,
+}
+
+impl Side {
+    /// The side where this side of a block ends up with the orientation
+    pub fn oriented(self, orientation: Orientation) -> Side {
+        let mut side = match (orientation.mirrored, self) {
+            (true, Side::Left) => Side::Right,
+            (true, Side::Right) => Side::Left,
+            _ => self,
+        };
+        for _ in 0..orientation.rotation.quarter_turns() {
+            side = match side {
+                Side::Left => Side::Top,
+                Side::Top => Side::Right,
+                Side::Right => Side::Bottom,
+                Side::Bottom => Side::Left,
+            };
+        }
+        side
+    }
+}
+
+impl PinLayout {
+    pub fn is_default(&self) -> bool {
+        self.inputs.is_empty() && self.outputs.is_empty()
+    }
+
+    pub fn input_side(&self, pin: usize) -> Side {
+        self.inputs.get(pin).copied().unwrap_or(Side::Left)
+    }
+
+    pub fn output_side(&self, pin: usize) -> Side {
+        self.outputs.get(pin).copied().unwrap_or(Side::Right)
+    }
+}
+
+impl Rotation {
+    pub fn quarter_turns(self) -> u8 {
+        self as u8
+    }
+
+    pub fn degrees(self) -> u16 {
+        self.quarter_turns() as u16 * 90
+    }
+
+    /// The rotation of some degrees, if it is a multiple of 90 up to 270
+    pub fn from_degrees(degrees: u16) -> Option<Self> {
+        match degrees {
+            0 => Some(Rotation::R0),
+            90 => Some(Rotation::R90),
+            180 => Some(Rotation::R180),
+            270 => Some(Rotation::R270),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<u16> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u16) -> Result<Self, String> {
+        Rotation::from_degrees(degrees)
+            .ok_or_else(|| format!("invalid rotation {degrees}, it must be 0, 90, 180 or 270"))
+    }
+}
+
+impl From<Rotation> for u16 {
+    fn from(rotation: Rotation) -> u16 {
+        rotation.degrees()
+    }
+}
+
+impl Orientation {
+    pub fn is_default(&self) -> bool {
+        *self == Orientation::default()
+    }
+
+    /// Moves a point of a shape of the given size to where it is with the orientation
+    pub fn apply(self, point: Vec2<i32>, size: Vec2<i32>) -> Vec2<i32> {
+        let [mut x, mut y] = <[i32; 2]>::from(point);
+        let [mut w, mut h] = <[i32; 2]>::from(size);
+        if self.mirrored {
+            x = w - x;
+        }
+        for _ in 0..self.rotation.quarter_turns() {
+            (x, y) = (h - y, x);
+            (w, h) = (h, w);
+        }
+        Vec2::new(x, y)
+    }
+}
+
+impl BlockShape {
+    /// The side of a pin, without orientation
+    pub fn pin_side(&self, output: bool, pin: usize) -> Side {
+        match output {
+            true => self.layout.output_side(pin),
+            false => self.layout.input_side(pin),
+        }
+    }
+
+    /// Number of pins on each side, in the order of [`Side`]
+    pub(crate) fn side_pins(&self) -> [usize; 4] {
+        let mut pins = [0; 4];
+        for (pin, _) in self.inputs.iter().enumerate() {
+            pins[self.layout.input_side(pin) as usize] += 1;
+        }
+        for (pin, _) in self.outputs.iter().enumerate() {
+            pins[self.layout.output_side(pin) as usize] += 1;
+        }
+        pins
+    }
+
+    /// Position of a pin from the top left corner, without orientation
+    pub fn pin_offset(&self, output: bool, pin: usize) -> Option<Vec2<i32>> {
+        let cables = if output { &self.outputs } else { &self.inputs };
+        if pin >= cables.len() {
+            return None;
+        }
+        let side = self.pin_side(output, pin);
+        let before = |output, cables: &[BlockCable]| {
+            (0..cables.len())
+                .filter(|other| self.pin_side(output, *other) == side)
+                .count() as i32
+        };
+        let row = match output {
+            false => before(false, &self.inputs[..pin]),
+            true => before(false, &self.inputs) + before(true, &self.outputs[..pin]),
+        } + 1;
+
+        let size = self.size();
+        let (w, h) = (size.x as i32, size.y as i32);
+        Some(match side {
+            Side::Left => Vec2::new(0, row),
+            Side::Right => Vec2::new(w, row),
+            Side::Top => Vec2::new(row, 0),
+            Side::Bottom => Vec2::new(row, h),
+        })
+    }
+}
+
+impl Block {
+    /// Size in grid units, with the orientation
+    pub fn size(&self) -> Vec2<i32> {
+        let size = self.shape.size();
+        let (w, h) = (size.x as i32, size.y as i32);
+        match self.orientation.rotation {
+            Rotation::R0 | Rotation::R180 => Vec2::new(w, h),
+            Rotation::R90 | Rotation::R270 => Vec2::new(h, w),
+        }
+    }
+
+    pub fn rect(&self) -> Rect<i32> {
+        Rect::from_size(self.pos, self.size())
+    }
+
+    /// Position of a pin on the grid
+    pub fn pin_pos(&self, output: bool, pin: usize) -> Option<Vec2<i32>> {
+        let offset = self.shape.pin_offset(output, pin)?;
+        let size = self.shape.size();
+        let size = Vec2::new(size.x as i32, size.y as i32);
+        Some(self.pos + self.orientation.apply(offset, size))
+    }
+
+    /// The side of the placed block where a pin is
+    pub fn pin_side(&self, output: bool, pin: usize) -> Side {
+        self.shape.pin_side(output, pin).oriented(self.orientation)
+    }
+
+    /// What is at a point, in grid units. The pins stick out of the block by [`PIN_RADIUS`].
+    pub fn hit(&self, point: Vec2<f32>) -> Option<BlockHit> {
+        let [x, y] = <[f32; 2]>::from(point);
+        let near = |pin: Vec2<i32>| {
+            let [px, py] = <[i32; 2]>::from(pin);
+            (x - px as f32).abs() <= PIN_RADIUS && (y - py as f32).abs() <= PIN_RADIUS
+        };
+        for pin in 0..self.shape.inputs.len() {
+            if self.pin_pos(false, pin).is_some_and(near) {
+                return Some(BlockHit::Input(pin));
+            }
+        }
+        for pin in 0..self.shape.outputs.len() {
+            if self.pin_pos(true, pin).is_some_and(near) {
+                return Some(BlockHit::Output(pin));
+            }
+        }
+
+        let rect = self.rect();
+        let ([x0, y0], [x1, y1]) = (<[i32; 2]>::from(rect.min), <[i32; 2]>::from(rect.max));
+        let inside = (x0 as f32..=x1 as f32).contains(&x) && (y0 as f32..=y1 as f32).contains(&y);
+        inside.then_some(BlockHit::Body)
+    }
+}
+
+impl ChapterSolution {
+    /// The block at a point and what of it is there.
+    /// The blocks placed last are on top.
+    pub fn hit(&self, point: Vec2<f32>) -> Option<(usize, BlockHit)> {
+        (self.blocks.iter().enumerate().rev())
+            .find_map(|(index, block)| Some((index, block.hit(point)?)))
+    }
+}
@@ -1,15 +1,38 @@
 mod block_description;
 mod block_logic;
 mod block_shape;
+mod layout;
+mod template;
 
 use crate::*;
 pub use block_description::*;
 pub use block_logic::*;
 pub use block_shape::*;
+pub use layout::*;
+use serde::{Deserialize, Serialize};
+pub use template::*;
 
 /// A placed block on a chapter
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     // [Perfomance TODO] Share Block struct for multipl BlockPanel instances. (Change 'block' to be an index or a Rc<>)
     pub shape: BlockShape,
+    #[serde(with = "pos_format")]
     pub pos: Vec2<i32>,
+    #[serde(default, skip_serializing_if = "Orientation::is_default")]
+    pub orientation: Orientation,
+}
+
+/// Saves a position as `[x, y]`
+mod pos_format {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(pos: &Vec2<i32>, serializer: S) -> Result<S::Ok, S::Error> {
+        <[i32; 2]>::from(*pos).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2<i32>, D::Error> {
+        <[i32; 2]>::deserialize(deserializer).map(Vec2::from)
+    }
 }
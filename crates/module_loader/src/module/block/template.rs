@@ -0,0 +1,343 @@
+//! Template values of the blocks, that give the number of cables and wires of their pins
+//!
+//! The values of a placed block are not stored, they are read from the widths of its shape.
+//! A cable of 0 wires has a width that is not known yet.
+
+use crate::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Cables of a bundle whose size is not known when the block is placed
+pub const DEFAULT_BUNDLE_CABLES: u8 = 2;
+
+/// Values of the template names of a placed block, like `C` in `cable<C>`
+pub type TemplateValues = HashMap<String, i64>;
+
+/// The type of a pin of a placed block or of a chapter port, with the template values applied
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PinType {
+    /// A cable of the given number of wires, 0 if it is not known yet
+    Cable(u8),
+    Bundle {
+        cables: u8,
+        wires_per_cable: u8,
+    },
+}
+
+/// A template name that should have two different values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateConflict {
+    pub name: String,
+    pub values: [i64; 2],
+}
+
+impl PinType {
+    /// Wires of each cable of the pin
+    pub fn cable_wires(self) -> u8 {
+        match self {
+            PinType::Cable(wires) => wires,
+            PinType::Bundle {
+                wires_per_cable, ..
+            } => wires_per_cable,
+        }
+    }
+}
+
+impl fmt::Display for PinType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PinType::Cable(0) => write!(f, "cable"),
+            PinType::Cable(1) => write!(f, "wire"),
+            PinType::Cable(wires) => write!(f, "cable<{wires}>"),
+            PinType::Bundle {
+                cables,
+                wires_per_cable,
+            } => write!(f, "bundle<{cables}, {wires_per_cable}>"),
+        }
+    }
+}
+
+impl TemplateNumber {
+    pub fn value(&self, values: &TemplateValues) -> Option<i64> {
+        match self {
+            TemplateNumber::Const(name) => values.get(name).copied(),
+            TemplateNumber::Num(value) => Some(*value),
+        }
+    }
+}
+
+impl TemplateExpr {
+    pub fn value(&self, values: &TemplateValues) -> Option<i64> {
+        match self {
+            TemplateExpr::Number(number) => number.value(values),
+            TemplateExpr::Bits(name) => {
+                let count = *values.get(name)?;
+                Some((64 - (count - 1).max(1).leading_zeros()) as i64)
+            }
+        }
+    }
+}
+
+impl BlockDescSubset {
+    /// The template values of a new block of the subset.
+    /// Like [`BlockDesc::new_values`], with the values of the subset.
+    pub fn new_values(&self) -> Result<TemplateValues, TemplateConflict> {
+        self.block_desc.complete_values(self.values.clone())
+    }
+
+    /// Returns true if the shape follows the description, its template values agree
+    /// with the constants, and it has the values of the subset.
+    pub fn allows(&self, shape: &BlockShape) -> bool {
+        let desc = &self.block_desc;
+        if shape.description != desc.id {
+            return false;
+        }
+        let Some(mut values) = desc.template_values(shape) else {
+            return false;
+        };
+        desc.resolve(&mut values).is_ok()
+            && (self.values.iter()).all(|(name, value)| values.get(name) == Some(value))
+    }
+}
+
+impl PinTypeTemplate {
+    /// The type with the given values. The unknown widths are 0.
+    ///
+    /// A value that is not a number of cables or wires conflicts with the nearest number that is.
+    pub fn resolve(&self, values: &TemplateValues) -> Result<PinType, TemplateConflict> {
+        let number = |number: &TemplateNumber, unknown: u8| {
+            let Some(value) = number.value(values) else {
+                return Ok(unknown);
+            };
+            u8::try_from(value).map_err(|_| TemplateConflict {
+                name: match number {
+                    TemplateNumber::Const(name) => name.clone(),
+                    TemplateNumber::Num(value) => value.to_string(),
+                },
+                values: [value, value.clamp(0, u8::MAX as i64)],
+            })
+        };
+        Ok(match self {
+            PinTypeTemplate::Cable { wires } => PinType::Cable(number(wires, 0)?),
+            PinTypeTemplate::Bundle {
+                cables,
+                wires_per_cable,
+            } => PinType::Bundle {
+                cables: number(cables, DEFAULT_BUNDLE_CABLES)?,
+                wires_per_cable: number(wires_per_cable, 0)?,
+            },
+        })
+    }
+
+    /// The template name of the width of the cables, if it is not a number
+    pub fn width_name(&self) -> Option<&str> {
+        match self {
+            PinTypeTemplate::Cable {
+                wires: TemplateNumber::Const(name),
+            }
+            | PinTypeTemplate::Bundle {
+                wires_per_cable: TemplateNumber::Const(name),
+                ..
+            } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl BlockDesc {
+    /// The shape of a new block with the given template values.
+    ///
+    /// Bundles of unknown size get [`DEFAULT_BUNDLE_CABLES`] cables,
+    /// and cables of unknown width have 0 wires.
+    pub fn shape(&self, values: &TemplateValues) -> Result<BlockShape, TemplateConflict> {
+        let cables = |pins: &[BlockPinDesc], default: Side| {
+            let mut cables = Vec::new();
+            let mut sides = Vec::new();
+            for pin in pins {
+                let pin_type = pin.pin_type.resolve(values)?;
+                let count = match pin_type {
+                    PinType::Cable(_) => 1,
+                    PinType::Bundle { cables, .. } => cables,
+                };
+                for i in 0..count {
+                    let lable = match pin_type {
+                        PinType::Cable(_) => pin.lable.clone(),
+                        PinType::Bundle { .. } => format!("{}{i}", pin.lable),
+                    };
+                    let wires = pin_type.cable_wires();
+                    cables.push(BlockCable { lable, wires });
+                    sides.push(pin.side);
+                }
+            }
+            // The default layout is written as no sides
+            let sides = match sides.iter().all(Option::is_none) {
+                true => vec![],
+                false => sides
+                    .into_iter()
+                    .map(|side| side.unwrap_or(default))
+                    .collect(),
+            };
+            Ok::<_, TemplateConflict>((cables, sides))
+        };
+
+        let (inputs, input_sides) = cables(&self.inputs, Side::Left)?;
+        let (outputs, output_sides) = cables(&self.outputs, Side::Right)?;
+        Ok(BlockShape {
+            description: self.id.clone(),
+            lable: self.lable.clone(),
+            inputs,
+            outputs,
+            layout: PinLayout {
+                inputs: input_sides,
+                outputs: output_sides,
+            },
+        })
+    }
+
+    /// The template values of a new block: the default size of the bundles,
+    /// and the constants that can be computed from them
+    pub fn new_values(&self) -> Result<TemplateValues, TemplateConflict> {
+        self.complete_values(TemplateValues::new())
+    }
+
+    /// Gives the default size to the bundles without value, and computes the constants
+    fn complete_values(
+        &self,
+        mut values: TemplateValues,
+    ) -> Result<TemplateValues, TemplateConflict> {
+        for pin in self.inputs.iter().chain(&self.outputs) {
+            if let PinTypeTemplate::Bundle {
+                cables: TemplateNumber::Const(name),
+                ..
+            } = &pin.pin_type
+            {
+                if !self.constants.contains_key(name) {
+                    (values.entry(name.clone())).or_insert(DEFAULT_BUNDLE_CABLES as i64);
+                }
+            }
+        }
+        self.resolve(&mut values)?;
+        Ok(values)
+    }
+
+    /// Returns true if `name` is a template of a pin or a constant of the description
+    pub fn has_template(&self, name: &str) -> bool {
+        let is_name =
+            |number: &TemplateNumber| matches!(number, TemplateNumber::Const(n) if n == name);
+        self.constants.contains_key(name)
+            || (self.inputs.iter().chain(&self.outputs)).any(|pin| match &pin.pin_type {
+                PinTypeTemplate::Cable { wires } => is_name(wires),
+                PinTypeTemplate::Bundle {
+                    cables,
+                    wires_per_cable,
+                } => is_name(cables) || is_name(wires_per_cable),
+            })
+    }
+
+    /// Reads the template values of a placed block from its shape.
+    /// Returns `None` if the shape does not follow the description.
+    pub fn template_values(&self, shape: &BlockShape) -> Option<TemplateValues> {
+        let mut values = TemplateValues::new();
+        for (pins, cables) in [
+            (&self.inputs, &shape.inputs),
+            (&self.outputs, &shape.outputs),
+        ] {
+            // The size of one bundle on each side can be known from the number of cables
+            let sizes: Vec<Option<i64>> = (pins.iter())
+                .map(|pin| match &pin.pin_type {
+                    PinTypeTemplate::Cable { .. } => Some(1),
+                    PinTypeTemplate::Bundle { cables, .. } => cables.value(&values),
+                })
+                .collect();
+            let known: i64 = sizes.iter().flatten().sum();
+            let unknown = match sizes.iter().filter(|size| size.is_none()).count() {
+                0 => None,
+                1 => Some(cables.len() as i64 - known),
+                _ => return None,
+            };
+            if unknown.is_none() && known != cables.len() as i64 || unknown.is_some_and(|u| u < 0) {
+                return None;
+            }
+
+            let mut cables = cables.iter();
+            for (pin, size) in pins.iter().zip(sizes) {
+                let size = size.or(unknown).unwrap();
+                if let PinTypeTemplate::Bundle {
+                    cables: TemplateNumber::Const(name),
+                    ..
+                } = &pin.pin_type
+                {
+                    values.insert(name.clone(), size);
+                }
+                for cable in cables.by_ref().take(size as usize) {
+                    let wires = cable.wires as i64;
+                    match pin.pin_type.width_name() {
+                        Some(_) if wires == 0 => {}
+                        Some(name) => match values.get(name) {
+                            Some(value) if *value != wires => return None,
+                            _ => {
+                                values.insert(name.into(), wires);
+                            }
+                        },
+                        None => {
+                            let pin_type = pin.pin_type.resolve(&values).ok()?;
+                            if pin_type.cable_wires() != cable.wires {
+                                return None;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Some(values)
+    }
+
+    /// Computes the constants of the description from the known values
+    pub fn resolve(&self, values: &mut TemplateValues) -> Result<(), TemplateConflict> {
+        loop {
+            let mut changed = false;
+            for (name, expr) in &self.constants {
+                let Some(value) = expr.value(values) else {
+                    continue;
+                };
+                match values.get(name) {
+                    None => {
+                        values.insert(name.clone(), value);
+                        changed = true;
+                    }
+                    Some(known) if *known != value => {
+                        return Err(TemplateConflict {
+                            name: name.clone(),
+                            values: [*known, value],
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The pin of the description that has the cable number `cable` of a placed block
+    pub fn cable_pin(
+        &self,
+        values: &TemplateValues,
+        output: bool,
+        cable: usize,
+    ) -> Option<&BlockPinDesc> {
+        let pins = if output { &self.outputs } else { &self.inputs };
+        let mut first = 0;
+        for pin in pins {
+            first += match pin.pin_type.resolve(values).ok()? {
+                PinType::Cable(_) => 1,
+                PinType::Bundle { cables, .. } => cables as usize,
+            };
+            if cable < first {
+                return Some(pin);
+            }
+        }
+        None
+    }
+}
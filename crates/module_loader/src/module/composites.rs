@@ -0,0 +1,74 @@
+//! Composite blocks made by the player, saved apart from the module that they extend
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{read_to_string, write};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize)]
+struct CompositesSaveFile {
+    composites: Vec<CompositeSave>,
+}
+
+/// A composite block with its netlist, that refers to the other blocks by name
+#[derive(Serialize, Deserialize)]
+struct CompositeSave {
+    name: String,
+    lable: String,
+    group: String,
+    /// The color packed as `r | g << 8 | b << 16 | a << 24`
+    color: u32,
+    netlist: CompositeLogic,
+}
+
+impl Module {
+    /// Adds a composite block to the blocks of the module,
+    /// like one made with [`BlockDesc::from_solution`].
+    pub fn add_composite(&mut self, desc: BlockDesc) {
+        self.blocks.insert(desc.id.name.clone(), Arc::new(desc));
+    }
+
+    /// Saves the netlists of the composite blocks of the module
+    pub fn save_composites(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut composites: Vec<CompositeSave> = (self.blocks.values())
+            .filter_map(|desc| match &desc.logic {
+                Some(BlockLogic::Composite(netlist)) => Some(CompositeSave {
+                    name: desc.id.name.clone(),
+                    lable: desc.lable.clone(),
+                    group: desc.group.clone(),
+                    color: desc.color.into(),
+                    netlist: netlist.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        composites.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let save_file = CompositesSaveFile { composites };
+        let toml = toml::to_string(&save_file)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        write(path, toml)
+    }
+
+    /// Adds the composite blocks saved with [`Module::save_composites`] to the module.
+    /// They replace the blocks with the same name.
+    pub fn load_composites(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let src = read_to_string(path)?;
+        let save_file: CompositesSaveFile = toml::from_str(&src)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        for composite in save_file.composites {
+            self.add_composite(BlockDesc::from_composite(
+                BlockDescId {
+                    name: composite.name,
+                },
+                composite.lable,
+                composite.group,
+                composite.color.into(),
+                composite.netlist,
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct ModuleId {
     pub name: String,
@@ -16,7 +18,7 @@ pub struct ChapterId {
     pub title: String,
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BlockDescId {
     pub name: String,
 }
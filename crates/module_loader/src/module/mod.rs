@@ -1,12 +1,17 @@
 mod block;
+mod composites;
 mod id;
 mod local_modules;
+mod sequence_test;
 mod solution;
+mod truth_table;
 
 pub use block::*;
 pub use id::*;
 pub use local_modules::*;
+pub use sequence_test::*;
 pub use solution::*;
+pub use truth_table::*;
 
 use std::{collections::HashMap, sync::Arc};
 
@@ -22,16 +27,40 @@ pub struct Module {
 
 pub struct Book {
     pub id: BookId,
+    /// Blocks allowed in all the chapters of the book
+    pub allowed_blocks: Vec<BlockDescSubset>,
     pub(crate) chapters: Vec<Chapter>,
 }
 
 pub struct Chapter {
     pub id: ChapterId,
     pub allowed_blocks: Vec<BlockDescSubset>,
+    /// Cables that the solution receives
+    pub inputs: Vec<BlockCable>,
+    /// Cables that the solution has to generate
+    pub outputs: Vec<BlockCable>,
+    pub truth_table: Option<TruthTable>,
+    /// Expected behaviour over time, for the chapters with memory
+    pub test: Option<SequenceTest>,
+    /// A solution shipped with the module, that only uses the allowed blocks
+    pub reference_solution: Option<ChapterSolution>,
     pub completion_status: ChapterCompletionStatus,
+    /// Metrics of the best solution that completed the chapter
+    pub best_metrics: Option<SolutionMetrics>,
 }
 
 impl Module {
+    /// A module without books, that only provides blocks
+    pub fn new(id: ModuleId, blocks: ModuleBlocks) -> Self {
+        Module {
+            id,
+            description: String::new(),
+            author: Vec::new(),
+            blocks,
+            books: HashMap::new(),
+        }
+    }
+
     pub fn get_book(&self, book_id: &BookId) -> &Book {
         if self.id != book_id.module_id {
             panic!("Invalid BookId. Requesting a book of a diferent module");
@@ -41,6 +70,18 @@ impl Module {
     pub fn iter_books(&self) -> impl Iterator<Item = &Book> {
         self.books.values()
     }
+
+    /// Finds a chapter by title.
+    /// If many books have a chapter with the title, use `book/chapter`.
+    pub fn find_chapter(&self, name: &str) -> Option<&Chapter> {
+        if let Some((book, chapter)) = name.split_once('/') {
+            let book = self.books.get(book)?;
+            return book.iter_chapters().find(|c| c.id.title == chapter);
+        }
+        (self.iter_books())
+            .flat_map(|book| book.iter_chapters())
+            .find(|c| c.id.title == name)
+    }
 }
 
 impl Book {
@@ -48,3 +89,12 @@ impl Book {
         self.chapters.iter()
     }
 }
+
+impl Chapter {
+    /// Returns true if the chapter or its book allow to use a block with the shape
+    pub fn allows_block(&self, book: &Book, shape: &BlockShape) -> bool {
+        (self.allowed_blocks.iter())
+            .chain(&book.allowed_blocks)
+            .any(|subset| subset.allows(shape))
+    }
+}
@@ -0,0 +1,19 @@
+/// Inputs and expected outputs of a chapter solution over time,
+/// for the chapters whose outputs depend on the previous inputs
+#[derive(Debug, Clone)]
+pub struct SequenceTest {
+    /// Index in `Chapter::inputs` of the clock input.
+    /// If there is a clock, each step is a rising edge: the clock is set to 0, and then to 1.
+    /// Otherwise each step is a tick of the simulation.
+    pub clock: Option<usize>,
+    pub steps: Vec<TestStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestStep {
+    /// Value of each chapter input on the step, `None` if it keeps the previous value.
+    /// The inputs start at 0.
+    pub inputs: Vec<Option<u64>>,
+    /// Expected value of each chapter output after the step, `None` if it is not checked
+    pub expected: Vec<Option<u64>>,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Measures of the quality of a solution. Lower is better.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolutionMetrics {
+    /// Number of blocks of each description placed in the solution, by name
+    pub block_counts: BTreeMap<String, usize>,
+    /// Sum of the costs of the blocks, where a composite block costs as much as its blocks
+    pub cost: u64,
+    /// Blocks on the longest combinational path, from a chapter input or a sequential block
+    /// to a chapter output or a sequential block.
+    /// It is `None` if the solution has a combinational loop.
+    pub critical_path: Option<u32>,
+}
+
+impl SolutionMetrics {
+    /// Total number of blocks placed in the solution
+    pub fn blocks(&self) -> usize {
+        self.block_counts.values().sum()
+    }
+
+    /// Compares the cost, then the critical path and then the number of blocks
+    pub fn is_better_than(&self, other: &SolutionMetrics) -> bool {
+        // A solution with a loop has the longest path
+        let path = |metrics: &SolutionMetrics| metrics.critical_path.unwrap_or(u32::MAX);
+        (self.cost, path(self), self.blocks()) < (other.cost, path(other), other.blocks())
+    }
+}
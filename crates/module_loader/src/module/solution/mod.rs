@@ -1,40 +1,149 @@
+mod metrics;
 mod save_file;
+mod text;
 
 use crate::*;
+pub use metrics::*;
 use save_file::*;
-use std::{collections::HashMap, path::PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+pub use text::*;
 
 pub struct ChapterSolution {
     pub completion_status: ChapterCompletionStatus,
     pub blocks: Vec<Block>,
+    pub wires: Vec<Wire>,
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Connects an output cable (`source`) to an input cable (`sink`).
+///
+/// A source can drive many sinks, but a sink has at most one source.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Wire {
+    pub source: PinRef,
+    pub sink: PinRef,
+}
+
+/// A cable pin of a solution.
+/// It refers to an input or an output depending on which end of the wire it is.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PinRef {
+    /// The pin number `pin` of `blocks[block]`
+    Block { block: usize, pin: usize },
+    /// A port of the chapter.
+    /// The chapter inputs are sources and the chapter outputs are sinks.
+    Chapter { port: usize },
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChapterCompletionStatus {
+    #[default]
     NotStarted,
     InProgress,
     Completed,
 }
 
 impl Chapter {
-    // pub fn load_solution(&self) -> ChapterSolution {
-    //     let save_file_path = todo!()
-    //
-    //     if let Some(save_file) = SolutionSaveFile::from_path(save_file_path) {
-    //         ChapterSolution {
-    //             blocks: save_file.blocks,
-    //             completion_status: self.completion_status,
-    //         }
-    //     } else {
-    //         self.new_solution()
-    //     }
-    // }
+    /// Loads a solution saved with [`Chapter::save_solution`], and restores the
+    /// completion status and the best metrics of the chapter from it.
+    /// Returns `None` if it does not exist or it is invalid.
+    pub fn load_solution(&mut self, path: impl AsRef<Path>) -> Option<ChapterSolution> {
+        let save_file = SolutionSaveFile::from_path(path)?;
+        self.completion_status = save_file.completion_status;
+        self.best_metrics = save_file.best_metrics;
+        Some(ChapterSolution {
+            blocks: save_file.blocks,
+            wires: save_file.wires,
+            completion_status: self.completion_status,
+        })
+    }
+
+    /// Saves a solution with the completion status and the best metrics of the chapter
+    pub fn save_solution(
+        &self,
+        solution: &ChapterSolution,
+        path: impl AsRef<Path>,
+    ) -> std::io::Result<()> {
+        let save_file = SolutionSaveFile {
+            completion_status: self.completion_status,
+            best_metrics: self.best_metrics.clone(),
+            blocks: solution.blocks.clone(),
+            wires: solution.wires.clone(),
+        };
+        save_file.save(path)
+    }
+
+    /// Keeps `metrics` as the best metrics of the chapter if they are better.
+    /// Returns true if they are kept.
+    pub fn record_metrics(&mut self, metrics: SolutionMetrics) -> bool {
+        let better = (self.best_metrics.as_ref()).is_none_or(|best| metrics.is_better_than(best));
+        if better {
+            self.best_metrics = Some(metrics);
+        }
+        better
+    }
 
     /// Creates a blank solution without checking or loading any save_file
     pub fn new_solution(&self) -> ChapterSolution {
         ChapterSolution {
             blocks: vec![],
+            wires: vec![],
             completion_status: ChapterCompletionStatus::NotStarted,
         }
     }
 }
+
+impl ChapterSolution {
+    /// Loads a solution saved with [`ChapterSolution::save`],
+    /// or written in the text format if the file ends with `.txt`.
+    /// Returns `None` if it does not exist or it is invalid.
+    pub fn load(
+        path: impl AsRef<Path>,
+        completion_status: ChapterCompletionStatus,
+    ) -> Option<Self> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|extension| extension == "txt") {
+            let text = std::fs::read_to_string(path).ok()?;
+            let mut solution = SolutionText::parse(&text).ok()?.solution;
+            solution.completion_status = completion_status;
+            return Some(solution);
+        }
+        let save_file = SolutionSaveFile::from_path(path)?;
+        Some(ChapterSolution {
+            blocks: save_file.blocks,
+            wires: save_file.wires,
+            completion_status,
+        })
+    }
+
+    /// The first wire with an end that is not a pin of its block or a port of the chapter
+    pub fn dangling_wire(&self, chapter: &Chapter) -> Option<Wire> {
+        let exists = |pin: PinRef, source: bool| match pin {
+            PinRef::Chapter { port } => match source {
+                true => port < chapter.inputs.len(),
+                false => port < chapter.outputs.len(),
+            },
+            PinRef::Block { block, pin } => {
+                self.blocks.get(block).is_some_and(|block| match source {
+                    true => pin < block.shape.outputs.len(),
+                    false => pin < block.shape.inputs.len(),
+                })
+            }
+        };
+        (self.wires.iter().copied())
+            .find(|wire| !exists(wire.source, true) || !exists(wire.sink, false))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let save_file = SolutionSaveFile {
+            completion_status: self.completion_status,
+            best_metrics: None,
+            blocks: self.blocks.clone(),
+            wires: self.wires.clone(),
+        };
+        save_file.save(path)
+    }
+}
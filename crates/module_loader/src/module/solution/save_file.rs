@@ -1,12 +1,25 @@
 use crate::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{read_to_string, write};
 use std::path::*;
 
+#[derive(Serialize, Deserialize)]
 pub struct SolutionSaveFile {
+    /// Progress of the chapter when the solution was saved
+    #[serde(default)]
+    pub completion_status: ChapterCompletionStatus,
+    pub best_metrics: Option<SolutionMetrics>,
     pub blocks: Vec<Block>,
+    pub wires: Vec<Wire>,
 }
 
 impl SolutionSaveFile {
     pub fn from_path(p: impl AsRef<Path>) -> Option<Self> {
-        todo!()
+        let src = read_to_string(p).ok()?;
+        toml::from_str(&src).ok()
+    }
+
+    pub fn save(&self, p: impl AsRef<Path>) -> std::io::Result<()> {
+        write(p, toml::to_string(self).unwrap())
     }
 }
@@ -0,0 +1,897 @@
+//! A text format for solutions, to write them by hand and compare them with diff.
+//!
+//! ```text
+//! # A 2 bit adder made of full adders
+//! input a[2], b[2]
+//! output sum[3]
+//!
+//! block split_a: Split<W=2>(in[W]) -> (_, _) at 2 0
+//! block split_b: Split<W=2>(in[W]) -> (_, _) at 2 4
+//! block fa0: "Full Adder"(a, b, cin) -> (s, cout) at 6 0
+//! block fa1: "Full Adder"(a, b, cin top) -> (s, cout) at 6 4
+//! block join: Join(_, _, _) -> (out[3]) at 10 2 lable "Join sum"
+//!
+//! split_a.in = a
+//! split_b.in = b
+//! fa0.a = split_a.0
+//! fa0.b = split_b.0
+//! fa1.a = split_a.1
+//! fa1.b = split_b.1
+//! net carry = fa0.cout
+//! fa1.cin, join.2 = carry
+//! join.0 = fa0.s
+//! join.1 = fa1.s
+//! sum = join.out
+//! ```
+//!
+//! Each line is a statement:
+//! - `input` and `output` list the chapter ports, as `name` or `name[wires]`.
+//! - `block` places a block: its name in the text, its description, the template parameters
+//!   that the widths can use, its input and output cables (`_` if they have no lable),
+//!   and optionally its position, its rotation (`turn 90`) and whether it is `mirrored`,
+//!   and the lable shown, which is the name by default.
+//!   A cable can be followed by the side of its pin, `left`, `top`, `right` or `bottom`,
+//!   when it is not the default one: left for the inputs and right for the outputs.
+//! - `net` names a source, to use it in the connections.
+//! - `sinks = source` connects a source to some sinks. A pin of a block is
+//!   `block.pin`, with the lable of the pin or its number.
+//!
+//! Names and lables that are not identifiers are written between quotes,
+//! and everything after a `#` is a comment.
+
+use crate::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A solution read from the text format, with the chapter ports that it uses
+/// and the names of the text, to write it back the same way
+pub struct SolutionText {
+    pub inputs: Vec<BlockCable>,
+    pub outputs: Vec<BlockCable>,
+    pub solution: ChapterSolution,
+    /// How each block of the solution is written
+    pub blocks: Vec<BlockText>,
+    /// The named sources, in their order
+    pub nets: Vec<NetText>,
+}
+
+/// How a block is written in the text
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockText {
+    /// Name of the block in the text, that the connections use
+    pub name: String,
+    /// Template parameters and their values, in their order
+    pub parameters: Vec<(String, i64)>,
+    /// Parameter written as the width of each input cable, if any
+    pub input_widths: Vec<Option<String>>,
+    /// Parameter written as the width of each output cable, if any
+    pub output_widths: Vec<Option<String>>,
+}
+
+/// A source named with `net`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetText {
+    pub name: String,
+    pub source: PinRef,
+}
+
+/// An error in a line of a solution in the text format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextError {
+    /// Number of the line, starting at 1
+    pub line: usize,
+    pub kind: TextErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextErrorKind {
+    /// The line does not follow the format. It has something else where it expects this.
+    Expected(&'static str),
+    /// A width uses a template parameter that the block does not have
+    UnknownParameter(String),
+    /// Two ports, blocks or nets have the same name
+    Duplicate(String),
+    /// There is no port, block or net with this name
+    UnknownName(String),
+    /// The block has no pin with this lable or number
+    UnknownPin { block: String, pin: String },
+    /// The sink is connected to more than one source
+    ManySources(String),
+    /// The net is defined from itself
+    NetLoop(String),
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            TextErrorKind::Expected(expected) => write!(f, "expected {expected}"),
+            TextErrorKind::UnknownParameter(name) => write!(f, "unknown parameter {name:?}"),
+            TextErrorKind::Duplicate(name) => write!(f, "{name:?} is declared twice"),
+            TextErrorKind::UnknownName(name) => write!(f, "unknown port, block or net {name:?}"),
+            TextErrorKind::UnknownPin { block, pin } => {
+                write!(f, "the block {block:?} has no pin {pin:?}")
+            }
+            TextErrorKind::ManySources(sink) => write!(f, "{sink} has more than one source"),
+            TextErrorKind::NetLoop(name) => write!(f, "the net {name:?} is defined from itself"),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+impl SolutionText {
+    /// Reads a solution written in the text format
+    pub fn parse(text: &str) -> Result<Self, TextError> {
+        let mut statements = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let error = |kind| TextError {
+                line: index + 1,
+                kind,
+            };
+            let tokens = tokenize(line).map_err(error)?;
+            if !tokens.is_empty() {
+                let statement = Tokens { tokens, next: 0 }.statement().map_err(error)?;
+                statements.push((index + 1, statement));
+            }
+        }
+
+        let mut netlist = Netlist::default();
+        for (line, statement) in &statements {
+            let error = |kind| TextError { line: *line, kind };
+            netlist.declare(statement).map_err(error)?;
+        }
+        for (line, statement) in &statements {
+            let error = |kind| TextError { line: *line, kind };
+            netlist.connect(statement).map_err(error)?;
+        }
+
+        Ok(SolutionText {
+            inputs: netlist.inputs,
+            outputs: netlist.outputs,
+            solution: ChapterSolution {
+                completion_status: ChapterCompletionStatus::InProgress,
+                blocks: netlist.blocks,
+                wires: netlist.wires,
+            },
+            blocks: netlist.block_texts,
+            nets: netlist.nets,
+        })
+    }
+
+    /// Writes the solution in the text format,
+    /// with the names, the template parameters and the nets that it was read with
+    pub fn to_text(&self) -> String {
+        write_text(
+            &self.inputs,
+            &self.outputs,
+            &self.solution,
+            &self.blocks,
+            &self.nets,
+        )
+    }
+}
+
+impl ChapterSolution {
+    /// Writes the solution in the text format, for the chapter ports `inputs` and `outputs`.
+    /// The blocks are named after their lable, or their description if they have none.
+    pub fn to_text(&self, inputs: &[BlockCable], outputs: &[BlockCable]) -> String {
+        let mut used: HashSet<String> = (inputs.iter().chain(outputs))
+            .map(|port| port.lable.clone())
+            .collect();
+        let mut blocks = Vec::new();
+        for block in &self.blocks {
+            let shape = &block.shape;
+            let base = [&shape.lable, &shape.description.name]
+                .into_iter()
+                .find(|name| !name.is_empty())
+                .map_or("block".into(), String::clone);
+            let mut name = base.clone();
+            let mut count = 1;
+            while !used.insert(name.clone()) {
+                name = format!("{base}_{count}");
+                count += 1;
+            }
+            blocks.push(BlockText {
+                name,
+                ..Default::default()
+            });
+        }
+        write_text(inputs, outputs, self, &blocks, &[])
+    }
+}
+
+/// Writes a solution with the given names of its blocks and its nets.
+/// The blocks without a [`BlockText`] are named after their index.
+fn write_text(
+    inputs: &[BlockCable],
+    outputs: &[BlockCable],
+    solution: &ChapterSolution,
+    blocks: &[BlockText],
+    nets: &[NetText],
+) -> String {
+    let mut text = String::new();
+    // The sides are only written when they are not the default one
+    let cables = |cables: &[BlockCable], widths: &[Option<String>], sides: &[Side], default| {
+        let cables: Vec<String> = (cables.iter().enumerate())
+            .map(|(i, cable)| {
+                let cable = cable_text(cable, widths.get(i).and_then(Option::as_deref));
+                match sides.get(i).copied().unwrap_or(default) {
+                    side if side == default => cable,
+                    side => format!("{cable} {}", side_text(side)),
+                }
+            })
+            .collect();
+        cables.join(", ")
+    };
+    if !inputs.is_empty() {
+        text += &format!("input {}\n", cables(inputs, &[], &[], Side::Left));
+    }
+    if !outputs.is_empty() {
+        text += &format!("output {}\n", cables(outputs, &[], &[], Side::Right));
+    }
+
+    let names: Vec<String> = (0..solution.blocks.len())
+        .map(|block| {
+            blocks
+                .get(block)
+                .map_or(format!("block{block}"), |b| b.name.clone())
+        })
+        .collect();
+    if !solution.blocks.is_empty() {
+        text.push('\n');
+    }
+    let default = BlockText::default();
+    for (index, (block, name)) in solution.blocks.iter().zip(&names).enumerate() {
+        let block_text = blocks.get(index).unwrap_or(&default);
+        let shape = &block.shape;
+        let parameters = match block_text.parameters.is_empty() {
+            true => String::new(),
+            false => {
+                let parameters: Vec<String> = (block_text.parameters.iter())
+                    .map(|(parameter, value)| format!("{}={value}", name_text(parameter)))
+                    .collect();
+                format!("<{}>", parameters.join(", "))
+            }
+        };
+        text += &format!(
+            "block {}: {}{parameters}({}) -> ({})",
+            name_text(name),
+            name_text(&shape.description.name),
+            cables(
+                &shape.inputs,
+                &block_text.input_widths,
+                &shape.layout.inputs,
+                Side::Left
+            ),
+            cables(
+                &shape.outputs,
+                &block_text.output_widths,
+                &shape.layout.outputs,
+                Side::Right
+            ),
+        );
+        let [x, y] = <[i32; 2]>::from(block.pos);
+        if (x, y) != (0, 0) {
+            text += &format!(" at {x} {y}");
+        }
+        if block.orientation.rotation != Rotation::R0 {
+            text += &format!(" turn {}", block.orientation.rotation.degrees());
+        }
+        if block.orientation.mirrored {
+            text += " mirrored";
+        }
+        if shape.lable != *name {
+            text += &format!(" lable {}", quote(&shape.lable));
+        }
+        text.push('\n');
+    }
+
+    // The sinks of each source, in the order of the wires
+    let mut sources: Vec<(PinRef, Vec<PinRef>)> = Vec::new();
+    for wire in &solution.wires {
+        match sources
+            .iter_mut()
+            .find(|(source, _)| *source == wire.source)
+        {
+            Some((_, sinks)) => sinks.push(wire.sink),
+            None => sources.push((wire.source, vec![wire.sink])),
+        }
+    }
+    let pin = |pin: PinRef, output: bool| -> String {
+        match pin {
+            PinRef::Chapter { port } => {
+                let ports = if output { inputs } else { outputs };
+                match ports.get(port) {
+                    Some(port) => name_text(&port.lable),
+                    None => format!("{}{port}", if output { "in" } else { "out" }),
+                }
+            }
+            PinRef::Block { block, pin } => {
+                let name = names
+                    .get(block)
+                    .map_or(format!("{block}"), |n| name_text(n));
+                let cables = (solution.blocks.get(block)).map(|b| match output {
+                    true => &b.shape.outputs,
+                    false => &b.shape.inputs,
+                });
+                let lable = cables.and_then(|c| Some(&c.get(pin)?.lable));
+                let unique = |lable: &String| {
+                    !lable.is_empty()
+                        && cables
+                            .is_some_and(|c| c.iter().filter(|c| c.lable == *lable).count() == 1)
+                };
+                match lable.filter(|lable| unique(lable)) {
+                    Some(lable) => format!("{name}.{}", name_text(lable)),
+                    None => format!("{name}.{pin}"),
+                }
+            }
+        }
+    };
+
+    if !nets.is_empty() {
+        text.push('\n');
+    }
+    for net in nets {
+        text += &format!("net {} = {}\n", name_text(&net.name), pin(net.source, true));
+    }
+
+    if !sources.is_empty() {
+        text.push('\n');
+    }
+    for (source, sinks) in sources {
+        let sinks: Vec<String> = sinks.into_iter().map(|sink| pin(sink, false)).collect();
+        // The sources that have a net are written with its name
+        let source = match nets.iter().find(|net| net.source == source) {
+            Some(net) => name_text(&net.name),
+            None => pin(source, true),
+        };
+        text += &format!("{} = {source}\n", sinks.join(", "));
+    }
+    text
+}
+
+const KEYWORDS: &[&str] = &[
+    "input", "output", "block", "net", "at", "turn", "mirrored", "lable",
+];
+
+/// The name can be written without quotes
+fn is_word(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !KEYWORDS.contains(&name)
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn name_text(name: &str) -> String {
+    match is_word(name) {
+        true => name.into(),
+        false => quote(name),
+    }
+}
+
+fn side_text(side: Side) -> &'static str {
+    match side {
+        Side::Left => "left",
+        Side::Top => "top",
+        Side::Right => "right",
+        Side::Bottom => "bottom",
+    }
+}
+
+/// A cable, with the template parameter that gives its width if there is one
+fn cable_text(cable: &BlockCable, width: Option<&str>) -> String {
+    let name = match cable.lable.as_str() {
+        "" => "_".into(),
+        lable => name_text(lable),
+    };
+    match (width, cable.wires) {
+        (Some(parameter), _) => format!("{name}[{parameter}]"),
+        (None, 1) => name,
+        (None, wires) => format!("{name}[{wires}]"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Number(i64),
+    /// A text between quotes
+    Text(String),
+    Symbol(char),
+    Arrow,
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, TextErrorKind> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '#' => break,
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut word = String::new();
+                while let Some(&c) = chars
+                    .peek()
+                    .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+                {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = String::from(c);
+                chars.next();
+                if c == '-' && chars.peek() == Some(&'>') {
+                    chars.next();
+                    tokens.push(Token::Arrow);
+                    continue;
+                }
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    number.push(c);
+                    chars.next();
+                }
+                let number = number
+                    .parse()
+                    .map_err(|_| TextErrorKind::Expected("a number"))?;
+                tokens.push(Token::Number(number));
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(TextErrorKind::Expected("a closing quote")),
+                        Some('"') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c => {
+                chars.next();
+                tokens.push(Token::Symbol(c));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A line of the text
+enum Statement {
+    Ports {
+        output: bool,
+        cables: Vec<BlockCable>,
+    },
+    Block {
+        text: BlockText,
+        block: Block,
+    },
+    Net {
+        name: String,
+        source: PinName,
+    },
+    Connect {
+        sinks: Vec<PinName>,
+        source: PinName,
+    },
+}
+
+/// A port or a net, or a pin of a block with `block.pin`
+#[derive(Clone)]
+struct PinName {
+    name: String,
+    pin: Option<PinKey>,
+}
+
+#[derive(Clone)]
+enum PinKey {
+    Index(usize),
+    Lable(String),
+}
+
+struct Tokens {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+/// The cables of a list, with the parameter that gives the width of each one
+/// and the side of each one, if they are written
+#[derive(Default)]
+struct CableList {
+    cables: Vec<BlockCable>,
+    widths: Vec<Option<String>>,
+    sides: Vec<Option<Side>>,
+}
+
+impl CableList {
+    /// The sides of the pins, empty if they all have the default side
+    fn layout(&self, default: Side) -> Vec<Side> {
+        match self.sides.iter().all(Option::is_none) {
+            true => vec![],
+            false => (self.sides.iter())
+                .map(|side| side.unwrap_or(default))
+                .collect(),
+        }
+    }
+}
+
+impl Tokens {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &Token, expected: &'static str) -> Result<(), TextErrorKind> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(TextErrorKind::Expected(expected)),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.eat(&Token::Word(keyword.into()))
+    }
+
+    /// A word or a text between quotes
+    fn name(&mut self, expected: &'static str) -> Result<String, TextErrorKind> {
+        match self.peek().cloned() {
+            Some(Token::Word(name) | Token::Text(name)) => {
+                self.next += 1;
+                Ok(name)
+            }
+            _ => Err(TextErrorKind::Expected(expected)),
+        }
+    }
+
+    fn number(&mut self, expected: &'static str) -> Result<i64, TextErrorKind> {
+        match self.peek().cloned() {
+            Some(Token::Number(number)) => {
+                self.next += 1;
+                Ok(number)
+            }
+            _ => Err(TextErrorKind::Expected(expected)),
+        }
+    }
+
+    fn end(&self) -> Result<(), TextErrorKind> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(TextErrorKind::Expected("the end of the line")),
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, TextErrorKind> {
+        let statement = if self.keyword("input") || self.keyword("output") {
+            let output = self.tokens[0] == Token::Word("output".into());
+            let cables = self.cables(&[], false)?.cables;
+            Statement::Ports { output, cables }
+        } else if self.keyword("block") {
+            self.block()?
+        } else if self.keyword("net") {
+            let name = self.name("the name of the net")?;
+            self.expect(&Token::Symbol('='), "`=`")?;
+            let source = self.pin_name()?;
+            Statement::Net { name, source }
+        } else {
+            let mut sinks = vec![self.pin_name()?];
+            while self.eat(&Token::Symbol(',')) {
+                sinks.push(self.pin_name()?);
+            }
+            self.expect(&Token::Symbol('='), "`=` after the sinks")?;
+            let source = self.pin_name()?;
+            Statement::Connect { sinks, source }
+        };
+        self.end()?;
+        Ok(statement)
+    }
+
+    fn block(&mut self) -> Result<Statement, TextErrorKind> {
+        let name = self.name("the name of the block")?;
+        self.expect(&Token::Symbol(':'), "`:` after the name of the block")?;
+        let description = self.name("the description of the block")?;
+
+        let mut parameters = Vec::new();
+        if self.eat(&Token::Symbol('<')) {
+            loop {
+                let parameter = self.name("a template parameter")?;
+                self.expect(&Token::Symbol('='), "`=` after the parameter")?;
+                parameters.push((parameter, self.number("the value of the parameter")?));
+                if !self.eat(&Token::Symbol(',')) {
+                    break;
+                }
+            }
+            self.expect(&Token::Symbol('>'), "`>` after the parameters")?;
+        }
+
+        self.expect(&Token::Symbol('('), "`(` before the inputs")?;
+        let inputs = match self.peek() == Some(&Token::Symbol(')')) {
+            true => CableList::default(),
+            false => self.cables(&parameters, true)?,
+        };
+        self.expect(&Token::Symbol(')'), "`)` after the inputs")?;
+        self.expect(&Token::Arrow, "`->` before the outputs")?;
+        self.expect(&Token::Symbol('('), "`(` before the outputs")?;
+        let outputs = match self.peek() == Some(&Token::Symbol(')')) {
+            true => CableList::default(),
+            false => self.cables(&parameters, true)?,
+        };
+        self.expect(&Token::Symbol(')'), "`)` after the outputs")?;
+
+        let mut pos = [0, 0];
+        if self.keyword("at") {
+            for coordinate in &mut pos {
+                let value = self.number("a coordinate of the position")?;
+                *coordinate =
+                    i32::try_from(value).map_err(|_| TextErrorKind::Expected("a position"))?;
+            }
+        }
+        let mut orientation = Orientation::default();
+        if self.keyword("turn") {
+            let degrees = self.number("the degrees of the rotation")?;
+            orientation.rotation = (u16::try_from(degrees).ok())
+                .and_then(Rotation::from_degrees)
+                .ok_or(TextErrorKind::Expected("0, 90, 180 or 270 degrees"))?;
+        }
+        orientation.mirrored = self.keyword("mirrored");
+        let mut lable = name.clone();
+        if self.keyword("lable") {
+            lable = self.name("the lable of the block")?;
+        }
+
+        let block = Block {
+            shape: BlockShape {
+                description: BlockDescId { name: description },
+                lable,
+                layout: PinLayout {
+                    inputs: inputs.layout(Side::Left),
+                    outputs: outputs.layout(Side::Right),
+                },
+                inputs: inputs.cables,
+                outputs: outputs.cables,
+            },
+            pos: pos.into(),
+            orientation,
+        };
+        let text = BlockText {
+            name,
+            parameters,
+            input_widths: inputs.widths,
+            output_widths: outputs.widths,
+        };
+        Ok(Statement::Block { text, block })
+    }
+
+    /// The cables of a list. Only the pins of the blocks can have a side.
+    fn cables(
+        &mut self,
+        parameters: &[(String, i64)],
+        sides: bool,
+    ) -> Result<CableList, TextErrorKind> {
+        let mut list = CableList::default();
+        loop {
+            let mut lable = self.name("a cable")?;
+            if lable == "_" && self.tokens[self.next - 1] == Token::Word("_".into()) {
+                lable.clear();
+            }
+            let mut wires = 1;
+            let mut width = None;
+            if self.eat(&Token::Symbol('[')) {
+                wires = match self.peek().cloned() {
+                    Some(Token::Word(parameter)) => {
+                        self.next += 1;
+                        let value = (parameters.iter())
+                            .find(|(name, _)| *name == parameter)
+                            .map(|(_, value)| *value)
+                            .ok_or_else(|| TextErrorKind::UnknownParameter(parameter.clone()))?;
+                        width = Some(parameter);
+                        value
+                    }
+                    _ => self.number("the wires of the cable")?,
+                };
+                self.expect(&Token::Symbol(']'), "`]` after the wires")?;
+            }
+            let wires =
+                u8::try_from(wires).map_err(|_| TextErrorKind::Expected("0 to 255 wires"))?;
+            let side = match self.peek() {
+                Some(Token::Word(word)) if sides => {
+                    let side = match word.as_str() {
+                        "left" => Side::Left,
+                        "top" => Side::Top,
+                        "right" => Side::Right,
+                        "bottom" => Side::Bottom,
+                        _ => return Err(TextErrorKind::Expected("left, top, right or bottom")),
+                    };
+                    self.next += 1;
+                    Some(side)
+                }
+                _ => None,
+            };
+            list.cables.push(BlockCable { lable, wires });
+            list.widths.push(width);
+            list.sides.push(side);
+            if !self.eat(&Token::Symbol(',')) {
+                return Ok(list);
+            }
+        }
+    }
+
+    fn pin_name(&mut self) -> Result<PinName, TextErrorKind> {
+        let name = self.name("a port, a net or a block")?;
+        let pin = match self.eat(&Token::Symbol('.')) {
+            false => None,
+            true => match self.peek().cloned() {
+                Some(Token::Number(index)) if index >= 0 => {
+                    self.next += 1;
+                    Some(PinKey::Index(index as usize))
+                }
+                _ => Some(PinKey::Lable(self.name("a pin of the block")?)),
+            },
+        };
+        Ok(PinName { name, pin })
+    }
+}
+
+#[derive(Default)]
+struct Netlist {
+    inputs: Vec<BlockCable>,
+    outputs: Vec<BlockCable>,
+    blocks: Vec<Block>,
+    wires: Vec<Wire>,
+    block_texts: Vec<BlockText>,
+    nets: Vec<NetText>,
+    names: HashMap<String, Named>,
+    /// The sinks that have a source
+    driven: HashSet<PinRef>,
+}
+
+/// What a name of the text is
+enum Named {
+    Input(usize),
+    Output(usize),
+    Block(usize),
+    Net(PinName),
+}
+
+impl Netlist {
+    fn declare(&mut self, statement: &Statement) -> Result<(), TextErrorKind> {
+        let mut declare = |name: &str, named| match self.names.insert(name.into(), named) {
+            None => Ok(()),
+            Some(_) => Err(TextErrorKind::Duplicate(name.into())),
+        };
+        match statement {
+            Statement::Ports { output, cables } => {
+                let first = if *output {
+                    self.outputs.len()
+                } else {
+                    self.inputs.len()
+                };
+                for (index, cable) in cables.iter().enumerate() {
+                    let port = first + index;
+                    let named = if *output {
+                        Named::Output(port)
+                    } else {
+                        Named::Input(port)
+                    };
+                    declare(&cable.lable, named)?;
+                }
+                let ports = if *output {
+                    &mut self.outputs
+                } else {
+                    &mut self.inputs
+                };
+                ports.extend(cables.iter().cloned());
+            }
+            Statement::Block { text, block } => {
+                declare(&text.name, Named::Block(self.blocks.len()))?;
+                self.blocks.push(block.clone());
+                self.block_texts.push(text.clone());
+            }
+            Statement::Net { name, source } => declare(name, Named::Net(source.clone()))?,
+            Statement::Connect { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn connect(&mut self, statement: &Statement) -> Result<(), TextErrorKind> {
+        if let Statement::Net { name, source } = statement {
+            let source = self.source(source, 0)?;
+            let name = name.clone();
+            self.nets.push(NetText { name, source });
+            return Ok(());
+        }
+        let Statement::Connect { sinks, source } = statement else {
+            return Ok(());
+        };
+        let source = self.source(source, 0)?;
+        for sink in sinks {
+            let pin = self.sink(sink)?;
+            if !self.driven.insert(pin) {
+                return Err(TextErrorKind::ManySources(sink.to_string()));
+            }
+            self.wires.push(Wire { source, sink: pin });
+        }
+        Ok(())
+    }
+
+    fn named(&self, name: &str) -> Result<&Named, TextErrorKind> {
+        (self.names.get(name)).ok_or_else(|| TextErrorKind::UnknownName(name.into()))
+    }
+
+    /// Nets can be defined from other nets, `depth` counts them to find loops
+    fn source(&self, name: &PinName, depth: usize) -> Result<PinRef, TextErrorKind> {
+        match (self.named(&name.name)?, &name.pin) {
+            (Named::Input(port), None) => Ok(PinRef::Chapter { port: *port }),
+            (Named::Net(source), None) if depth < self.names.len() => {
+                self.source(source, depth + 1)
+            }
+            (Named::Net(_), None) => Err(TextErrorKind::NetLoop(name.name.clone())),
+            (Named::Block(block), Some(pin)) => {
+                let pin = self.pin(&self.blocks[*block].shape.outputs, name, pin)?;
+                Ok(PinRef::Block { block: *block, pin })
+            }
+            _ => Err(TextErrorKind::Expected(
+                "an input, a net or an output pin as source",
+            )),
+        }
+    }
+
+    fn sink(&self, name: &PinName) -> Result<PinRef, TextErrorKind> {
+        match (self.named(&name.name)?, &name.pin) {
+            (Named::Output(port), None) => Ok(PinRef::Chapter { port: *port }),
+            (Named::Block(block), Some(pin)) => {
+                let pin = self.pin(&self.blocks[*block].shape.inputs, name, pin)?;
+                Ok(PinRef::Block { block: *block, pin })
+            }
+            _ => Err(TextErrorKind::Expected("an output or an input pin as sink")),
+        }
+    }
+
+    fn pin(
+        &self,
+        cables: &[BlockCable],
+        name: &PinName,
+        pin: &PinKey,
+    ) -> Result<usize, TextErrorKind> {
+        let found = match pin {
+            PinKey::Index(index) => Some(*index).filter(|index| *index < cables.len()),
+            PinKey::Lable(lable) => cables.iter().position(|cable| cable.lable == *lable),
+        };
+        found.ok_or_else(|| TextErrorKind::UnknownPin {
+            block: name.name.clone(),
+            pin: match pin {
+                PinKey::Index(index) => index.to_string(),
+                PinKey::Lable(lable) => lable.clone(),
+            },
+        })
+    }
+}
+
+impl fmt::Display for PinName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", name_text(&self.name))?;
+        match &self.pin {
+            None => Ok(()),
+            Some(PinKey::Index(index)) => write!(f, ".{index}"),
+            Some(PinKey::Lable(lable)) => write!(f, ".{}", name_text(lable)),
+        }
+    }
+}
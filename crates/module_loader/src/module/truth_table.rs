@@ -0,0 +1,24 @@
+use std::collections::HashMap;
+
+/// The expected behaviour of a chapter solution
+#[derive(Debug, Clone)]
+pub struct TruthTable {
+    /// How to display a row. `$port` is replaced by the value of the port.
+    pub format: String,
+    pub rows: TruthTableRows,
+    /// With a function, the inputs with more wires are checked with edge cases and random rows
+    /// instead of all their combinations. The verifier has a default if it is `None`.
+    pub max_exhaustive_wires: Option<u32>,
+    /// Number of random rows to check when the inputs have too many wires
+    pub random_rows: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TruthTableRows {
+    /// Name of a function that computes the outputs from the inputs.
+    /// All the combinations of inputs are checked.
+    Function(String),
+    /// Values of the ports on each row.
+    /// The missing inputs are 0, and the missing outputs are not checked.
+    Table(Vec<HashMap<String, u64>>),
+}
@@ -0,0 +1,160 @@
+//! Where the pins of a block are, with the sides of the manifest and the orientation of the block
+
+use digolog_math::*;
+use digolog_module_loader::*;
+use std::path::Path;
+
+const BLOCKS: &str = r##"color = "#fff"
+
+[blocks.Mux]
+lable = "Mux"
+inputs = ["cable<W> a", "cable<W> b", "cable<S> sel"]
+outputs = ["cable<W> out"]
+sides = { sel = "bottom" }
+"##;
+
+const BOOK: &str = r#"allowed_blocks = ["Mux"]
+
+[[chapters]]
+title = "Select"
+inputs = ["a", "b", "sel"]
+outputs = ["r"]
+"#;
+
+fn module() -> Module {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("layout");
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(path.join("blocks")).unwrap();
+    std::fs::create_dir_all(path.join("books")).unwrap();
+    std::fs::write(
+        path.join("module.toml"),
+        "name = \"Layout\"\nrequirements = []\n",
+    )
+    .unwrap();
+    std::fs::write(path.join("blocks/Gates.toml"), BLOCKS).unwrap();
+    std::fs::write(path.join("books/Book.toml"), BOOK).unwrap();
+    Module::from_path(path, "test".into()).unwrap()
+}
+
+/// A Mux of 1 wire, 6 by 3 units: `a` and `b` on the left, `sel` at the bottom, `out` on the right
+fn mux(module: &Module, pos: Vec2<i32>, rotation: Rotation, mirrored: bool) -> Block {
+    let shape = BlockShapeBuilder {
+        desc: &module.blocks["Mux"],
+        values: [("W".to_string(), 1), ("S".to_string(), 1)].into(),
+    }
+    .build()
+    .unwrap();
+    Block {
+        shape,
+        pos,
+        orientation: Orientation { rotation, mirrored },
+    }
+}
+
+#[test]
+fn pins_are_on_the_sides_of_the_manifest() {
+    let module = module();
+    let shape = mux(&module, Vec2::new(0, 0), Rotation::R0, false).shape;
+    assert_eq!(shape.layout.inputs, [Side::Left, Side::Left, Side::Bottom]);
+    assert!(shape.layout.outputs.is_empty());
+    assert_eq!(shape.size(), Vec2::new(6., 3.));
+
+    assert_eq!(shape.pin_offset(false, 0), Some(Vec2::new(0, 1)));
+    assert_eq!(shape.pin_offset(false, 1), Some(Vec2::new(0, 2)));
+    assert_eq!(shape.pin_offset(false, 2), Some(Vec2::new(1, 3)));
+    assert_eq!(shape.pin_offset(true, 0), Some(Vec2::new(6, 1)));
+    assert_eq!(shape.pin_offset(false, 3), None);
+    assert_eq!(shape.pin_offset(true, 1), None);
+}
+
+#[test]
+fn orientations_move_the_corners_of_a_shape() {
+    let size = Vec2::new(6, 3);
+    let turned = |rotation, mirrored, point: [i32; 2]| {
+        let orientation = Orientation { rotation, mirrored };
+        <[i32; 2]>::from(orientation.apply(point.into(), size))
+    };
+    assert_eq!(turned(Rotation::R0, false, [0, 1]), [0, 1]);
+    assert_eq!(turned(Rotation::R90, false, [0, 1]), [2, 0]);
+    assert_eq!(turned(Rotation::R180, false, [0, 1]), [6, 2]);
+    assert_eq!(turned(Rotation::R270, false, [0, 1]), [1, 6]);
+    // Mirrored first, then turned
+    assert_eq!(turned(Rotation::R0, true, [0, 1]), [6, 1]);
+    assert_eq!(turned(Rotation::R90, true, [0, 1]), [2, 6]);
+    assert_eq!(turned(Rotation::R90, false, [6, 3]), [0, 6]);
+
+    assert_eq!(Rotation::try_from(270), Ok(Rotation::R270));
+    assert!(Rotation::try_from(45).is_err());
+}
+
+#[test]
+fn placed_pins_follow_the_orientation() {
+    let module = module();
+    let pos = Vec2::new(10, 20);
+
+    let block = mux(&module, pos, Rotation::R90, false);
+    assert_eq!(block.size(), Vec2::new(3, 6));
+    assert_eq!(block.pin_pos(false, 0), Some(Vec2::new(12, 20)));
+    assert_eq!(block.pin_pos(false, 2), Some(Vec2::new(10, 21)));
+    assert_eq!(block.pin_pos(true, 0), Some(Vec2::new(12, 26)));
+    assert_eq!(block.pin_side(false, 0), Side::Top);
+    assert_eq!(block.pin_side(false, 2), Side::Left);
+    assert_eq!(block.pin_side(true, 0), Side::Bottom);
+
+    let block = mux(&module, pos, Rotation::R0, true);
+    assert_eq!(block.size(), Vec2::new(6, 3));
+    assert_eq!(block.pin_pos(false, 0), Some(Vec2::new(16, 21)));
+    assert_eq!(block.pin_pos(true, 0), Some(Vec2::new(10, 21)));
+    assert_eq!(block.pin_side(false, 0), Side::Right);
+    assert_eq!(block.pin_side(false, 2), Side::Bottom);
+    assert_eq!(block.pin_side(true, 0), Side::Left);
+
+    let block = mux(&module, pos, Rotation::R90, true);
+    assert_eq!(block.pin_side(true, 0), Side::Top);
+    assert_eq!(block.pin_pos(false, 3), None);
+}
+
+#[test]
+fn pins_stick_out_of_the_block() {
+    let module = module();
+    let block = mux(&module, Vec2::new(10, 20), Rotation::R0, false);
+    let hit = |x, y| block.hit(Vec2::new(x, y));
+
+    assert_eq!(hit(10.4, 21.3), Some(BlockHit::Input(0)));
+    assert_eq!(hit(9.5, 22.), Some(BlockHit::Input(1)));
+    assert_eq!(hit(11., 23.5), Some(BlockHit::Input(2)));
+    assert_eq!(hit(16.5, 21.), Some(BlockHit::Output(0)));
+    assert_eq!(hit(13., 21.5), Some(BlockHit::Body));
+    assert_eq!(hit(9.4, 21.), None);
+    assert_eq!(hit(16.6, 21.), None);
+    assert_eq!(hit(13., 23.6), None);
+
+    // The turned block is 3 wide and 6 high
+    let block = mux(&module, Vec2::new(10, 20), Rotation::R90, false);
+    assert_eq!(block.hit(Vec2::new(12., 19.6)), Some(BlockHit::Input(0)));
+    assert_eq!(block.hit(Vec2::new(12.5, 25.)), Some(BlockHit::Body));
+    assert_eq!(block.hit(Vec2::new(15., 21.)), None);
+}
+
+#[test]
+fn the_last_placed_block_is_on_top() {
+    let module = module();
+    let chapter = module.find_chapter("Select").unwrap();
+    let mut solution = chapter.new_solution();
+    solution
+        .blocks
+        .push(mux(&module, Vec2::new(0, 0), Rotation::R0, false));
+    solution
+        .blocks
+        .push(mux(&module, Vec2::new(4, 1), Rotation::R0, false));
+
+    assert_eq!(solution.hit(Vec2::new(1., 1.)), Some((0, BlockHit::Body)));
+    assert_eq!(solution.hit(Vec2::new(5., 2.)), Some((1, BlockHit::Body)));
+    // The output of the first block is under the second block
+    assert_eq!(solution.hit(Vec2::new(6., 1.)), Some((1, BlockHit::Body)));
+    assert_eq!(
+        solution.hit(Vec2::new(4., 2.)),
+        Some((1, BlockHit::Input(0)))
+    );
+    assert_eq!(solution.hit(Vec2::new(20., 20.)), None);
+}
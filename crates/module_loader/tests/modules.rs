@@ -0,0 +1,223 @@
+//! Modules loaded from their folder, and the errors of invalid modules
+
+use digolog_module_loader::*;
+use std::path::{Path, PathBuf};
+
+const MODULE: &str = r#"name = "Test"
+requirements = []
+"#;
+
+const BLOCKS: &str = r##"color = "#fff"
+
+[blocks.And]
+lable = "And"
+inputs = ["bundle<N, W> in"]
+outputs = ["cable<W>"]
+gate = "and"
+"##;
+
+const BOOK: &str = r#"allowed_blocks = ["And"]
+
+[[chapters]]
+title = "Both"
+inputs = ["a", "b"]
+outputs = ["r"]
+"#;
+
+/// Writes a module with a block group and a book in a new folder
+fn write_module(name: &str, blocks: &str, book: &str) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(path.join("blocks")).unwrap();
+    std::fs::create_dir_all(path.join("books")).unwrap();
+    std::fs::write(path.join("module.toml"), MODULE).unwrap();
+    std::fs::write(path.join("blocks/Gates.toml"), blocks).unwrap();
+    std::fs::write(path.join("books/Book.toml"), book).unwrap();
+    path
+}
+
+fn load_error(name: &str, blocks: &str, book: &str) -> ModuleError {
+    match Module::from_path(write_module(name, blocks, book), "test".into()) {
+        Ok(_) => panic!("the module {name:?} is loaded"),
+        Err(error) => error,
+    }
+}
+
+#[test]
+fn a_valid_module_is_loaded() {
+    let module = Module::from_path(write_module("valid", BLOCKS, BOOK), "test".into()).unwrap();
+    let chapter = module.find_chapter("Both").unwrap();
+    assert_eq!(chapter.inputs.len(), 2);
+    assert_eq!(chapter.allowed_blocks.len(), 0);
+    assert!(module.blocks["And"].logic.is_some());
+}
+
+#[test]
+fn a_missing_module_is_an_error() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("missing");
+    let error = Module::from_path(path, "test".into()).err().unwrap();
+    assert!(matches!(error, ModuleError::Io { .. }), "{error}");
+}
+
+#[test]
+fn invalid_manifests_are_errors() {
+    let error = load_error("invalid_toml", "color = ", BOOK);
+    assert!(matches!(error, ModuleError::Manifest { .. }), "{error}");
+
+    let pin = BLOCKS.replace("cable<W>", "cable<W");
+    let error = load_error("invalid_pin", &pin, BOOK);
+    assert!(matches!(error, ModuleError::InvalidPin { .. }), "{error}");
+
+    let port = BOOK.replace("\"a\"", "\"a[two]\"");
+    let error = load_error("invalid_port", BLOCKS, &port);
+    assert!(
+        matches!(&error, ModuleError::InvalidPort { port, .. } if port == "a[two]"),
+        "{error}"
+    );
+}
+
+#[test]
+fn unknown_allowed_blocks_are_errors() {
+    let book = BOOK.replace("[\"And\"]", "[\"Nand\"]");
+    let error = load_error("unknown_block", BLOCKS, &book);
+    assert!(
+        matches!(&error, ModuleError::UnknownBlock { allowed_in, block }
+            if allowed_in == "Book" && block == "Nand"),
+        "{error}"
+    );
+}
+
+#[test]
+fn allowed_blocks_can_have_template_values() {
+    let book = BOOK.replace("[\"And\"]", "[\"And<N=3, W=1>\"]");
+    let path = write_module("template_values", BLOCKS, &book);
+    let module = Module::from_path(path, "test".into()).unwrap();
+    let subset = &module.iter_books().next().unwrap().allowed_blocks[0];
+    assert_eq!(subset.values.len(), 2);
+    assert_eq!(subset.values["N"], 3);
+    assert_eq!(subset.values["W"], 1);
+
+    let and = &subset.block_desc;
+    let shape = |inputs: usize, wires: u8| BlockShape {
+        description: and.id.clone(),
+        lable: and.lable.clone(),
+        inputs: (0..inputs)
+            .map(|i| BlockCable {
+                lable: format!("in{i}"),
+                wires,
+            })
+            .collect(),
+        outputs: vec![BlockCable {
+            lable: String::new(),
+            wires,
+        }],
+        layout: PinLayout::default(),
+    };
+    assert!(subset.allows(&shape(3, 1)));
+    assert!(
+        !subset.allows(&shape(2, 1)),
+        "the bundle has the wrong size"
+    );
+    assert!(
+        !subset.allows(&shape(3, 4)),
+        "the cables have the wrong width"
+    );
+    let mut mismatched = shape(3, 1);
+    mismatched.outputs[0].wires = 2;
+    assert!(
+        !subset.allows(&mismatched),
+        "the shape does not follow the description"
+    );
+
+    let values = subset.new_values().unwrap();
+    assert_eq!((values["N"], values["W"]), (3, 1));
+}
+
+#[test]
+fn invalid_template_values_are_errors() {
+    for allowed in [
+        "And<M=3>",
+        "And<N=three>",
+        "And<N>",
+        "Gates<N=3>",
+        "And<N=3",
+    ] {
+        let book = BOOK.replace("\"And\"", &format!("{allowed:?}"));
+        let error = load_error("invalid_template_values", BLOCKS, &book);
+        assert!(
+            matches!(&error, ModuleError::InvalidTemplateValues { block, .. } if block == allowed),
+            "{allowed}: {error}"
+        );
+    }
+}
+
+#[test]
+fn a_missing_reference_solution_is_an_error() {
+    let book = format!("{BOOK}reference_solution = \"solutions/Both.txt\"\n");
+    let error = load_error("missing_reference", BLOCKS, &book);
+    assert!(
+        matches!(&error, ModuleError::InvalidReferenceSolution { chapter, .. } if chapter == "Both"),
+        "{error}"
+    );
+}
+
+#[test]
+fn invalid_sequence_tests_are_errors() {
+    let book = format!("{BOOK}test.steps = [{{ a = 1, c = 0 }}]\n");
+    let error = load_error("unknown_test_port", BLOCKS, &book);
+    assert!(
+        matches!(&error, ModuleError::UnknownTestPort { port, .. } if port == "c"),
+        "{error}"
+    );
+
+    let book = format!("{BOOK}test.steps = [{{ a = 1, r = \"?\" }}]\n");
+    let error = load_error("invalid_test_value", BLOCKS, &book);
+    assert!(
+        matches!(&error, ModuleError::InvalidTestValue { value, .. } if value == "?"),
+        "{error}"
+    );
+}
+
+#[test]
+fn template_values_that_do_not_fit_in_a_pin_conflict() {
+    let module = Module::from_path(write_module("overflow", BLOCKS, BOOK), "test".into()).unwrap();
+    let and = &module.blocks["And"];
+    let shape = |name: &str, value: i64| and.shape(&[(name.to_string(), value)].into());
+
+    let conflict = |name: &str, values| {
+        Some(TemplateConflict {
+            name: name.into(),
+            values,
+        })
+    };
+    assert_eq!(shape("W", 300).err(), conflict("W", [300, 255]));
+    assert_eq!(shape("N", -1).err(), conflict("N", [-1, 0]));
+    assert_eq!(shape("N", 256).err(), conflict("N", [256, 255]));
+
+    let shape = shape("W", 255).unwrap();
+    assert_eq!(shape.outputs[0].wires, 255);
+    assert_eq!(shape.inputs.len(), DEFAULT_BUNDLE_CABLES as usize);
+}
+
+#[test]
+fn sequence_tests_have_a_clock_and_dont_cares() {
+    let book = BOOK.replace("[\"a\", \"b\"]", "[\"a\", \"clk\"]")
+        + "test.clock = \"clk\"\n"
+        + "test.steps = [{ a = 1, r = 0 }, { a = \"-\", r = \"x\" }, { r = 1 }]\n";
+    let path = write_module("sequence_test", BLOCKS, &book);
+    let module = Module::from_path(path, "test".into()).unwrap();
+    let test = module.find_chapter("Both").unwrap().test.as_ref().unwrap();
+    assert_eq!(test.clock, Some(1));
+    let step = |inputs: &[Option<u64>], expected| TestStep {
+        inputs: inputs.to_vec(),
+        expected: vec![expected],
+    };
+    assert_eq!(
+        test.steps,
+        [
+            step(&[Some(1), None], Some(0)),
+            step(&[None, None], None),
+            step(&[None, None], Some(1)),
+        ]
+    );
+}
@@ -0,0 +1,135 @@
+//! Shapes of blocks built from their description, and their size on the grid
+
+use digolog_math::*;
+use digolog_module_loader::*;
+use std::path::Path;
+
+const BLOCKS: &str = r##"color = "#fff"
+
+[blocks.Add]
+lable = "Add"
+inputs = ["cable<W> a", "cable<W> b"]
+outputs = ["cable<W> sum"]
+
+[blocks.And]
+lable = "And"
+inputs = ["bundle<N, W> in"]
+outputs = ["cable<W>"]
+
+[blocks.Decoder]
+lable = "Dec"
+inputs = ["cable<C>"]
+outputs = ["bundle<B, 1>"]
+constant.C = "B:bits()"
+"##;
+
+fn blocks() -> ModuleBlocks {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("shapes");
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(path.join("blocks")).unwrap();
+    std::fs::create_dir_all(path.join("books")).unwrap();
+    std::fs::write(
+        path.join("module.toml"),
+        "name = \"Shapes\"\nrequirements = []\n",
+    )
+    .unwrap();
+    std::fs::write(path.join("blocks/Gates.toml"), BLOCKS).unwrap();
+    Module::from_path(path, "test".into()).unwrap().blocks
+}
+
+fn build(
+    blocks: &ModuleBlocks,
+    name: &str,
+    values: &[(&str, i64)],
+) -> Result<BlockShape, BlockShapeError> {
+    BlockShapeBuilder {
+        desc: &blocks[name],
+        values: (values.iter())
+            .map(|&(name, value)| (name.to_string(), value))
+            .collect(),
+    }
+    .build()
+}
+
+#[test]
+fn blocks_are_wide_enough_for_their_lables() {
+    let blocks = blocks();
+    // "Add", "a" and "sum" are 7 characters, 2.45 units and a unit on each side
+    let add = build(&blocks, "Add", &[("W", 8)]).unwrap();
+    assert_eq!(add.size(), Vec2::new(5., 3.));
+
+    let mut alu = add.clone();
+    alu.lable = "Arithmetic Logic Unit".into();
+    assert_eq!(alu.size(), Vec2::new(11., 3.));
+
+    // Without lables, a block is not narrower than 3 units
+    let mut empty = add;
+    empty.lable.clear();
+    for cable in empty.inputs.iter_mut().chain(&mut empty.outputs) {
+        cable.lable.clear();
+    }
+    assert_eq!(empty.size(), Vec2::new(3., 3.));
+}
+
+#[test]
+fn blocks_have_a_row_for_each_pin() {
+    let blocks = blocks();
+    let and = build(&blocks, "And", &[("N", 5), ("W", 1)]).unwrap();
+    assert_eq!(and.inputs.len(), 5);
+    assert_eq!(and.size(), Vec2::new(5., 6.));
+
+    // The output still needs a row
+    let and = build(&blocks, "And", &[("N", 0), ("W", 1)]).unwrap();
+    assert_eq!(and.size(), Vec2::new(4., 2.));
+
+    let decoder = build(&blocks, "Decoder", &[("B", 8)]).unwrap();
+    assert_eq!(decoder.inputs[0].wires, 3);
+    assert_eq!(decoder.size(), Vec2::new(4., 9.));
+}
+
+#[test]
+fn equal_shapes_have_the_same_id() {
+    let blocks = blocks();
+    let add = |wires| build(&blocks, "Add", &[("W", wires)]).unwrap();
+    assert!(add(8).id() == add(8).id());
+    assert!(add(8).id() != add(4).id());
+
+    let mut renamed = add(8);
+    renamed.inputs[1].lable = "c".into();
+    assert!(add(8).id() != renamed.id(), "the lables are drawn too");
+}
+
+#[test]
+fn every_cable_needs_a_valid_width() {
+    let blocks = blocks();
+    assert_eq!(
+        build(&blocks, "Add", &[]).err(),
+        Some(BlockShapeError::MissingValue("W".into()))
+    );
+    assert_eq!(
+        build(&blocks, "And", &[("W", 1)]).err(),
+        Some(BlockShapeError::MissingValue("N".into()))
+    );
+
+    let invalid = |name: &str, value| {
+        Some(BlockShapeError::InvalidValue {
+            name: name.into(),
+            value,
+        })
+    };
+    assert_eq!(build(&blocks, "Add", &[("W", 0)]).err(), invalid("W", 0));
+    assert_eq!(
+        build(&blocks, "Add", &[("W", 256)]).err(),
+        invalid("W", 256)
+    );
+    assert_eq!(
+        build(&blocks, "And", &[("N", -1), ("W", 1)]).err(),
+        invalid("N", -1)
+    );
+
+    let Err(BlockShapeError::Conflict(conflict)) = build(&blocks, "Decoder", &[("B", 4), ("C", 3)])
+    else {
+        panic!("4 cables are selected with 2 wires");
+    };
+    assert_eq!(conflict.name, "C");
+}
@@ -0,0 +1,158 @@
+//! Solutions written in the text format, read and written back
+
+use digolog_module_loader::*;
+
+/// The example of the documentation of the format
+const ADDER: &str = r#"# A 2 bit adder made of full adders
+input a[2], b[2]
+output sum[3]
+
+block split_a: Split<W=2>(in[W]) -> (_, _) at 2 0
+block split_b: Split<W=2>(in[W]) -> (_, _) at 2 4
+block fa0: "Full Adder"(a, b, cin) -> (s, cout) at 6 0
+block fa1: "Full Adder"(a, b, cin top) -> (s, cout) at 6 4
+block join: Join(_, _, _) -> (out[3]) at 10 2 lable "Join sum"
+
+split_a.in = a
+split_b.in = b
+fa0.a = split_a.0
+fa0.b = split_b.0
+fa1.a = split_a.1
+fa1.b = split_b.1
+net carry = fa0.cout
+fa1.cin, join.2 = carry
+join.0 = fa0.s
+join.1 = fa1.s
+sum = join.out
+"#;
+
+fn parse(text: &str) -> SolutionText {
+    SolutionText::parse(text).unwrap_or_else(|error| panic!("{error}\n{text}"))
+}
+
+/// Everything that the text says about a solution
+fn assert_same(a: &SolutionText, b: &SolutionText) {
+    assert_eq!(a.inputs, b.inputs);
+    assert_eq!(a.outputs, b.outputs);
+    assert_eq!(a.blocks, b.blocks);
+    assert_eq!(a.nets, b.nets);
+    assert_eq!(a.solution.wires, b.solution.wires);
+    assert_eq!(a.solution.blocks.len(), b.solution.blocks.len());
+    for (a, b) in a.solution.blocks.iter().zip(&b.solution.blocks) {
+        assert_eq!(a.shape.description, b.shape.description);
+        assert_eq!(a.shape.lable, b.shape.lable);
+        assert_eq!(a.shape.inputs, b.shape.inputs);
+        assert_eq!(a.shape.outputs, b.shape.outputs);
+        assert_eq!(a.shape.layout, b.shape.layout);
+        assert_eq!(a.pos, b.pos);
+        assert_eq!(a.orientation, b.orientation);
+    }
+}
+
+#[test]
+fn the_example_is_written_back() {
+    let parsed = parse(ADDER);
+    let layout = &parsed.solution.blocks[3].shape.layout;
+    assert_eq!(layout.inputs, [Side::Left, Side::Left, Side::Top]);
+    assert!(layout.outputs.is_empty());
+    let text = parsed.to_text();
+    assert!(text.contains("block split_a: Split<W=2>(in[W]) -> (_, _) at 2 0\n"));
+    assert!(text.contains("block join: Join(_, _, _) -> (out[3]) at 10 2 lable \"Join sum\"\n"));
+    assert!(text.contains("block fa1: \"Full Adder\"(a, b, cin top) -> (s, cout) at 6 4\n"));
+    assert!(text.contains("net carry = fa0.cout\n"));
+    assert!(text.contains("fa1.cin, join.2 = carry\n"));
+
+    let reparsed = parse(&text);
+    assert_same(&parsed, &reparsed);
+    assert_eq!(reparsed.to_text(), text);
+}
+
+#[test]
+fn solutions_without_text_are_named_after_their_lables() {
+    let parsed = parse(
+        r#"
+input a
+output r
+block "a b": Not(_) -> (_) lable "a b"
+block n: Not(_ top) -> (_ bottom) turn 90 mirrored
+"a b".0 = a
+n.0 = "a b".0
+r = n.0
+"#,
+    );
+    let text = parsed.solution.to_text(&parsed.inputs, &parsed.outputs);
+    assert!(text.contains("block \"a b\": Not(_) -> (_)\n"));
+    assert!(text.contains("block n: Not(_ top) -> (_ bottom) turn 90 mirrored\n"));
+
+    let reparsed = parse(&text);
+    assert_same(&parsed, &reparsed);
+}
+
+#[test]
+fn errors_are_on_their_line() {
+    let error = |text: &str| SolutionText::parse(text).err().unwrap();
+    let header = "input a, b\noutput r\nblock x: And(_, _) -> (_)\n";
+
+    assert_eq!(
+        error(&format!("{header}block y: Not(_[N]) -> (_)")),
+        TextError {
+            line: 4,
+            kind: TextErrorKind::UnknownParameter("N".into()),
+        }
+    );
+    assert_eq!(
+        error(&format!("{header}\nblock x: Not(_) -> (_)")),
+        TextError {
+            line: 5,
+            kind: TextErrorKind::Duplicate("x".into()),
+        }
+    );
+    assert_eq!(
+        error(&format!("{header}x.0 = c")),
+        TextError {
+            line: 4,
+            kind: TextErrorKind::UnknownName("c".into()),
+        }
+    );
+    assert_eq!(
+        error(&format!("{header}x.2 = a")),
+        TextError {
+            line: 4,
+            kind: TextErrorKind::UnknownPin {
+                block: "x".into(),
+                pin: "2".into(),
+            },
+        }
+    );
+    assert_eq!(
+        error(&format!("{header}x.0 = a\nx.1 = b\nx.0 = b")),
+        TextError {
+            line: 6,
+            kind: TextErrorKind::ManySources("x.0".into()),
+        }
+    );
+    let net_loop = error(&format!("{header}net p = q\nnet q = p"));
+    assert_eq!(net_loop.line, 4);
+    assert!(matches!(net_loop.kind, TextErrorKind::NetLoop(_)));
+    assert_eq!(
+        error(&format!("{header}block y: Not(_ up) -> (_)")),
+        TextError {
+            line: 4,
+            kind: TextErrorKind::Expected("left, top, right or bottom"),
+        }
+    );
+    assert_eq!(
+        error("input a top"),
+        TextError {
+            line: 1,
+            kind: TextErrorKind::Expected("the end of the line"),
+        }
+    );
+    assert_eq!(
+        error(&format!("{header}block y: Not(_) -> (_) at 1")),
+        TextError {
+            line: 4,
+            kind: TextErrorKind::Expected("a coordinate of the position"),
+        }
+    );
+}